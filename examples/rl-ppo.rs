@@ -1,7 +1,7 @@
 //! Implements the reinforcement learning algorithm Proximal Policy Optimization (PPO) on random data.
 
 use dfdx::prelude::*;
-use rand::{rngs::StdRng, Rng, SeedableRng};
+use rand::{rngs::StdRng, SeedableRng};
 use std::time::Instant;
 
 const STATE_SIZE: usize = 4;
@@ -17,7 +17,6 @@ fn main() {
     let mut rng = StdRng::seed_from_u64(0);
 
     let state: Tensor2D<64, STATE_SIZE> = Tensor2D::randn(&mut rng);
-    let action: [usize; 64] = [(); 64].map(|_| rng.gen_range(0..ACTION_SIZE));
     let advantage: Tensor1D<64> = Tensor1D::randn(&mut rng);
 
     // initiliaze model - all weights are 0s
@@ -26,6 +25,11 @@ fn main() {
 
     let target_pi_net: PolicyNetwork = pi_net.clone();
 
+    // sample actions from the target policy
+    let action: [usize; 64] = target_pi_net
+        .forward(state.clone())
+        .sample_categorical_logits(&mut rng);
+
     let mut sgd = Sgd::new(SgdConfig {
         lr: 1e-1,
         momentum: Some(Momentum::Nesterov(0.9)),
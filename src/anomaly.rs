@@ -0,0 +1,220 @@
+//! Anomaly detection, which finds the first operation that produces a NaN or infinity.
+//!
+//! Enable it with [set_detect_anomaly()]. While enabled (on the current thread):
+//! 1. The output of every operation recorded on an [crate::gradients::OwnedTape] is checked
+//!    right after the operation.
+//! 2. Every gradient written by a backward operation is checked right after it runs.
+//!
+//! The first non-finite value **panics** with the name of the operation, the shape of the
+//! tensor and the index of the value, and a backtrace of where the operation was called.
+//!
+//! ```rust,should_panic
+//! # use dfdx::prelude::*;
+//! set_detect_anomaly(true);
+//! let x = tensor([1.0, 0.0, -1.0]);
+//! // panics with "Anomaly detected in the forward of `ln`: output of shape [3] has -inf at index [1]"
+//! let y = x.trace().ln().sum();
+//! ```
+//!
+//! This is slow, since a backtrace is captured for every operation, so it should only
+//! be enabled while debugging. Operations on tensors without a tape aren't checked.
+
+use std::any::Any;
+use std::backtrace::Backtrace;
+use std::cell::Cell;
+
+use crate::arrays::{CountElements, HasShape};
+use crate::devices::Device;
+use crate::gradcheck::unravel_index;
+
+thread_local! {
+    static DETECT_ANOMALY: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Enables or disables anomaly detection on the current thread. See [crate::anomaly].
+pub fn set_detect_anomaly(enabled: bool) {
+    DETECT_ANOMALY.with(|d| d.set(enabled));
+}
+
+/// Whether anomaly detection is enabled on the current thread.
+pub fn is_anomaly_enabled() -> bool {
+    DETECT_ANOMALY.with(|d| d.get())
+}
+
+/// An operation recorded on a tape while anomaly detection was enabled.
+#[derive(Debug)]
+pub(crate) struct RecordedOp {
+    name: &'static str,
+    backtrace: Backtrace,
+}
+
+impl RecordedOp {
+    /// Records the operation whose backward operation has type `F`.
+    pub(crate) fn capture<F>() -> Self {
+        Self {
+            name: op_name(std::any::type_name::<F>()),
+            backtrace: Backtrace::force_capture(),
+        }
+    }
+
+    /// **Panics** if `output` contains a non-finite value.
+    pub(crate) fn check_forward<A: HasShape + CountElements, D: Device<A>>(&self, output: &A) {
+        if let Some(non_finite) = NonFinite::find::<A, D>(output) {
+            self.report("forward", "output", non_finite);
+        }
+    }
+
+    /// **Panics** if `gradient` contains a non-finite value. `find` is from [NonFinite::find_any()].
+    pub(crate) fn check_backward(&self, gradient: &dyn Any, find: FindNonFinite) {
+        if let Some(non_finite) = find(gradient) {
+            self.report("backward", "gradient", non_finite);
+        }
+    }
+
+    fn report(&self, pass: &str, what: &str, non_finite: NonFinite) -> ! {
+        panic!(
+            "Anomaly detected in the {pass} of `{}`: {what} of shape {:?} has {} at index {:?}\n\nThe operation was called at:\n{}",
+            self.name, non_finite.shape, non_finite.value, non_finite.index, self.backtrace
+        );
+    }
+}
+
+/// Type erased version of [NonFinite::find()], which is created where the array type is known.
+pub(crate) type FindNonFinite = fn(&dyn Any) -> Option<NonFinite>;
+
+/// The first non-finite value of an array.
+#[derive(Debug)]
+pub(crate) struct NonFinite {
+    shape: &'static [usize],
+    index: Vec<usize>,
+    value: f32,
+}
+
+impl NonFinite {
+    fn find<A: HasShape + CountElements, D: Device<A>>(a: &A) -> Option<Self> {
+        D::first_non_finite(a).map(|(i, value)| Self {
+            shape: A::SHAPE,
+            index: unravel_index(i, A::SHAPE),
+            value,
+        })
+    }
+
+    pub(crate) fn find_any<A, D>(a: &dyn Any) -> Option<Self>
+    where
+        A: 'static + HasShape + CountElements,
+        D: Device<A>,
+    {
+        Self::find::<A, D>(a.downcast_ref().unwrap())
+    }
+}
+
+/// The name of the operation that created a backward operation, based on the type name of
+/// the backward operation. The closure may be created in a helper (e.g. in `tensor_ops::utils`),
+/// but the function that called the helper is part of the generic parameters.
+pub(crate) fn op_name(type_name: &'static str) -> &'static str {
+    const OPS: [&str; 3] = ["dfdx::tensor_ops::", "dfdx::nn::", "dfdx::losses::"];
+    let mut rest = type_name;
+    while let Some(start) = rest.find("dfdx::") {
+        rest = &rest[start..];
+        let end = rest
+            .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == ':'))
+            .unwrap_or(rest.len());
+        let path = rest[..end].trim_end_matches(':');
+        if OPS.iter().any(|m| path.starts_with(m)) && !path.starts_with("dfdx::tensor_ops::utils") {
+            return path.rsplit("::").next().unwrap();
+        }
+        rest = &rest[end..];
+    }
+    type_name
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+
+    #[test]
+    fn test_op_name() {
+        assert_eq!(
+            op_name("dfdx::tensor_ops::utils::map<dfdx::tensor::structs::Tensor1D<3, dfdx::gradients::OwnedTape>, dfdx::tensor_ops::map::tanh<dfdx::tensor::structs::Tensor1D<3, dfdx::gradients::OwnedTape>>::{{closure}}>::{{closure}}"),
+            "tanh"
+        );
+        assert_eq!(
+            op_name("dfdx::tensor_ops::matmul::matmul<2, 3, 4, dfdx::gradients::OwnedTape>::{{closure}}"),
+            "matmul"
+        );
+        assert_eq!(
+            op_name("dfdx::nn::checkpoint::<impl dfdx::nn::module::Module<dfdx::tensor::structs::Tensor1D<3, dfdx::gradients::OwnedTape>> for dfdx::nn::checkpoint::Checkpoint<Inner>>::forward::{{closure}}"),
+            "checkpoint"
+        );
+        assert_eq!(
+            op_name("my_crate::op::{{closure}}"),
+            "my_crate::op::{{closure}}"
+        );
+    }
+
+    #[test]
+    #[should_panic = "Anomaly detected in the forward of `ln`: output of shape [3] has -inf at index [1]"]
+    fn test_forward_anomaly() {
+        set_detect_anomaly(true);
+        let x = tensor([1.0, 0.0, -1.0]);
+        let _ = x.trace().ln();
+    }
+
+    #[test]
+    #[should_panic = "Anomaly detected in the forward of `div`: output of shape [2, 2] has NaN at index [1, 0]"]
+    fn test_forward_anomaly_2d() {
+        set_detect_anomaly(true);
+        let x = tensor([[1.0, 2.0], [0.0, 4.0]]);
+        let _ = div(x.trace(), &tensor([[1.0, 1.0], [0.0, 1.0]]));
+    }
+
+    #[test]
+    #[should_panic = "Anomaly detected in the backward of `sqrt`: gradient of shape [2] has inf at index [0]"]
+    fn test_backward_anomaly() {
+        set_detect_anomaly(true);
+        let x = tensor([0.0, 4.0]);
+        let _ = x.trace().sqrt().sum().backward();
+    }
+
+    #[test]
+    fn test_disabled_anomaly_propagates() {
+        let x = tensor([1.0, 0.0, -1.0]);
+        let y = x.trace().ln().sum();
+        assert!(y.data().is_nan());
+        let g = y.backward();
+        assert_eq!(g.ref_gradient(&x)[1], f32::INFINITY);
+    }
+
+    #[test]
+    fn test_anomaly_with_checkpoint() {
+        let model: Checkpoint<(Linear<3, 2>, ReLU)> = Default::default();
+        let x = tensor([1.0, -2.0, 3.0]);
+        let g1 = model.0.forward(x.trace()).sum().backward();
+        set_detect_anomaly(true);
+        let g2 = model.forward(x.trace()).sum().backward();
+        assert_eq!(g1.ref_gradient(&x), g2.ref_gradient(&x));
+        assert_eq!(
+            g1.ref_gradient(&model.0 .0.weight),
+            g2.ref_gradient(&model.0 .0.weight)
+        );
+    }
+
+    #[test]
+    #[should_panic = "Anomaly detected in the backward of `sqrt`"]
+    fn test_backward_anomaly_in_checkpoint() {
+        set_detect_anomaly(true);
+        let model: Checkpoint<(Linear<2, 2>, Sqrt)> = Default::default();
+        let _ = model.forward(tensor([0.0, 0.0]).trace()).sum().backward();
+    }
+
+    #[test]
+    fn test_no_anomaly() {
+        set_detect_anomaly(true);
+        let x = tensor([1.0, 2.0, 3.0]);
+        let g = x.trace().square().mean().backward();
+        assert_eq!(g.ref_gradient(&x), &[2.0 / 3.0, 4.0 / 3.0, 2.0]);
+        set_detect_anomaly(false);
+        assert!(!is_anomaly_enabled());
+    }
+}
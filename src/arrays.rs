@@ -154,6 +154,27 @@ impl<const M: usize, const N: usize, const O: usize, const P: usize> HasLastAxis
     const SIZE: usize = P;
 }
 
+/// An NdArray with a compile time known shape.
+pub trait HasShape {
+    /// The size of each axis. E.g. an nd array of shape (M, N, O) has `SHAPE` = `&[M, N, O]`,
+    /// and `f32` has `SHAPE` = `&[]`.
+    const SHAPE: &'static [usize];
+}
+
+macro_rules! impl_has_shape {
+    ($SrcTy:tt, [$($Vars:tt),*]) => {
+impl<$(const $Vars: usize, )*> HasShape for $SrcTy {
+    const SHAPE: &'static [usize] = &[$($Vars, )*];
+}
+    };
+}
+
+impl_has_shape!(f32, []);
+impl_has_shape!([f32; M], [M]);
+impl_has_shape!([[f32; N]; M], [M, N]);
+impl_has_shape!([[[f32; O]; N]; M], [M, N, O]);
+impl_has_shape!([[[[f32; P]; O]; N]; M], [M, N, O, P]);
+
 /// Something that has compile time known zero values.
 pub trait ZeroElements {
     const ZEROS: Self;
@@ -163,6 +184,10 @@ impl ZeroElements for f32 {
     const ZEROS: Self = 0.0;
 }
 
+impl ZeroElements for usize {
+    const ZEROS: Self = 0;
+}
+
 impl<T: ZeroElements, const M: usize> ZeroElements for [T; M] {
     const ZEROS: Self = [T::ZEROS; M];
 }
@@ -177,7 +202,8 @@ pub trait HasArrayType {
         + ZeroElements
         + HasAxes<Axis<0>>
         + HasAxes<AllAxes>
-        + HasLastAxis;
+        + HasLastAxis
+        + HasShape;
 }
 
 /// Something that has [HasArrayType], and also can return a reference to or mutate `Self::Array`.
@@ -210,6 +236,13 @@ mod tests {
         assert_eq!(30, <[[[f32; 2]; 3]; 5]>::NUM_ELEMENTS);
     }
 
+    #[test]
+    fn test_shapes() {
+        assert_eq!(f32::SHAPE, &[]);
+        assert_eq!(<[f32; 5]>::SHAPE, &[5]);
+        assert_eq!(<[[[[f32; 2]; 3]; 5]; 7]>::SHAPE, &[7, 5, 3, 2]);
+    }
+
     #[test]
     fn test_first_elem_ref() {
         let mut a: [[f32; 2]; 3] = [[1.0, 2.0], [3.0, 4.0], [5.0, 6.0]];
@@ -20,11 +20,7 @@ use crate::tensor::{Tensor1D, Tensor2D, TensorCreator};
 /// assert_eq!(t.data(), &[0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0]);
 /// ```
 pub fn arange<const N: usize>() -> Tensor1D<N> {
-    let mut output = Tensor1D::zeros();
-    for i in 0..N {
-        output.mut_data()[i] = i as f32;
-    }
-    output
+    TensorCreator::arange()
 }
 
 /// One hot encodes an array of class labels into a [Tensor2D] of probability
@@ -0,0 +1,197 @@
+//! Einstein summation of two nd arrays, where every axis is labelled by a single character.
+//!
+//! The summation is lowered to a batched matrix multiplication:
+//! 1. Every label is classified by which of `lhs`, `rhs`, and `out` it appears in:
+//!     - batch labels are in all three
+//!     - `m` labels are only in `lhs` and `out`
+//!     - `n` labels are only in `rhs` and `out`
+//!     - `k` (contracted) labels are only in `lhs` and `rhs`
+//! 2. `lhs` is packed into a `[batch, m, k]` buffer, and `rhs` into a `[batch, k, n]` buffer.
+//!    Labels that are only in one of the inputs are summed over while packing.
+//! 3. Each batch is multiplied with the same matmul kernel as [super::MatMul].
+//! 4. The `[batch, m, n]` result is added to `out`. Labels that are only in `out` are
+//!    broadcasted.
+
+use super::{as_mut_slice, as_slice, Cpu};
+use crate::arrays::{CountElements, HasShape};
+
+/// Einstein summation of `L` and `R` into `O`.
+pub trait DeviceEinsum<L, R, O> {
+    /// Computes `out += einsum(lhs, rhs)`, where `*_labels` contain the label of each axis of
+    /// the corresponding array.
+    ///
+    /// Labels can not be repeated within a single array.
+    fn einsum(
+        lhs: &L,
+        lhs_labels: &[u8],
+        rhs: &R,
+        rhs_labels: &[u8],
+        out: &mut O,
+        out_labels: &[u8],
+    );
+}
+
+impl<L, R, O> DeviceEinsum<L, R, O> for Cpu
+where
+    L: CountElements<Dtype = f32> + HasShape,
+    R: CountElements<Dtype = f32> + HasShape,
+    O: CountElements<Dtype = f32> + HasShape,
+{
+    fn einsum(
+        lhs: &L,
+        lhs_labels: &[u8],
+        rhs: &R,
+        rhs_labels: &[u8],
+        out: &mut O,
+        out_labels: &[u8],
+    ) {
+        assert_eq!(lhs_labels.len(), L::SHAPE.len());
+        assert_eq!(rhs_labels.len(), R::SHAPE.len());
+        assert_eq!(out_labels.len(), O::SHAPE.len());
+
+        let mut batch = Vec::new();
+        let mut m = Vec::new();
+        let mut n = Vec::new();
+        let mut k = Vec::new();
+        for (&label, &size) in out_labels.iter().zip(O::SHAPE.iter()) {
+            match (lhs_labels.contains(&label), rhs_labels.contains(&label)) {
+                (true, true) => batch.push((label, size)),
+                (true, false) => m.push((label, size)),
+                (false, true) => n.push((label, size)),
+                (false, false) => {}
+            }
+        }
+        for (&label, &size) in lhs_labels.iter().zip(L::SHAPE.iter()) {
+            if rhs_labels.contains(&label) && !out_labels.contains(&label) {
+                k.push((label, size));
+            }
+        }
+
+        let size = |group: &[(u8, usize)]| group.iter().map(|(_, s)| s).product::<usize>();
+        let (num_batch, num_m, num_n, num_k) = (size(&batch), size(&m), size(&n), size(&k));
+
+        let mut a = vec![0.0; num_batch * num_m * num_k];
+        let a_labels = [batch.as_slice(), &m, &k].concat();
+        scatter_add(as_slice(lhs), lhs_labels, L::SHAPE, &mut a, &a_labels);
+
+        let mut b = vec![0.0; num_batch * num_k * num_n];
+        let b_labels = [batch.as_slice(), &k, &n].concat();
+        scatter_add(as_slice(rhs), rhs_labels, R::SHAPE, &mut b, &b_labels);
+
+        let mut c = vec![0.0; num_batch * num_m * num_n];
+        for i in 0..num_batch {
+            Cpu::mm_slices(
+                num_m,
+                num_k,
+                num_n,
+                &a[i * num_m * num_k..(i + 1) * num_m * num_k],
+                &b[i * num_k * num_n..(i + 1) * num_k * num_n],
+                &mut c[i * num_m * num_n..(i + 1) * num_m * num_n],
+            );
+        }
+
+        let c_labels = [batch.as_slice(), &m, &n].concat();
+        let out_strides = strides_in(out_labels, &c_labels);
+        let out = as_mut_slice(out);
+        foreach_index(O::SHAPE, &out_strides, &mut |i, j| out[i] += c[j]);
+    }
+}
+
+/// Computes `dst[..] += src[..]` where the axes of `dst` are a subset of the labels of `src`.
+/// Axes of `src` that are not in `dst` are summed over.
+fn scatter_add(
+    src: &[f32],
+    src_labels: &[u8],
+    src_shape: &[usize],
+    dst: &mut [f32],
+    dst_labels: &[(u8, usize)],
+) {
+    let strides = strides_in(src_labels, dst_labels);
+    foreach_index(src_shape, &strides, &mut |i, j| dst[j] += src[i]);
+}
+
+/// The stride of each label in `labels` within a contiguous array with axes `other`.
+/// Labels that aren't in `other` have a stride of 0.
+fn strides_in(labels: &[u8], other: &[(u8, usize)]) -> Vec<usize> {
+    labels
+        .iter()
+        .map(|label| match other.iter().position(|(l, _)| l == label) {
+            Some(i) => other[i + 1..].iter().map(|(_, s)| s).product(),
+            None => 0,
+        })
+        .collect()
+}
+
+/// Calls `f` with the flat index of every element of a contiguous array of `shape`, along with
+/// the index of that element using `strides`.
+fn foreach_index<F: FnMut(usize, usize)>(shape: &[usize], strides: &[usize], f: &mut F) {
+    let numel: usize = shape.iter().product();
+    let mut idx = vec![0; shape.len()];
+    let mut j = 0;
+    for i in 0..numel {
+        f(i, j);
+        for ax in (0..shape.len()).rev() {
+            idx[ax] += 1;
+            j += strides[ax];
+            if idx[ax] < shape[ax] {
+                break;
+            }
+            j -= idx[ax] * strides[ax];
+            idx[ax] = 0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::devices::MatMul;
+    use crate::tests::assert_close;
+
+    #[test]
+    fn test_einsum_matmul() {
+        let a = [[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]];
+        let b = [[1.0, 2.0], [3.0, 4.0], [5.0, 6.0]];
+        let mut expected = [[0.0; 2]; 2];
+        Cpu::mm(&a, &b, &mut expected);
+
+        let mut c = [[0.0; 2]; 2];
+        Cpu::einsum(&a, b"ij", &b, b"jk", &mut c, b"ik");
+        assert_close(&c, &expected);
+
+        // transposed output
+        let mut c_t = [[0.0; 2]; 2];
+        Cpu::einsum(&a, b"ij", &b, b"jk", &mut c_t, b"ki");
+        assert_close(&c_t, &[[22.0, 49.0], [28.0, 64.0]]);
+    }
+
+    #[test]
+    fn test_einsum_sum_and_broadcast() {
+        let a = [[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]];
+
+        // sum over j, since it's only in lhs
+        let mut r = [0.0; 2];
+        Cpu::einsum(&a, b"ij", &1.0, b"", &mut r, b"i");
+        assert_eq!(r, [6.0, 15.0]);
+
+        // broadcast over k, since it's only in out
+        let mut r = [[0.0; 3]; 2];
+        Cpu::einsum(&[1.0, 2.0], b"i", &1.0, b"", &mut r, b"ik");
+        assert_eq!(r, [[1.0; 3], [2.0; 3]]);
+    }
+
+    #[test]
+    fn test_einsum_batched_outer() {
+        let a = [[1.0, 2.0], [3.0, 4.0]];
+        let b = [[1.0, -1.0, 0.5], [2.0, 0.0, 1.0]];
+        let mut r = [[[0.0; 3]; 2]; 2];
+        Cpu::einsum(&a, b"bi", &b, b"bj", &mut r, b"bij");
+        assert_eq!(
+            r,
+            [
+                [[1.0, -1.0, 0.5], [2.0, -2.0, 1.0]],
+                [[6.0, 0.0, 3.0], [8.0, 0.0, 4.0]]
+            ]
+        );
+    }
+}
@@ -0,0 +1,284 @@
+//! Discrete fourier transforms along the last axis of nd arrays.
+//!
+//! Complex arrays are represented by a pair of arrays with the same shape, one with
+//! the real parts and one with the imaginary parts.
+//!
+//! Every transform is computed in `f64` on a single lane of the last axis at a time:
+//! - Lanes with a power of two length use an iterative radix-2 cooley-tukey FFT
+//! - Other lengths use bluestein's algorithm, which turns the transform into a convolution
+//!   that is computed with power of two FFTs.
+//!
+//! The backward functions accumulate into the gradients (`+=`), while the forward functions
+//! overwrite their outputs.
+
+use super::{as_mut_slice, as_slice, Cpu};
+use crate::arrays::{CountElements, HasLastAxis};
+use std::f64::consts::PI;
+
+/// Fourier transforms along the last axis of `T`.
+///
+/// `R` are arrays with the same shape as `T`, except the last axis has size `N / 2 + 1`,
+/// where `N` is the size of the last axis of `T`.
+pub trait DeviceFft<T> {
+    /// Unnormalized complex DFT in place: `X[k] = sum_n x[n] * exp(-2 pi i k n / N)`.
+    /// If `inverse` is true, the sign of the exponent is flipped.
+    fn fft(re: &mut T, im: &mut T, inverse: bool);
+
+    /// The first `N / 2 + 1` frequencies of the DFT of the real array `x`.
+    fn rfft<R: CountElements<Dtype = f32>>(x: &T, re: &mut R, im: &mut R);
+    fn rfft_backward<R: CountElements<Dtype = f32>>(re_grad: &R, im_grad: &R, x_grad: &mut T);
+
+    /// Inverse of [DeviceFft::rfft()], normalized by `1 / N`. The imaginary parts of the
+    /// frequencies that don't have one in a real signal (`0` and `N / 2`) are ignored.
+    fn irfft<R: CountElements<Dtype = f32>>(re: &R, im: &R, x: &mut T);
+    fn irfft_backward<R: CountElements<Dtype = f32>>(x_grad: &T, re_grad: &mut R, im_grad: &mut R);
+}
+
+impl<T: CountElements<Dtype = f32> + HasLastAxis> DeviceFft<T> for Cpu {
+    fn fft(re: &mut T, im: &mut T, inverse: bool) {
+        let n = T::SIZE;
+        let lanes = as_mut_slice(re)
+            .chunks_mut(n)
+            .zip(as_mut_slice(im).chunks_mut(n));
+        for (re, im) in lanes {
+            let mut z: Vec<Complex> = re.iter().zip(im.iter()).map(|(&r, &i)| c(r, i)).collect();
+            dft(&mut z, inverse);
+            for ((re, im), z) in re.iter_mut().zip(im.iter_mut()).zip(z.iter()) {
+                *re = z.0 as f32;
+                *im = z.1 as f32;
+            }
+        }
+    }
+
+    fn rfft<R: CountElements<Dtype = f32>>(x: &T, re: &mut R, im: &mut R) {
+        let (n, k) = sizes::<T, R>();
+        let lanes = as_mut_slice(re)
+            .chunks_mut(k)
+            .zip(as_mut_slice(im).chunks_mut(k));
+        for (x, (re, im)) in as_slice(x).chunks(n).zip(lanes) {
+            let mut z: Vec<Complex> = x.iter().map(|&x| c(x, 0.0)).collect();
+            dft(&mut z, false);
+            for ((re, im), z) in re.iter_mut().zip(im.iter_mut()).zip(z.iter()) {
+                *re = z.0 as f32;
+                *im = z.1 as f32;
+            }
+        }
+    }
+
+    fn rfft_backward<R: CountElements<Dtype = f32>>(re_grad: &R, im_grad: &R, x_grad: &mut T) {
+        let (n, k) = sizes::<T, R>();
+        let lanes = as_slice(re_grad).chunks(k).zip(as_slice(im_grad).chunks(k));
+        for ((re, im), x_grad) in lanes.zip(as_mut_slice(x_grad).chunks_mut(n)) {
+            // x_grad += real(conj(F)^T * g), where g is zero for the frequencies that aren't returned
+            let mut z = vec![c(0.0, 0.0); n];
+            for (z, (&r, &i)) in z.iter_mut().zip(re.iter().zip(im.iter())) {
+                *z = c(r, i);
+            }
+            dft(&mut z, true);
+            for (g, z) in x_grad.iter_mut().zip(z.iter()) {
+                *g += z.0 as f32;
+            }
+        }
+    }
+
+    fn irfft<R: CountElements<Dtype = f32>>(re: &R, im: &R, x: &mut T) {
+        let (n, k) = sizes::<T, R>();
+        let lanes = as_slice(re).chunks(k).zip(as_slice(im).chunks(k));
+        for ((re, im), x) in lanes.zip(as_mut_slice(x).chunks_mut(n)) {
+            // fill in the rest of the hermitian spectrum of a real signal
+            let mut z = vec![c(0.0, 0.0); n];
+            for i in 0..n {
+                z[i] = if i < k {
+                    c(re[i], im[i])
+                } else {
+                    c(re[n - i], -im[n - i])
+                };
+            }
+            z[0].1 = 0.0;
+            if n % 2 == 0 {
+                z[n / 2].1 = 0.0;
+            }
+            dft(&mut z, true);
+            for (x, z) in x.iter_mut().zip(z.iter()) {
+                *x = (z.0 / n as f64) as f32;
+            }
+        }
+    }
+
+    fn irfft_backward<R: CountElements<Dtype = f32>>(x_grad: &T, re_grad: &mut R, im_grad: &mut R) {
+        let (n, k) = sizes::<T, R>();
+        let lanes = as_mut_slice(re_grad)
+            .chunks_mut(k)
+            .zip(as_mut_slice(im_grad).chunks_mut(k));
+        for (g, (re_grad, im_grad)) in as_slice(x_grad).chunks(n).zip(lanes) {
+            // every frequency except 0 and N / 2 shows up twice in the hermitian spectrum
+            let mut z: Vec<Complex> = g.iter().map(|&g| c(g, 0.0)).collect();
+            dft(&mut z, false);
+            for (i, z) in z.iter().take(k).enumerate() {
+                let scale = if i == 0 || 2 * i == n { 1.0 } else { 2.0 } / n as f64;
+                re_grad[i] += (scale * z.0) as f32;
+                im_grad[i] += (scale * z.1) as f32;
+            }
+        }
+    }
+}
+
+/// The size of the last axis of `T`, and the number of frequencies `N / 2 + 1` stored
+/// in the last axis of `R`.
+fn sizes<T: CountElements + HasLastAxis, R: CountElements>() -> (usize, usize) {
+    let n = T::SIZE;
+    let k = R::NUM_ELEMENTS / (T::NUM_ELEMENTS / n);
+    debug_assert_eq!(k, n / 2 + 1);
+    (n, k)
+}
+
+/// A complex number `(real, imaginary)`.
+type Complex = (f64, f64);
+
+fn c(re: f32, im: f32) -> Complex {
+    (re as f64, im as f64)
+}
+
+fn mul(a: Complex, b: Complex) -> Complex {
+    (a.0 * b.0 - a.1 * b.1, a.0 * b.1 + a.1 * b.0)
+}
+
+/// `exp(i * angle)`
+fn expi(angle: f64) -> Complex {
+    (angle.cos(), angle.sin())
+}
+
+/// Unnormalized DFT of a single lane in place.
+fn dft(z: &mut [Complex], inverse: bool) {
+    match z.len() {
+        0 | 1 => {}
+        n if n.is_power_of_two() => radix2(z, inverse),
+        _ => bluestein(z, inverse),
+    }
+}
+
+/// Iterative radix-2 cooley-tukey FFT. `z.len()` must be a power of two.
+fn radix2(z: &mut [Complex], inverse: bool) {
+    let n = z.len();
+    let sign = if inverse { 1.0 } else { -1.0 };
+
+    // bit reversal permutation
+    let bits = n.trailing_zeros();
+    for i in 0..n {
+        let j = i.reverse_bits() >> (usize::BITS - bits);
+        if i < j {
+            z.swap(i, j);
+        }
+    }
+
+    let mut len = 2;
+    while len <= n {
+        let half = len / 2;
+        for j in 0..half {
+            let w = expi(sign * 2.0 * PI * j as f64 / len as f64);
+            for start in (0..n).step_by(len) {
+                let u = z[start + j];
+                let v = mul(z[start + j + half], w);
+                z[start + j] = (u.0 + v.0, u.1 + v.1);
+                z[start + j + half] = (u.0 - v.0, u.1 - v.1);
+            }
+        }
+        len *= 2;
+    }
+}
+
+/// Bluestein's algorithm for arbitrary lengths. Uses `k * n = (k^2 + n^2 - (k - n)^2) / 2`
+/// to write the DFT as a convolution with the chirp `exp(sign * i * pi * m^2 / N)`.
+fn bluestein(z: &mut [Complex], inverse: bool) {
+    let n = z.len();
+    let m = (2 * n - 1).next_power_of_two();
+    let sign = if inverse { 1.0 } else { -1.0 };
+
+    // m^2 is reduced modulo 2n to keep the angles small & accurate
+    let chirp: Vec<Complex> = (0..n)
+        .map(|i| expi(sign * PI * ((i * i) % (2 * n)) as f64 / n as f64))
+        .collect();
+
+    let mut a = vec![(0.0, 0.0); m];
+    for i in 0..n {
+        a[i] = mul(z[i], chirp[i]);
+    }
+    let mut b = vec![(0.0, 0.0); m];
+    b[0] = (chirp[0].0, -chirp[0].1);
+    for i in 1..n {
+        b[i] = (chirp[i].0, -chirp[i].1);
+        b[m - i] = b[i];
+    }
+
+    radix2(&mut a, false);
+    radix2(&mut b, false);
+    for (a, b) in a.iter_mut().zip(b.iter()) {
+        *a = mul(*a, *b);
+    }
+    radix2(&mut a, true);
+
+    for i in 0..n {
+        let conv = (a[i].0 / m as f64, a[i].1 / m as f64);
+        z[i] = mul(conv, chirp[i]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// O(N^2) DFT to compare against.
+    fn naive_dft(z: &[Complex], inverse: bool) -> Vec<Complex> {
+        let n = z.len();
+        let sign = if inverse { 1.0 } else { -1.0 };
+        (0..n)
+            .map(|k| {
+                z.iter().enumerate().fold((0.0, 0.0), |acc, (i, &z)| {
+                    let w = expi(sign * 2.0 * PI * ((k * i) % n) as f64 / n as f64);
+                    let v = mul(z, w);
+                    (acc.0 + v.0, acc.1 + v.1)
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_dft_matches_naive() {
+        for n in [1, 2, 3, 5, 8, 12, 16, 17, 100] {
+            let z: Vec<Complex> = (0..n)
+                .map(|i| ((i as f64).sin(), (i as f64 * 0.3).cos()))
+                .collect();
+            for inverse in [false, true] {
+                let expected = naive_dft(&z, inverse);
+                let mut actual = z.clone();
+                dft(&mut actual, inverse);
+                for (a, e) in actual.iter().zip(expected.iter()) {
+                    assert!((a.0 - e.0).abs() < 1e-9 && (a.1 - e.1).abs() < 1e-9);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_rfft_irfft_roundtrip() {
+        for x in [[1.0, -2.0, 3.0, 0.5, 4.0], [0.0, 1.0, 0.0, -1.0, 2.0]] {
+            let mut re = [0.0; 3];
+            let mut im = [0.0; 3];
+            <Cpu as DeviceFft<_>>::rfft(&x, &mut re, &mut im);
+            let mut y = [0.0; 5];
+            <Cpu as DeviceFft<_>>::irfft(&re, &im, &mut y);
+            for (x, y) in x.iter().zip(y.iter()) {
+                assert!((x - y).abs() < 1e-6);
+            }
+        }
+    }
+
+    #[test]
+    fn test_fft_2d_lanes() {
+        let mut re = [[1.0, 0.0, 0.0, 0.0], [1.0, 1.0, 1.0, 1.0]];
+        let mut im = [[0.0; 4]; 2];
+        <Cpu as DeviceFft<_>>::fft(&mut re, &mut im, false);
+        assert_eq!(re, [[1.0; 4], [4.0, 0.0, 0.0, 0.0]]);
+        assert_eq!(im, [[0.0; 4]; 2]);
+    }
+}
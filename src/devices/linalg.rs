@@ -0,0 +1,1134 @@
+//! Linear algebra on square matrices `[[f32; N]; N]`, and batches of square matrices
+//! `[[[f32; N]; N]; B]`.
+//!
+//! Every kernel is implemented on flat row major slices of a single `n x n` matrix, and
+//! [DeviceLinalg] loops over the batch. Decompositions use LAPACK when the `cblas` feature
+//! is enabled, and pure rust implementations otherwise:
+//! - LU decomposition with partial pivoting (used by inverse, determinant, and solve)
+//! - Cholesky decomposition
+//! - QR decomposition with householder reflections
+//! - SVD with one sided jacobi rotations
+//!
+//! The backward functions accumulate into the gradients (`+=`), while the forward functions
+//! overwrite their outputs.
+
+use super::{as_mut_slice, as_slice, Cpu};
+use crate::arrays::{CountElements, HasShape};
+
+/// Linear algebra on `T`, which is either a square matrix or a batch of square matrices.
+///
+/// `S` are arrays with one value per matrix (`f32` or `[f32; B]`), and `X` are arrays
+/// with the same number of matrices & rows as `T` (e.g. `[[f32; K]; N]` or `[f32; N]`).
+pub trait DeviceLinalg<T> {
+    /// `out = inverse(a)`
+    fn inverse(a: &T, out: &mut T);
+    fn inverse_backward(inv: &T, inv_grad: &T, a_grad: &mut T);
+
+    /// `out = det(a)`
+    fn det<S: CountElements<Dtype = f32>>(a: &T, out: &mut S);
+    fn det_backward<S: CountElements<Dtype = f32>>(a: &T, det: &S, det_grad: &S, a_grad: &mut T);
+
+    /// `out = ln(|det(a)|)`
+    fn logdet<S: CountElements<Dtype = f32>>(a: &T, out: &mut S);
+    fn logdet_backward<S: CountElements<Dtype = f32>>(a: &T, out_grad: &S, a_grad: &mut T);
+
+    /// `l` is lower triangular such that `a = l * l^T`.
+    fn cholesky(a: &T, l: &mut T);
+    fn cholesky_backward(l: &T, l_grad: &T, a_grad: &mut T);
+
+    /// Solves `a * x = b` where `a` is lower triangular if `lower`, and upper triangular otherwise.
+    fn triangular_solve<X: CountElements<Dtype = f32>>(a: &T, b: &X, x: &mut X, lower: bool);
+    fn triangular_solve_backward<X: CountElements<Dtype = f32>>(
+        a: &T,
+        x: &X,
+        x_grad: &X,
+        lower: bool,
+        a_grad: &mut T,
+        b_grad: &mut X,
+    );
+
+    /// Solves `a * x = b`.
+    fn solve<X: CountElements<Dtype = f32>>(a: &T, b: &X, x: &mut X);
+    fn solve_backward<X: CountElements<Dtype = f32>>(
+        a: &T,
+        x: &X,
+        x_grad: &X,
+        a_grad: &mut T,
+        b_grad: &mut X,
+    );
+
+    /// `a = q * r` where `q` is orthogonal and `r` is upper triangular with a non negative diagonal.
+    fn qr(a: &T, q: &mut T, r: &mut T);
+    fn qr_backward(q: &T, r: &T, q_grad: &T, r_grad: &T, a_grad: &mut T);
+
+    /// `a = u * diag(s) * v^T` where `u` & `v` are orthogonal, and `s` is sorted in descending order.
+    fn svd<S: CountElements<Dtype = f32>>(a: &T, u: &mut T, s: &mut S, v: &mut T);
+    #[allow(clippy::too_many_arguments)]
+    fn svd_backward<S: CountElements<Dtype = f32>>(
+        u: &T,
+        s: &S,
+        v: &T,
+        u_grad: &T,
+        s_grad: &S,
+        v_grad: &T,
+        a_grad: &mut T,
+    );
+}
+
+impl<T: CountElements<Dtype = f32> + HasShape> DeviceLinalg<T> for Cpu {
+    fn inverse(a: &T, out: &mut T) {
+        let n = size::<T>();
+        for (a, out) in matrices(a, n).zip(matrices_mut(out, n)) {
+            out.copy_from_slice(&inverse(a, n));
+        }
+    }
+
+    fn inverse_backward(inv: &T, inv_grad: &T, a_grad: &mut T) {
+        let n = size::<T>();
+        let inputs = matrices(inv, n).zip(matrices(inv_grad, n));
+        for ((y, y_grad), a_grad) in inputs.zip(matrices_mut(a_grad, n)) {
+            // a_grad -= y^T * y_grad * y^T
+            let y_t = transpose(n, n, y);
+            let g = mm(n, n, n, &mm(n, n, n, &y_t, y_grad), &y_t);
+            sub_assign(a_grad, &g);
+        }
+    }
+
+    fn det<S: CountElements<Dtype = f32>>(a: &T, out: &mut S) {
+        let n = size::<T>();
+        for (a, out) in matrices(a, n).zip(as_mut_slice(out).iter_mut()) {
+            let mut lu = a.to_vec();
+            let mut pivots = vec![0; n];
+            let sign = lu_decompose(&mut lu, n, &mut pivots);
+            *out = (0..n).fold(sign, |det, i| det * lu[i * n + i]);
+        }
+    }
+
+    fn det_backward<S: CountElements<Dtype = f32>>(a: &T, det: &S, det_grad: &S, a_grad: &mut T) {
+        let n = size::<T>();
+        let scales = as_slice(det).iter().zip(as_slice(det_grad).iter());
+        for ((a, (d, g)), a_grad) in matrices(a, n).zip(scales).zip(matrices_mut(a_grad, n)) {
+            if *d == 0.0 {
+                // a^{-1} doesn't exist, but the adjugate does
+                let adj_t = adjugate_transpose(a, n);
+                for (a_grad, adj) in a_grad.iter_mut().zip(adj_t.iter()) {
+                    *a_grad += g * adj;
+                }
+                continue;
+            }
+            // a_grad += det_grad * det * a^{-T}
+            let inv = inverse(a, n);
+            for i in 0..n {
+                for j in 0..n {
+                    a_grad[i * n + j] += g * d * inv[j * n + i];
+                }
+            }
+        }
+    }
+
+    fn logdet<S: CountElements<Dtype = f32>>(a: &T, out: &mut S) {
+        let n = size::<T>();
+        for (a, out) in matrices(a, n).zip(as_mut_slice(out).iter_mut()) {
+            let mut lu = a.to_vec();
+            let mut pivots = vec![0; n];
+            lu_decompose(&mut lu, n, &mut pivots);
+            *out = (0..n).map(|i| lu[i * n + i].abs().ln()).sum();
+        }
+    }
+
+    fn logdet_backward<S: CountElements<Dtype = f32>>(a: &T, out_grad: &S, a_grad: &mut T) {
+        let n = size::<T>();
+        let grads = as_slice(out_grad).iter();
+        for ((a, g), a_grad) in matrices(a, n).zip(grads).zip(matrices_mut(a_grad, n)) {
+            // a_grad += out_grad * a^{-T}
+            let inv = inverse(a, n);
+            for i in 0..n {
+                for j in 0..n {
+                    a_grad[i * n + j] += g * inv[j * n + i];
+                }
+            }
+        }
+    }
+
+    fn cholesky(a: &T, l: &mut T) {
+        let n = size::<T>();
+        for (a, l) in matrices(a, n).zip(matrices_mut(l, n)) {
+            cholesky(a, l, n);
+        }
+    }
+
+    fn cholesky_backward(l: &T, l_grad: &T, a_grad: &mut T) {
+        let n = size::<T>();
+        let inputs = matrices(l, n).zip(matrices(l_grad, n));
+        for ((l, l_grad), a_grad) in inputs.zip(matrices_mut(a_grad, n)) {
+            // p = phi(l^T * l_grad), where phi takes the lower triangle and halves the diagonal
+            let mut p = mm(n, n, n, &transpose(n, n, l), l_grad);
+            for i in 0..n {
+                p[i * n + i] *= 0.5;
+                for j in i + 1..n {
+                    p[i * n + j] = 0.0;
+                }
+            }
+            // s = l^{-T} * p * l^{-1}
+            triangular_solve(l, n, &mut p, n, true, true);
+            let mut s = transpose(n, n, &p);
+            triangular_solve(l, n, &mut s, n, true, true);
+            // a_grad += (s + s^T) / 2. NOTE: s is transposed at this point
+            for i in 0..n {
+                for j in 0..n {
+                    a_grad[i * n + j] += 0.5 * (s[i * n + j] + s[j * n + i]);
+                }
+            }
+        }
+    }
+
+    fn triangular_solve<X: CountElements<Dtype = f32>>(a: &T, b: &X, x: &mut X, lower: bool) {
+        let n = size::<T>();
+        let k = columns::<T, X>(n);
+        x.clone_from(b);
+        for (a, x) in matrices(a, n).zip(as_mut_slice(x).chunks_exact_mut((n * k).max(1))) {
+            triangular_solve(a, n, x, k, lower, false);
+        }
+    }
+
+    fn triangular_solve_backward<X: CountElements<Dtype = f32>>(
+        a: &T,
+        x: &X,
+        x_grad: &X,
+        lower: bool,
+        a_grad: &mut T,
+        b_grad: &mut X,
+    ) {
+        let n = size::<T>();
+        let k = columns::<T, X>(n);
+        let n_k = (n * k).max(1);
+        for (i, ((a, x), x_grad)) in matrices(a, n)
+            .zip(as_slice(x).chunks_exact(n_k))
+            .zip(as_slice(x_grad).chunks_exact(n_k))
+            .enumerate()
+        {
+            // b_grad += a^{-T} * x_grad
+            let mut g = x_grad.to_vec();
+            triangular_solve(a, n, &mut g, k, lower, true);
+            add_assign(&mut as_mut_slice(b_grad)[i * n_k..(i + 1) * n_k], &g);
+
+            // a_grad -= g * x^T, masked to the triangle of a
+            let a_grad = &mut as_mut_slice(a_grad)[i * n * n..(i + 1) * n * n];
+            let g_x_t = mm(n, k, n, &g, &transpose(n, k, x));
+            for r in 0..n {
+                for c in 0..n {
+                    if (lower && c <= r) || (!lower && c >= r) {
+                        a_grad[r * n + c] -= g_x_t[r * n + c];
+                    }
+                }
+            }
+        }
+    }
+
+    fn solve<X: CountElements<Dtype = f32>>(a: &T, b: &X, x: &mut X) {
+        let n = size::<T>();
+        let k = columns::<T, X>(n);
+        x.clone_from(b);
+        for (a, x) in matrices(a, n).zip(as_mut_slice(x).chunks_exact_mut((n * k).max(1))) {
+            let mut lu = a.to_vec();
+            let mut pivots = vec![0; n];
+            lu_decompose(&mut lu, n, &mut pivots);
+            assert_invertible(&lu, n, "solve with");
+            lu_solve(&lu, &pivots, n, x, k);
+        }
+    }
+
+    fn solve_backward<X: CountElements<Dtype = f32>>(
+        a: &T,
+        x: &X,
+        x_grad: &X,
+        a_grad: &mut T,
+        b_grad: &mut X,
+    ) {
+        let n = size::<T>();
+        let k = columns::<T, X>(n);
+        let n_k = (n * k).max(1);
+        for (i, ((a, x), x_grad)) in matrices(a, n)
+            .zip(as_slice(x).chunks_exact(n_k))
+            .zip(as_slice(x_grad).chunks_exact(n_k))
+            .enumerate()
+        {
+            // b_grad += a^{-T} * x_grad
+            let mut lu = transpose(n, n, a);
+            let mut pivots = vec![0; n];
+            lu_decompose(&mut lu, n, &mut pivots);
+            let mut g = x_grad.to_vec();
+            lu_solve(&lu, &pivots, n, &mut g, k);
+            add_assign(&mut as_mut_slice(b_grad)[i * n_k..(i + 1) * n_k], &g);
+
+            // a_grad -= g * x^T
+            let a_grad = &mut as_mut_slice(a_grad)[i * n * n..(i + 1) * n * n];
+            sub_assign(a_grad, &mm(n, k, n, &g, &transpose(n, k, x)));
+        }
+    }
+
+    fn qr(a: &T, q: &mut T, r: &mut T) {
+        let n = size::<T>();
+        let outputs = matrices_mut(q, n).zip(matrices_mut(r, n));
+        for (a, (q, r)) in matrices(a, n).zip(outputs) {
+            qr(a, q, r, n);
+        }
+    }
+
+    fn qr_backward(q: &T, r: &T, q_grad: &T, r_grad: &T, a_grad: &mut T) {
+        let n = size::<T>();
+        let factors = matrices(q, n).zip(matrices(r, n));
+        let grads = matrices(q_grad, n).zip(matrices(r_grad, n));
+        for (((q, r), (q_grad, r_grad)), a_grad) in factors.zip(grads).zip(matrices_mut(a_grad, n))
+        {
+            // m = r * r_grad^T - q_grad^T * q
+            let mut m = mm(n, n, n, r, &transpose(n, n, r_grad));
+            sub_assign(&mut m, &mm(n, n, n, &transpose(n, n, q_grad), q));
+            // copy the lower triangle of m into the upper triangle
+            for i in 0..n {
+                for j in i + 1..n {
+                    m[i * n + j] = m[j * n + i];
+                }
+            }
+            // a_grad += (q_grad + q * m) * r^{-T}
+            let mut g = q_grad.to_vec();
+            add_assign(&mut g, &mm(n, n, n, q, &m));
+            let mut g_t = transpose(n, n, &g);
+            triangular_solve(r, n, &mut g_t, n, false, false);
+            add_assign(a_grad, &transpose(n, n, &g_t));
+        }
+    }
+
+    fn svd<S: CountElements<Dtype = f32>>(a: &T, u: &mut T, s: &mut S, v: &mut T) {
+        let n = size::<T>();
+        let outputs = matrices_mut(u, n).zip(as_mut_slice(s).chunks_exact_mut(n.max(1)));
+        for ((a, (u, s)), v) in matrices(a, n).zip(outputs).zip(matrices_mut(v, n)) {
+            svd(a, u, s, v, n);
+        }
+    }
+
+    fn svd_backward<S: CountElements<Dtype = f32>>(
+        u: &T,
+        s: &S,
+        v: &T,
+        u_grad: &T,
+        s_grad: &S,
+        v_grad: &T,
+        a_grad: &mut T,
+    ) {
+        let n = size::<T>();
+        let factors = matrices(u, n)
+            .zip(as_slice(s).chunks_exact(n.max(1)))
+            .zip(matrices(v, n));
+        let grads = matrices(u_grad, n)
+            .zip(as_slice(s_grad).chunks_exact(n.max(1)))
+            .zip(matrices(v_grad, n));
+        for ((((u, s), v), ((u_grad, s_grad), v_grad)), a_grad) in
+            factors.zip(grads).zip(matrices_mut(a_grad, n))
+        {
+            // f[i, j] = 1 / (s[j]^2 - s[i]^2)
+            let f = |i: usize, j: usize| {
+                if i == j {
+                    0.0
+                } else {
+                    (s[j].powi(2) - s[i].powi(2)).recip()
+                }
+            };
+            let u_t_u_grad = mm(n, n, n, &transpose(n, n, u), u_grad);
+            let v_t_v_grad = mm(n, n, n, &transpose(n, n, v), v_grad);
+
+            // inner = (f o (u^T u_grad - u_grad^T u)) s + diag(s_grad) + s (f o (v^T v_grad - v_grad^T v))
+            let mut inner = vec![0.0; n * n];
+            for i in 0..n {
+                for j in 0..n {
+                    let ju = f(i, j) * (u_t_u_grad[i * n + j] - u_t_u_grad[j * n + i]);
+                    let jv = f(i, j) * (v_t_v_grad[i * n + j] - v_t_v_grad[j * n + i]);
+                    inner[i * n + j] = ju * s[j] + s[i] * jv;
+                }
+                inner[i * n + i] += s_grad[i];
+            }
+
+            // a_grad += u * inner * v^T
+            let g = mm(n, n, n, &mm(n, n, n, u, &inner), &transpose(n, n, v));
+            add_assign(a_grad, &g);
+        }
+    }
+}
+
+/// The number of rows & columns of each matrix in `T`.
+fn size<T: HasShape>() -> usize {
+    let shape = T::SHAPE;
+    assert!(shape.len() >= 2);
+    assert_eq!(shape[shape.len() - 1], shape[shape.len() - 2]);
+    shape[shape.len() - 1]
+}
+
+/// The number of columns of each matrix in `X`, which has the same number of matrices
+/// and rows as `T`.
+fn columns<T: CountElements, X: CountElements>(n: usize) -> usize {
+    (X::NUM_ELEMENTS * n)
+        .checked_div(T::NUM_ELEMENTS)
+        .unwrap_or(0)
+}
+
+fn matrices<T: CountElements<Dtype = f32>>(t: &T, n: usize) -> std::slice::ChunksExact<'_, f32> {
+    as_slice(t).chunks_exact((n * n).max(1))
+}
+
+fn matrices_mut<T: CountElements<Dtype = f32>>(
+    t: &mut T,
+    n: usize,
+) -> std::slice::ChunksExactMut<'_, f32> {
+    as_mut_slice(t).chunks_exact_mut((n * n).max(1))
+}
+
+fn add_assign(a: &mut [f32], b: &[f32]) {
+    a.iter_mut().zip(b.iter()).for_each(|(a, b)| *a += b);
+}
+
+fn sub_assign(a: &mut [f32], b: &[f32]) {
+    a.iter_mut().zip(b.iter()).for_each(|(a, b)| *a -= b);
+}
+
+/// Transposes the `m x n` matrix `a`.
+fn transpose(m: usize, n: usize, a: &[f32]) -> Vec<f32> {
+    let mut out = vec![0.0; m * n];
+    for i in 0..m {
+        for j in 0..n {
+            out[j * m + i] = a[i * n + j];
+        }
+    }
+    out
+}
+
+/// Multiplies the `m x k` matrix `a` with the `k x n` matrix `b`.
+fn mm(m: usize, k: usize, n: usize, a: &[f32], b: &[f32]) -> Vec<f32> {
+    let mut c = vec![0.0; m * n];
+    Cpu::mm_slices(m, k, n, a, b, &mut c);
+    c
+}
+
+fn inverse(a: &[f32], n: usize) -> Vec<f32> {
+    let mut lu = a.to_vec();
+    let mut pivots = vec![0; n];
+    lu_decompose(&mut lu, n, &mut pivots);
+    assert_invertible(&lu, n, "invert");
+    let mut inv = vec![0.0; n * n];
+    for i in 0..n {
+        inv[i * n + i] = 1.0;
+    }
+    lu_solve(&lu, &pivots, n, &mut inv, n);
+    inv
+}
+
+/// In place LU decomposition with partial pivoting. Row `i` was swapped with row `pivots[i]`
+/// at step `i`. `L` has an implicit unit diagonal. Returns the sign of the permutation.
+/// Singular matrices have a zero on the diagonal of `U`, see [assert_invertible()].
+#[cfg(not(feature = "cblas"))]
+fn lu_decompose(a: &mut [f32], n: usize, pivots: &mut [usize]) -> f32 {
+    let mut sign = 1.0;
+    for k in 0..n {
+        let mut p = k;
+        for i in k + 1..n {
+            if a[i * n + k].abs() > a[p * n + k].abs() {
+                p = i;
+            }
+        }
+        pivots[k] = p;
+        if p != k {
+            sign = -sign;
+            for j in 0..n {
+                a.swap(k * n + j, p * n + j);
+            }
+        }
+        let pivot = a[k * n + k];
+        if pivot == 0.0 {
+            // the rest of the column is already zero
+            continue;
+        }
+        for i in k + 1..n {
+            a[i * n + k] /= pivot;
+            let l = a[i * n + k];
+            for j in k + 1..n {
+                a[i * n + j] -= l * a[k * n + j];
+            }
+        }
+    }
+    sign
+}
+
+#[cfg(feature = "cblas")]
+fn lu_decompose(a: &mut [f32], n: usize, pivots: &mut [usize]) -> f32 {
+    if n == 0 {
+        return 1.0;
+    }
+    let mut ipiv = vec![0; n];
+    let n_ = n as libc::c_int;
+    let info = unsafe {
+        lapack::LAPACKE_sgetrf(
+            lapack::ROW_MAJOR,
+            n_,
+            n_,
+            a.as_mut_ptr(),
+            n_,
+            ipiv.as_mut_ptr(),
+        )
+    };
+    // info > 0 means U is singular, which is fine for the determinant
+    check_info("sgetrf", info);
+    let mut sign = 1.0;
+    for (i, p) in ipiv.iter().enumerate() {
+        pivots[i] = *p as usize - 1;
+        if pivots[i] != i {
+            sign = -sign;
+        }
+    }
+    sign
+}
+
+/// The transpose of the adjugate of `a`, which is the gradient of `det(a)`. This is
+/// `det(a) * a^{-T}` when `a` is invertible, but is also defined when it isn't. Computed
+/// from the svd `a = u * diag(s) * v^T` as `det(u) * det(v) * u * diag(c) * v^T`,
+/// where `c[k]` is the product of all the singular values except `s[k]`.
+fn adjugate_transpose(a: &[f32], n: usize) -> Vec<f32> {
+    let mut u = vec![0.0; n * n];
+    let mut s = vec![0.0; n];
+    let mut v = vec![0.0; n * n];
+    svd(a, &mut u, &mut s, &mut v, n);
+    let sign = orthogonal_det(&u, n) * orthogonal_det(&v, n);
+    let mut out = vec![0.0; n * n];
+    for k in 0..n {
+        let c: f32 = sign
+            * s.iter()
+                .enumerate()
+                .filter(|&(j, _)| j != k)
+                .map(|(_, s)| s)
+                .product::<f32>();
+        for i in 0..n {
+            for j in 0..n {
+                out[i * n + j] += c * u[i * n + k] * v[j * n + k];
+            }
+        }
+    }
+    out
+}
+
+/// The determinant of an orthogonal matrix, which is either `1.0` or `-1.0`.
+fn orthogonal_det(q: &[f32], n: usize) -> f32 {
+    let mut lu = q.to_vec();
+    let mut pivots = vec![0; n];
+    let sign = lu_decompose(&mut lu, n, &mut pivots);
+    (0..n).fold(sign, |det, i| det * lu[i * n + i]).signum()
+}
+
+/// **Panics** if the output of [lu_decompose] is singular, since the operation (e.g. `"invert"`)
+/// is undefined.
+fn assert_invertible(lu: &[f32], n: usize, op: &str) {
+    if (0..n).any(|i| lu[i * n + i] == 0.0) {
+        panic!("Tried to {op} a singular matrix");
+    }
+}
+
+/// Solves `a * x = b` in place using the output of [lu_decompose], where `b` is `n x k`.
+fn lu_solve(lu: &[f32], pivots: &[usize], n: usize, b: &mut [f32], k: usize) {
+    for (i, &p) in pivots.iter().enumerate() {
+        if p != i {
+            for c in 0..k {
+                b.swap(i * k + c, p * k + c);
+            }
+        }
+    }
+    for i in 0..n {
+        for j in 0..i {
+            let l = lu[i * n + j];
+            for c in 0..k {
+                b[i * k + c] -= l * b[j * k + c];
+            }
+        }
+    }
+    for i in (0..n).rev() {
+        for j in i + 1..n {
+            let u = lu[i * n + j];
+            for c in 0..k {
+                b[i * k + c] -= u * b[j * k + c];
+            }
+        }
+        let u = lu[i * n + i];
+        for c in 0..k {
+            b[i * k + c] /= u;
+        }
+    }
+}
+
+/// Solves `op(a) * x = b` in place, where `b` is `n x k`, `a` is lower triangular
+/// if `lower` (upper triangular otherwise), and `op(a)` is `a^T` if `transpose`.
+fn triangular_solve(a: &[f32], n: usize, b: &mut [f32], k: usize, lower: bool, transpose: bool) {
+    let at = |i: usize, j: usize| {
+        if transpose {
+            a[j * n + i]
+        } else {
+            a[i * n + j]
+        }
+    };
+    let forward = lower != transpose;
+    for step in 0..n {
+        let i = if forward { step } else { n - 1 - step };
+        let others: Box<dyn Iterator<Item = usize>> = if forward {
+            Box::new(0..i)
+        } else {
+            Box::new(i + 1..n)
+        };
+        for j in others {
+            let a_ij = at(i, j);
+            for c in 0..k {
+                b[i * k + c] -= a_ij * b[j * k + c];
+            }
+        }
+        let a_ii = at(i, i);
+        if a_ii == 0.0 {
+            panic!("Tried to solve with a singular triangular matrix");
+        }
+        for c in 0..k {
+            b[i * k + c] /= a_ii;
+        }
+    }
+}
+
+#[cfg(not(feature = "cblas"))]
+fn cholesky(a: &[f32], l: &mut [f32], n: usize) {
+    l.fill(0.0);
+    for j in 0..n {
+        let mut sum = a[j * n + j];
+        for k in 0..j {
+            sum -= l[j * n + k].powi(2);
+        }
+        if sum <= 0.0 || sum.is_nan() {
+            panic!("{NOT_POSITIVE_DEFINITE}");
+        }
+        let l_jj = sum.sqrt();
+        l[j * n + j] = l_jj;
+        for i in j + 1..n {
+            let mut sum = a[i * n + j];
+            for k in 0..j {
+                sum -= l[i * n + k] * l[j * n + k];
+            }
+            l[i * n + j] = sum / l_jj;
+        }
+    }
+}
+
+#[cfg(feature = "cblas")]
+fn cholesky(a: &[f32], l: &mut [f32], n: usize) {
+    l.copy_from_slice(a);
+    if n == 0 {
+        return;
+    }
+    let n_ = n as libc::c_int;
+    let info = unsafe {
+        lapack::LAPACKE_spotrf(
+            lapack::ROW_MAJOR,
+            b'L' as libc::c_char,
+            n_,
+            l.as_mut_ptr(),
+            n_,
+        )
+    };
+    check_info("spotrf", info);
+    if info > 0 {
+        panic!("{NOT_POSITIVE_DEFINITE}");
+    }
+    for i in 0..n {
+        for j in i + 1..n {
+            l[i * n + j] = 0.0;
+        }
+    }
+}
+
+const NOT_POSITIVE_DEFINITE: &str =
+    "Tried to compute the cholesky decomposition of a matrix that isn't positive definite";
+
+#[cfg(not(feature = "cblas"))]
+fn qr(a: &[f32], q: &mut [f32], r: &mut [f32], n: usize) {
+    r.copy_from_slice(a);
+    q.fill(0.0);
+    for i in 0..n {
+        q[i * n + i] = 1.0;
+    }
+    let mut v = vec![0.0; n];
+    for k in 0..n.saturating_sub(1) {
+        // householder vector that zeros r[k + 1.., k]
+        let norm = (k..n).map(|i| r[i * n + k].powi(2)).sum::<f32>().sqrt();
+        if norm == 0.0 {
+            continue;
+        }
+        let alpha = if r[k * n + k] > 0.0 { -norm } else { norm };
+        for i in k..n {
+            v[i] = r[i * n + k];
+        }
+        v[k] -= alpha;
+        let v_norm_sq = (k..n).map(|i| v[i].powi(2)).sum::<f32>();
+        if v_norm_sq == 0.0 {
+            continue;
+        }
+
+        // r = h * r, q = q * h, where h = I - 2 * v * v^T / (v^T * v)
+        for j in 0..n {
+            let dot = (k..n).map(|i| v[i] * r[i * n + j]).sum::<f32>();
+            let scale = 2.0 * dot / v_norm_sq;
+            for i in k..n {
+                r[i * n + j] -= scale * v[i];
+            }
+        }
+        for row in 0..n {
+            let dot = (k..n).map(|i| q[row * n + i] * v[i]).sum::<f32>();
+            let scale = 2.0 * dot / v_norm_sq;
+            for i in k..n {
+                q[row * n + i] -= scale * v[i];
+            }
+        }
+    }
+    make_r_unique(q, r, n);
+}
+
+#[cfg(feature = "cblas")]
+fn qr(a: &[f32], q: &mut [f32], r: &mut [f32], n: usize) {
+    if n == 0 {
+        return;
+    }
+    let n_ = n as libc::c_int;
+    let mut tau = vec![0.0; n];
+    q.copy_from_slice(a);
+    let info = unsafe {
+        lapack::LAPACKE_sgeqrf(
+            lapack::ROW_MAJOR,
+            n_,
+            n_,
+            q.as_mut_ptr(),
+            n_,
+            tau.as_mut_ptr(),
+        )
+    };
+    check_info("sgeqrf", info);
+    r.copy_from_slice(q);
+    let info = unsafe {
+        lapack::LAPACKE_sorgqr(
+            lapack::ROW_MAJOR,
+            n_,
+            n_,
+            n_,
+            q.as_mut_ptr(),
+            n_,
+            tau.as_ptr(),
+        )
+    };
+    check_info("sorgqr", info);
+    make_r_unique(q, r, n);
+}
+
+/// Zeros the lower triangle of `r`, and flips signs of rows of `r` and columns
+/// of `q` so the diagonal of `r` is non negative.
+fn make_r_unique(q: &mut [f32], r: &mut [f32], n: usize) {
+    for i in 0..n {
+        for j in 0..i {
+            r[i * n + j] = 0.0;
+        }
+        if r[i * n + i] < 0.0 {
+            for j in 0..n {
+                r[i * n + j] = -r[i * n + j];
+                q[j * n + i] = -q[j * n + i];
+            }
+        }
+    }
+}
+
+#[cfg(not(feature = "cblas"))]
+fn svd(a: &[f32], u: &mut [f32], s: &mut [f32], v: &mut [f32], n: usize) {
+    // one sided jacobi: rotate columns of w = a * v until they are orthogonal,
+    // then w = u * diag(s). Done in f64 for accuracy of the rotations.
+    let mut w: Vec<f64> = a.iter().map(|&x| x as f64).collect();
+    let mut v64 = vec![0.0f64; n * n];
+    for i in 0..n {
+        v64[i * n + i] = 1.0;
+    }
+    // columns smaller than this are numerically zero, and rotating them doesn't converge
+    let tiny = 1e-24 * w.iter().map(|x| x * x).sum::<f64>();
+    let mut converged = false;
+    for _sweep in 0..60 {
+        let mut rotated = false;
+        for p in 0..n {
+            for q in p + 1..n {
+                let (mut alpha, mut beta, mut gamma) = (0.0, 0.0, 0.0);
+                for i in 0..n {
+                    alpha += w[i * n + p].powi(2);
+                    beta += w[i * n + q].powi(2);
+                    gamma += w[i * n + p] * w[i * n + q];
+                }
+                if gamma.abs() <= 1e-15 * (alpha * beta).sqrt()
+                    || gamma == 0.0
+                    || alpha.min(beta) <= tiny
+                {
+                    continue;
+                }
+                rotated = true;
+                let zeta = (beta - alpha) / (2.0 * gamma);
+                let t = zeta.signum() / (zeta.abs() + (1.0 + zeta * zeta).sqrt());
+                let c = (1.0 + t * t).sqrt().recip();
+                let s = c * t;
+                for m in [&mut w, &mut v64] {
+                    for i in 0..n {
+                        let (x, y) = (m[i * n + p], m[i * n + q]);
+                        m[i * n + p] = c * x - s * y;
+                        m[i * n + q] = s * x + c * y;
+                    }
+                }
+            }
+        }
+        if !rotated {
+            converged = true;
+            break;
+        }
+    }
+    if !converged {
+        panic!("{SVD_NOT_CONVERGED}");
+    }
+
+    let norms: Vec<f64> = (0..n)
+        .map(|j| (0..n).map(|i| w[i * n + j].powi(2)).sum::<f64>())
+        .map(|norm2| if norm2 > tiny { norm2.sqrt() } else { 0.0 })
+        .collect();
+    let mut order: Vec<usize> = (0..n).collect();
+    order.sort_by(|&i, &j| norms[j].total_cmp(&norms[i]));
+    let mut u64 = vec![0.0f64; n * n];
+    for (dst, &src) in order.iter().enumerate() {
+        s[dst] = norms[src] as f32;
+        for i in 0..n {
+            if norms[src] > 0.0 {
+                u64[i * n + dst] = w[i * n + src] / norms[src];
+            }
+            v[i * n + dst] = v64[i * n + src] as f32;
+        }
+    }
+
+    // the columns of u for zero singular values are any orthonormal completion, so use the
+    // standard basis vector that is the least parallel to the columns so far
+    let rank = norms.iter().filter(|&&norm| norm > 0.0).count();
+    for dst in rank..n {
+        let mut best = (vec![0.0f64; n], 0.0);
+        for e in 0..n {
+            let mut col = vec![0.0f64; n];
+            col[e] = 1.0;
+            for k in 0..dst {
+                let dot = u64[e * n + k];
+                for (i, c) in col.iter_mut().enumerate() {
+                    *c -= dot * u64[i * n + k];
+                }
+            }
+            let norm = col.iter().map(|c| c * c).sum::<f64>().sqrt();
+            if norm > best.1 {
+                best = (col, norm);
+            }
+        }
+        let (col, norm) = best;
+        for (i, c) in col.iter().enumerate() {
+            u64[i * n + dst] = c / norm;
+        }
+    }
+    for (u, x) in u.iter_mut().zip(u64.iter()) {
+        *u = *x as f32;
+    }
+}
+
+#[cfg(feature = "cblas")]
+fn svd(a: &[f32], u: &mut [f32], s: &mut [f32], v: &mut [f32], n: usize) {
+    if n == 0 {
+        return;
+    }
+    let n_ = n as libc::c_int;
+    let mut a = a.to_vec();
+    let mut v_t = vec![0.0; n * n];
+    let mut superb = vec![0.0; n];
+    let all = b'A' as libc::c_char;
+    let info = unsafe {
+        lapack::LAPACKE_sgesvd(
+            lapack::ROW_MAJOR,
+            all,
+            all,
+            n_,
+            n_,
+            a.as_mut_ptr(),
+            n_,
+            s.as_mut_ptr(),
+            u.as_mut_ptr(),
+            n_,
+            v_t.as_mut_ptr(),
+            n_,
+            superb.as_mut_ptr(),
+        )
+    };
+    check_info("sgesvd", info);
+    if info > 0 {
+        panic!("{SVD_NOT_CONVERGED}");
+    }
+    v.copy_from_slice(&transpose(n, n, &v_t));
+}
+
+const SVD_NOT_CONVERGED: &str = "Tried to compute the SVD of a matrix, but it didn't converge. \
+    This usually means the matrix contains NaN or infinity";
+
+/// **Panics** if a LAPACK routine reports an illegal argument (`info < 0`), which is a bug.
+/// `info > 0` has a different meaning for each routine, so it's checked by the caller.
+#[cfg(feature = "cblas")]
+fn check_info(routine: &str, info: libc::c_int) {
+    assert!(
+        info >= 0,
+        "LAPACK {routine} got an illegal value for argument {}",
+        -info
+    );
+}
+
+#[cfg(feature = "cblas")]
+mod lapack {
+    use libc::{c_char, c_int};
+
+    pub const ROW_MAJOR: c_int = 101;
+
+    extern "C" {
+        pub fn LAPACKE_sgetrf(
+            layout: c_int,
+            m: c_int,
+            n: c_int,
+            a: *mut f32,
+            lda: c_int,
+            ipiv: *mut c_int,
+        ) -> c_int;
+        pub fn LAPACKE_spotrf(
+            layout: c_int,
+            uplo: c_char,
+            n: c_int,
+            a: *mut f32,
+            lda: c_int,
+        ) -> c_int;
+        pub fn LAPACKE_sgeqrf(
+            layout: c_int,
+            m: c_int,
+            n: c_int,
+            a: *mut f32,
+            lda: c_int,
+            tau: *mut f32,
+        ) -> c_int;
+        pub fn LAPACKE_sorgqr(
+            layout: c_int,
+            m: c_int,
+            n: c_int,
+            k: c_int,
+            a: *mut f32,
+            lda: c_int,
+            tau: *const f32,
+        ) -> c_int;
+        #[allow(clippy::too_many_arguments)]
+        pub fn LAPACKE_sgesvd(
+            layout: c_int,
+            jobu: c_char,
+            jobvt: c_char,
+            m: c_int,
+            n: c_int,
+            a: *mut f32,
+            lda: c_int,
+            s: *mut f32,
+            u: *mut f32,
+            ldu: c_int,
+            vt: *mut f32,
+            ldvt: c_int,
+            superb: *mut f32,
+        ) -> c_int;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::devices::MatMul;
+    use crate::tests::assert_close;
+
+    const A: [[f32; 3]; 3] = [[4.0, -2.0, 1.0], [3.0, 6.0, -4.0], [2.0, 1.0, 8.0]];
+
+    /// symmetric positive definite
+    const SPD: [[f32; 3]; 3] = [[4.0, 2.0, 0.4], [2.0, 5.0, 1.0], [0.4, 1.0, 3.0]];
+
+    fn matmul(a: &[[f32; 3]; 3], b: &[[f32; 3]; 3]) -> [[f32; 3]; 3] {
+        let mut c = [[0.0; 3]; 3];
+        Cpu::mm(a, b, &mut c);
+        c
+    }
+
+    fn transposed(a: &[[f32; 3]; 3]) -> [[f32; 3]; 3] {
+        let mut t = [[0.0; 3]; 3];
+        for i in 0..3 {
+            for j in 0..3 {
+                t[j][i] = a[i][j];
+            }
+        }
+        t
+    }
+
+    const EYE: [[f32; 3]; 3] = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+
+    #[test]
+    fn test_inverse_and_det() {
+        let mut inv = [[0.0; 3]; 3];
+        Cpu::inverse(&A, &mut inv);
+        assert_close(&matmul(&A, &inv), &EYE);
+
+        let mut det = 0.0;
+        Cpu::det(&A, &mut det);
+        assert!((det - 263.0).abs() < 1e-3);
+
+        let mut logdet = 0.0;
+        Cpu::logdet(&A, &mut logdet);
+        assert!((logdet - 263.0f32.ln()).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_batched_det_sign() {
+        let a = [A, [[0.0, 1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, 2.0]]];
+        let mut det = [0.0; 2];
+        Cpu::det(&a, &mut det);
+        assert!((det[0] - 263.0).abs() < 1e-3);
+        assert_eq!(det[1], -2.0);
+    }
+
+    const SINGULAR: [[f32; 3]; 3] = [[1.0, 2.0, 3.0], [0.0, 0.0, 0.0], [2.0, 1.0, 0.0]];
+
+    #[test]
+    fn test_singular_det() {
+        let mut det = 1.0;
+        Cpu::det(&SINGULAR, &mut det);
+        assert_eq!(det, 0.0);
+
+        let mut logdet = 0.0;
+        Cpu::logdet(&SINGULAR, &mut logdet);
+        assert_eq!(logdet, f32::NEG_INFINITY);
+    }
+
+    #[test]
+    fn test_singular_det_backward() {
+        let mut a_grad = [[0.0; 3]; 3];
+        Cpu::det_backward(&SINGULAR, &0.0, &2.0, &mut a_grad);
+        assert_close(&a_grad, &[[0.0; 3], [6.0, -12.0, 6.0], [0.0; 3]]);
+
+        let mut a_grad = [[0.0; 3]; 3];
+        let mut det = 0.0;
+        Cpu::det(&A, &mut det);
+        let mut inv = [[0.0; 3]; 3];
+        Cpu::inverse(&A, &mut inv);
+        Cpu::det_backward(&A, &det, &1.0, &mut a_grad);
+        let expected = transposed(&inv).map(|r| r.map(|x| x * det));
+        let adj_t = adjugate_transpose(&A.concat(), 3);
+        for (adj, e) in adj_t.iter().zip(expected.concat()) {
+            assert!((adj - e).abs() < 1e-3, "{adj} != {e}");
+        }
+        assert_close(&a_grad, &expected);
+    }
+
+    #[test]
+    #[should_panic = "Tried to invert a singular matrix"]
+    fn test_singular_inverse() {
+        Cpu::inverse(&SINGULAR, &mut [[0.0; 3]; 3]);
+    }
+
+    #[test]
+    #[should_panic = "Tried to solve with a singular matrix"]
+    fn test_singular_solve() {
+        Cpu::solve(&SINGULAR, &[1.0, 2.0, 3.0], &mut [0.0; 3]);
+    }
+
+    #[test]
+    #[should_panic = "matrix that isn't positive definite"]
+    fn test_cholesky_not_positive_definite() {
+        let a = [[1.0, 2.0, 0.0], [2.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+        Cpu::cholesky(&a, &mut [[0.0; 3]; 3]);
+    }
+
+    #[test]
+    #[should_panic = "didn't converge"]
+    fn test_svd_nan() {
+        let mut a = A;
+        a[1][1] = f32::NAN;
+        Cpu::svd(&a, &mut [[0.0; 3]; 3], &mut [0.0; 3], &mut [[0.0; 3]; 3]);
+    }
+
+    #[test]
+    fn test_cholesky() {
+        let mut l = [[0.0; 3]; 3];
+        Cpu::cholesky(&SPD, &mut l);
+        assert_eq!(l[0][1], 0.0);
+        assert_eq!(l[0][2], 0.0);
+        assert_eq!(l[1][2], 0.0);
+        assert_close(&matmul(&l, &transposed(&l)), &SPD);
+    }
+
+    #[test]
+    fn test_solves() {
+        let b = [[1.0, 2.0], [3.0, 4.0], [5.0, 6.0]];
+        let mut x = [[0.0; 2]; 3];
+        Cpu::solve(&A, &b, &mut x);
+        let mut ax = [[0.0; 2]; 3];
+        Cpu::mm(&A, &x, &mut ax);
+        assert_close(&ax, &b);
+
+        let mut l = [[0.0; 3]; 3];
+        Cpu::cholesky(&SPD, &mut l);
+        let mut x = [[0.0; 2]; 3];
+        Cpu::triangular_solve(&l, &b, &mut x, true);
+        let mut lx = [[0.0; 2]; 3];
+        Cpu::mm(&l, &x, &mut lx);
+        assert_close(&lx, &b);
+    }
+
+    #[test]
+    fn test_qr() {
+        let mut q = [[0.0; 3]; 3];
+        let mut r = [[0.0; 3]; 3];
+        Cpu::qr(&A, &mut q, &mut r);
+        assert_close(&matmul(&q, &r), &A);
+        assert_close(&matmul(&transposed(&q), &q), &EYE);
+        for (i, row) in r.iter().enumerate() {
+            assert!(row[i] >= 0.0);
+            assert!(row[..i].iter().all(|x| x == &0.0));
+        }
+    }
+
+    #[test]
+    fn test_svd() {
+        check_svd(&A);
+    }
+
+    #[test]
+    fn test_svd_singular() {
+        check_svd(&SINGULAR);
+        check_svd(&[[1.0, 2.0, 3.0], [2.0, 4.0, 6.0], [-1.0, -2.0, -3.0]]);
+        check_svd(&[[0.0; 3]; 3]);
+    }
+
+    fn check_svd(a: &[[f32; 3]; 3]) {
+        let mut u = [[0.0; 3]; 3];
+        let mut s = [0.0; 3];
+        let mut v = [[0.0; 3]; 3];
+        Cpu::svd(a, &mut u, &mut s, &mut v);
+        assert!(s[0] >= s[1] && s[1] >= s[2]);
+        let mut us = u;
+        for row in us.iter_mut() {
+            for j in 0..3 {
+                row[j] *= s[j];
+            }
+        }
+        let usv = matmul(&us, &transposed(&v));
+        for i in 0..3 {
+            for j in 0..3 {
+                assert!((usv[i][j] - a[i][j]).abs() < 1e-4);
+            }
+        }
+        assert_close(&matmul(&transposed(&u), &u), &EYE);
+        assert_close(&matmul(&transposed(&v), &v), &EYE);
+    }
+}
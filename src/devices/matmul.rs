@@ -227,6 +227,48 @@ impl<A: Transpose, B: Transpose, C: Transpose> MatMulOp<A, B, C> for Cpu where
 }
 
 impl Cpu {
+    /// matrix multiply `c += a * b` where `a` is `m x k`, `b` is `k x n`, and `c` is `m x n`,
+    /// all stored in row major order.
+    pub(crate) fn mm_slices(m: usize, k: usize, n: usize, a: &[f32], b: &[f32], c: &mut [f32]) {
+        assert_eq!(a.len(), m * k);
+        assert_eq!(b.len(), k * n);
+        assert_eq!(c.len(), m * n);
+        if m == 0 || n == 0 {
+            return;
+        }
+        let a = a.as_ptr();
+        let b = b.as_ptr();
+        let c = c.as_mut_ptr();
+
+        #[cfg(not(feature = "cblas"))]
+        unsafe {
+            matrixmultiply::sgemm(
+                m, k, n, 1.0, a, k as isize, 1, b, n as isize, 1, 1.0, c, n as isize, 1,
+            )
+        }
+
+        #[cfg(feature = "cblas")]
+        unsafe {
+            let (m, n, k) = (m as libc::c_int, n as libc::c_int, k as libc::c_int);
+            sgemm(
+                RowMajor,
+                NoTr,
+                NoTr,
+                m,
+                n,
+                k,
+                1.0,
+                a,
+                k.max(1),
+                b,
+                n,
+                1.0,
+                c,
+                n,
+            )
+        }
+    }
+
     /// vector matrix multiply `c += a * b`
     pub fn vm<const K: usize, const N: usize>(a: &[f32; K], b: &[[f32; N]; K], c: &mut [f32; N]) {
         let a = a.as_ptr();
@@ -0,0 +1,122 @@
+use super::{as_mut_slice, as_slice, Cpu};
+use crate::arrays::{CountElements, HasShape};
+
+/// Operations on the last two axes of nd arrays, which are treated as a batch of matrices.
+///
+/// `V` are arrays with one vector per matrix (e.g. `[f32; N]` for `[[f32; N]; N]`),
+/// and `S` are arrays with one value per matrix (e.g. `f32` for `[[f32; N]; N]`).
+pub trait DeviceMatrix<T> {
+    /// Zeros every element of each matrix outside of the triangle. Element `[i, j]` is
+    /// kept if `j - i >= offset` when `upper`, and if `j - i <= offset` otherwise.
+    fn triangle(t: &mut T, offset: isize, upper: bool);
+
+    /// `out[.., i] += t[.., i, i]`
+    fn diag_add<V: CountElements<Dtype = f32>>(t: &T, out: &mut V);
+
+    /// `out[.., i, i] += v[.., i]`
+    fn diag_embed_add<V: CountElements<Dtype = f32>>(v: &V, out: &mut T);
+
+    /// `out[..] += sum_i t[.., i, i]`
+    fn trace_add<S: CountElements<Dtype = f32>>(t: &T, out: &mut S);
+
+    /// `t_grad[.., i, i] += out_grad[..]`
+    fn trace_backward<S: CountElements<Dtype = f32>>(out_grad: &S, t_grad: &mut T);
+}
+
+impl<T: CountElements, const M: usize, const N: usize> DeviceMatrix<[[T; N]; M]> for Cpu
+where
+    [[T; N]; M]: CountElements<Dtype = f32> + HasShape,
+{
+    fn triangle(t: &mut [[T; N]; M], offset: isize, upper: bool) {
+        let (rows, cols) = matrix_shape::<[[T; N]; M]>();
+        for (i, x) in as_mut_slice(t).iter_mut().enumerate() {
+            let diff = ((i % cols) as isize) - ((i / cols % rows) as isize);
+            if (upper && diff < offset) || (!upper && diff > offset) {
+                *x = 0.0;
+            }
+        }
+    }
+
+    fn diag_add<V: CountElements<Dtype = f32>>(t: &[[T; N]; M], out: &mut V) {
+        let t = as_slice(t);
+        let out = as_mut_slice(out);
+        foreach_diagonal::<[[T; N]; M], _>(&mut |_, i, j| out[i] += t[j]);
+    }
+
+    fn diag_embed_add<V: CountElements<Dtype = f32>>(v: &V, out: &mut [[T; N]; M]) {
+        let v = as_slice(v);
+        let out = as_mut_slice(out);
+        foreach_diagonal::<[[T; N]; M], _>(&mut |_, i, j| out[j] += v[i]);
+    }
+
+    fn trace_add<S: CountElements<Dtype = f32>>(t: &[[T; N]; M], out: &mut S) {
+        let t = as_slice(t);
+        let out = as_mut_slice(out);
+        foreach_diagonal::<[[T; N]; M], _>(&mut |b, _, j| out[b] += t[j]);
+    }
+
+    fn trace_backward<S: CountElements<Dtype = f32>>(out_grad: &S, t_grad: &mut [[T; N]; M]) {
+        let out_grad = as_slice(out_grad);
+        let t_grad = as_mut_slice(t_grad);
+        foreach_diagonal::<[[T; N]; M], _>(&mut |b, _, j| t_grad[j] += out_grad[b]);
+    }
+}
+
+/// The number of rows and columns of each matrix in `T`.
+fn matrix_shape<T: HasShape>() -> (usize, usize) {
+    let shape = T::SHAPE;
+    (shape[shape.len() - 2], shape[shape.len() - 1])
+}
+
+/// Calls `f(b, i, j)` for every diagonal element of every matrix in `T`, where `b` is the
+/// index of the matrix, `i` is the flat index of the element in the batch of diagonals,
+/// and `j` is the flat index of the element in `T`.
+fn foreach_diagonal<T: CountElements + HasShape, F: FnMut(usize, usize, usize)>(f: &mut F) {
+    let (rows, cols) = matrix_shape::<T>();
+    let size = rows * cols;
+    if size == 0 {
+        return;
+    }
+    let len = rows.min(cols);
+    for b in 0..T::NUM_ELEMENTS / size {
+        for i in 0..len {
+            f(b, b * len + i, b * size + i * cols + i);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_triangle() {
+        let mut t = [[1.0; 3]; 2];
+        Cpu::triangle(&mut t, 0, true);
+        assert_eq!(t, [[1.0, 1.0, 1.0], [0.0, 1.0, 1.0]]);
+
+        let mut t = [[[1.0; 3]; 3]; 2];
+        Cpu::triangle(&mut t, -1, false);
+        let expected = [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [1.0, 1.0, 0.0]];
+        assert_eq!(t, [expected; 2]);
+    }
+
+    #[test]
+    fn test_diagonals() {
+        let t = [[[1.0, 2.0], [3.0, 4.0]], [[5.0, 6.0], [7.0, 8.0]]];
+        let mut d = [[0.0; 2]; 2];
+        Cpu::diag_add(&t, &mut d);
+        assert_eq!(d, [[1.0, 4.0], [5.0, 8.0]]);
+
+        let mut s = [0.0; 2];
+        Cpu::trace_add(&t, &mut s);
+        assert_eq!(s, [5.0, 13.0]);
+
+        let mut e = [[[0.0; 2]; 2]; 2];
+        Cpu::diag_embed_add(&d, &mut e);
+        assert_eq!(e, [[[1.0, 0.0], [0.0, 4.0]], [[5.0, 0.0], [0.0, 8.0]]]);
+
+        Cpu::trace_backward(&[1.0, -1.0], &mut e);
+        assert_eq!(e, [[[2.0, 0.0], [0.0, 5.0]], [[4.0, 0.0], [0.0, 7.0]]]);
+    }
+}
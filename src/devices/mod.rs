@@ -2,18 +2,30 @@
 
 mod allocate;
 mod broadcast_reduce;
+mod einsum;
+mod fft;
 mod fill;
 mod foreach;
+mod linalg;
 mod matmul;
+mod matrix;
 mod permute;
+mod resize;
+mod scan;
 mod select;
 
 pub use allocate::*;
 pub use broadcast_reduce::*;
+pub use einsum::*;
+pub use fft::*;
 pub use fill::*;
 pub use foreach::*;
+pub use linalg::*;
 pub use matmul::*;
+pub use matrix::*;
 pub use permute::*;
+pub use resize::*;
+pub use scan::*;
 pub use select::*;
 
 #[cfg(feature = "nightly")]
@@ -30,6 +42,16 @@ use std::ops::*;
 /// The CPU device
 pub struct Cpu;
 
+/// Views the nd array `t` as a flat slice of its elements.
+pub(crate) fn as_slice<T: crate::arrays::CountElements<Dtype = f32>>(t: &T) -> &[f32] {
+    unsafe { std::slice::from_raw_parts(t as *const T as *const f32, T::NUM_ELEMENTS) }
+}
+
+/// Views the nd array `t` as a flat mutable slice of its elements.
+pub(crate) fn as_mut_slice<T: crate::arrays::CountElements<Dtype = f32>>(t: &mut T) -> &mut [f32] {
+    unsafe { std::slice::from_raw_parts_mut(t as *mut T as *mut f32, T::NUM_ELEMENTS) }
+}
+
 /// Represents something that can act on `T`.
 pub trait Device<T: crate::arrays::CountElements>:
     FillElements<T> + DeviceReduce<T, crate::arrays::AllAxes> + AllocateZeros + ForEachElement<T>
@@ -65,15 +87,45 @@ pub trait Device<T: crate::arrays::CountElements>:
     {
         Self::foreach_mrr(out, lhs, rhs, &mut |o, l, r| o.add_assign(l * r))
     }
+
+    /// Returns the flat index and value of the first element of `t` that is NaN or infinite.
+    fn first_non_finite(t: &T) -> Option<(usize, f32)>;
+}
+
+fn find_non_finite<T: crate::arrays::CountElements<Dtype = f32>>(t: &T) -> Option<(usize, f32)> {
+    as_slice(t)
+        .iter()
+        .copied()
+        .enumerate()
+        .find(|(_, x)| !x.is_finite())
 }
 
-impl Device<f32> for Cpu {}
-impl<const M: usize> Device<[f32; M]> for Cpu {}
-impl<const M: usize, const N: usize> Device<[[f32; N]; M]> for Cpu {}
-impl<const M: usize, const N: usize, const O: usize> Device<[[[f32; O]; N]; M]> for Cpu {}
+impl Device<f32> for Cpu {
+    fn first_non_finite(t: &f32) -> Option<(usize, f32)> {
+        find_non_finite(t)
+    }
+}
+impl<const M: usize> Device<[f32; M]> for Cpu {
+    fn first_non_finite(t: &[f32; M]) -> Option<(usize, f32)> {
+        find_non_finite(t)
+    }
+}
+impl<const M: usize, const N: usize> Device<[[f32; N]; M]> for Cpu {
+    fn first_non_finite(t: &[[f32; N]; M]) -> Option<(usize, f32)> {
+        find_non_finite(t)
+    }
+}
+impl<const M: usize, const N: usize, const O: usize> Device<[[[f32; O]; N]; M]> for Cpu {
+    fn first_non_finite(t: &[[[f32; O]; N]; M]) -> Option<(usize, f32)> {
+        find_non_finite(t)
+    }
+}
 impl<const M: usize, const N: usize, const O: usize, const P: usize> Device<[[[[f32; P]; O]; N]; M]>
     for Cpu
 {
+    fn first_non_finite(t: &[[[[f32; P]; O]; N]; M]) -> Option<(usize, f32)> {
+        find_non_finite(t)
+    }
 }
 
 /// A [crate::arrays::HasArrayType] that has a [Device] for its [crate::arrays::HasArrayType::Array]
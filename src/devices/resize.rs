@@ -0,0 +1,185 @@
+//! Resizing the last two axes (height & width) of nd arrays with separable filters.
+//!
+//! Every resize is described by a list of [Taps] for each axis: output index `o` along an axis
+//! is the weighted sum of the input elements `taps[o]`. The 2d resize applies the width taps
+//! to every row, then the height taps to every column.
+//!
+//! The backward functions accumulate into the gradients (`+=`), while the forward functions
+//! overwrite their outputs.
+
+use super::{as_mut_slice, as_slice, Cpu};
+use crate::arrays::{CountElements, HasShape};
+
+/// The `(input index, weight)` pairs for each output index along an axis.
+pub type Taps = Vec<Vec<(usize, f32)>>;
+
+/// Resizes the last two axes of `T` into the last two axes of `R`. All other axes must be the same.
+pub trait DeviceResize2D<T, R> {
+    fn resize2d(inp: &T, out: &mut R, rows: &Taps, cols: &Taps);
+    fn resize2d_backward(out_grad: &R, inp_grad: &mut T, rows: &Taps, cols: &Taps);
+}
+
+impl<T, R> DeviceResize2D<T, R> for Cpu
+where
+    T: CountElements<Dtype = f32> + HasShape,
+    R: CountElements<Dtype = f32> + HasShape,
+{
+    fn resize2d(inp: &T, out: &mut R, rows: &Taps, cols: &Taps) {
+        let (h, w) = image_size::<T>();
+        let (oh, ow) = image_size::<R>();
+        let mut tmp = vec![0.0; h * ow];
+        let images = as_slice(inp).chunks(h * w);
+        for (inp, out) in images.zip(as_mut_slice(out).chunks_mut(oh * ow)) {
+            for y in 0..h {
+                for (ox, taps) in cols.iter().enumerate() {
+                    tmp[y * ow + ox] = taps.iter().map(|&(x, c)| c * inp[y * w + x]).sum();
+                }
+            }
+            for (oy, taps) in rows.iter().enumerate() {
+                for ox in 0..ow {
+                    out[oy * ow + ox] = taps.iter().map(|&(y, c)| c * tmp[y * ow + ox]).sum();
+                }
+            }
+        }
+    }
+
+    fn resize2d_backward(out_grad: &R, inp_grad: &mut T, rows: &Taps, cols: &Taps) {
+        let (h, w) = image_size::<T>();
+        let (oh, ow) = image_size::<R>();
+        let images = as_slice(out_grad).chunks(oh * ow);
+        for (out_grad, inp_grad) in images.zip(as_mut_slice(inp_grad).chunks_mut(h * w)) {
+            let mut tmp = vec![0.0; h * ow];
+            for (oy, taps) in rows.iter().enumerate() {
+                for ox in 0..ow {
+                    for &(y, c) in taps.iter() {
+                        tmp[y * ow + ox] += c * out_grad[oy * ow + ox];
+                    }
+                }
+            }
+            for y in 0..h {
+                for (ox, taps) in cols.iter().enumerate() {
+                    for &(x, c) in taps.iter() {
+                        inp_grad[y * w + x] += c * tmp[y * ow + ox];
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// The sizes of the last two axes of `T`.
+pub(crate) fn image_size<T: HasShape>() -> (usize, usize) {
+    let shape = T::SHAPE;
+    (shape[shape.len() - 2], shape[shape.len() - 1])
+}
+
+/// The coordinate in the input that output index `o` is sampled from.
+fn source_coord(o: usize, inp: usize, out: usize, align_corners: bool) -> f32 {
+    if align_corners {
+        if out > 1 {
+            o as f32 * (inp - 1) as f32 / (out - 1) as f32
+        } else {
+            0.0
+        }
+    } else {
+        (o as f32 + 0.5) * inp as f32 / out as f32 - 0.5
+    }
+}
+
+/// Copies the input element at `floor(o * inp / out)`.
+pub fn nearest_taps(inp: usize, out: usize) -> Taps {
+    (0..out)
+        .map(|o| vec![(((o * inp) / out).min(inp - 1), 1.0)])
+        .collect()
+}
+
+/// Linear interpolation between the two nearest input elements.
+pub fn linear_taps(inp: usize, out: usize, align_corners: bool) -> Taps {
+    (0..out)
+        .map(|o| {
+            let src = source_coord(o, inp, out, align_corners).max(0.0);
+            let i0 = (src.floor() as usize).min(inp - 1);
+            let i1 = (i0 + 1).min(inp - 1);
+            let t = src - i0 as f32;
+            vec![(i0, 1.0 - t), (i1, t)]
+        })
+        .collect()
+}
+
+/// Cubic convolution with the 4 nearest input elements, using `a = -0.75` like pytorch.
+/// Indices outside of the input are clamped to the border.
+pub fn cubic_taps(inp: usize, out: usize, align_corners: bool) -> Taps {
+    const A: f32 = -0.75;
+    fn near(x: f32) -> f32 {
+        ((A + 2.0) * x - (A + 3.0)) * x * x + 1.0
+    }
+    fn far(x: f32) -> f32 {
+        ((A * x - 5.0 * A) * x + 8.0 * A) * x - 4.0 * A
+    }
+    (0..out)
+        .map(|o| {
+            let src = source_coord(o, inp, out, align_corners);
+            let i = src.floor();
+            let t = src - i;
+            let weights = [far(t + 1.0), near(t), near(1.0 - t), far(2.0 - t)];
+            weights
+                .iter()
+                .enumerate()
+                .map(|(k, &c)| {
+                    let idx = (i as isize + k as isize - 1).clamp(0, inp as isize - 1);
+                    (idx as usize, c)
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// The average of the input elements in `[floor(o * inp / out), ceil((o + 1) * inp / out))`.
+pub fn adaptive_avg_taps(inp: usize, out: usize) -> Taps {
+    (0..out)
+        .map(|o| {
+            let start = (o * inp) / out;
+            let end = ((o + 1) * inp).div_ceil(out);
+            let c = 1.0 / (end - start) as f32;
+            (start..end).map(|i| (i, c)).collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_taps_sum_to_one() {
+        for (inp, out) in [(1, 3), (4, 2), (3, 7), (5, 5)] {
+            let taps = [
+                nearest_taps(inp, out),
+                linear_taps(inp, out, false),
+                linear_taps(inp, out, true),
+                cubic_taps(inp, out, false),
+                cubic_taps(inp, out, true),
+                adaptive_avg_taps(inp, out),
+            ];
+            for taps in taps.iter().flatten() {
+                assert!(taps.iter().all(|&(i, _)| i < inp));
+                let sum: f32 = taps.iter().map(|t| t.1).sum();
+                assert!((sum - 1.0).abs() < 1e-6);
+            }
+        }
+    }
+
+    #[test]
+    fn test_resize2d() {
+        let inp = [[[1.0, 2.0], [3.0, 4.0]]];
+        let mut out = [[[0.0; 3]; 3]];
+        let rows = linear_taps(2, 3, true);
+        let cols = linear_taps(2, 3, true);
+        <Cpu as DeviceResize2D<_, _>>::resize2d(&inp, &mut out, &rows, &cols);
+        assert_eq!(out, [[[1.0, 1.5, 2.0], [2.0, 2.5, 3.0], [3.0, 3.5, 4.0]]]);
+
+        let mut grad = [[[0.0; 2]; 2]];
+        <Cpu as DeviceResize2D<_, _>>::resize2d_backward(&[[[1.0; 3]; 3]], &mut grad, &rows, &cols);
+        assert_eq!(grad, [[[2.25; 2]; 2]]);
+    }
+}
@@ -0,0 +1,235 @@
+//! Implementations of cumulative operations (scans) along a single axis of an nd array.
+//!
+//! The same description of an axis is also used to remap indices along it, which implements
+//! flipping, rolling and repeating.
+//!
+//! Every nd array is stored contiguously, so a single axis can be described by three numbers:
+//! 1. `OUTER`: the product of the sizes of all axes before it
+//! 2. `SIZE`: the size of the axis itself
+//! 3. `INNER`: the product of the sizes of all axes after it (i.e. the stride of the axis)
+//!
+//! Each of the `OUTER * INNER` "lanes" along the axis is then scanned independently.
+
+use super::{as_mut_slice, as_slice, AllocateZeros, Cpu};
+use crate::arrays::{Axis, CountElements};
+
+/// Cumulative sums & products of `T` along `Axes`, and remapping indices along `Axes`.
+pub trait DeviceScan<T: CountElements<Dtype = f32>, Axes>: AllocateZeros {
+    /// Product of the sizes of the axes before the scanned axis.
+    const OUTER: usize;
+
+    /// Size of the scanned axis.
+    const SIZE: usize;
+
+    /// Product of the sizes of the axes after the scanned axis.
+    const INNER: usize;
+
+    /// Computes `out[i] = inp[i] + discount * out[i - 1]` along the axis.
+    /// If `reverse` is true, the scan starts at the end of the axis instead, i.e.
+    /// `out[i] = inp[i] + discount * out[i + 1]`.
+    fn cumsum(out: &mut T, inp: &T, discount: f32, reverse: bool) {
+        let inp = as_slice(inp);
+        let out = as_mut_slice(out);
+        foreach_lane::<Self, T, Axes, _>(reverse, &mut |lane| {
+            let mut acc = 0.0;
+            for &i in lane {
+                acc = inp[i] + discount * acc;
+                out[i] = acc;
+            }
+        });
+    }
+
+    /// Computes `out[i] = inp[i] * out[i - 1]` along the axis. If `reverse` is true,
+    /// the scan starts at the end of the axis.
+    fn cumprod(out: &mut T, inp: &T, reverse: bool) {
+        let inp = as_slice(inp);
+        let out = as_mut_slice(out);
+        foreach_lane::<Self, T, Axes, _>(reverse, &mut |lane| {
+            let mut acc = 1.0;
+            for &i in lane {
+                acc *= inp[i];
+                out[i] = acc;
+            }
+        });
+    }
+
+    /// Accumulates the gradient of [DeviceScan::cumprod()] into `inp_grad`, given the
+    /// gradient of the output `out_grad`.
+    ///
+    /// This does not divide by `inp`, so it is correct even if `inp` contains zeros.
+    fn cumprod_backward(inp_grad: &mut T, inp: &T, out_grad: &T, reverse: bool) {
+        let inp = as_slice(inp);
+        let out_grad = as_slice(out_grad);
+        let inp_grad = as_mut_slice(inp_grad);
+        let mut suffix = vec![0.0; Self::SIZE];
+        foreach_lane::<Self, T, Axes, _>(reverse, &mut |lane| {
+            // suffix[k] = sum_{j >= k} out_grad[j] * prod_{k < l <= j} inp[l]
+            let mut acc = 0.0;
+            for (k, &i) in lane.iter().enumerate().rev() {
+                suffix[k] = out_grad[i] + acc;
+                acc = suffix[k] * inp[i];
+            }
+            // prefix = prod_{l < k} inp[l]
+            let mut prefix = 1.0;
+            for (k, &i) in lane.iter().enumerate() {
+                inp_grad[i] += prefix * suffix[k];
+                prefix *= inp[i];
+            }
+        });
+    }
+
+    /// Computes `out[.., j, ..] += inp[.., idx(j), ..]` along the axis, where `R` is `T` with
+    /// a different size along the axis.
+    fn gather_add<R, F>(out: &mut R, inp: &T, mut idx: F)
+    where
+        R: CountElements<Dtype = f32>,
+        Self: DeviceScan<R, Axes>,
+        F: FnMut(usize) -> usize,
+    {
+        let inp = as_slice(inp);
+        let out = as_mut_slice(out);
+        foreach_gather::<Self, T, R, Axes, _>(&mut idx, &mut |o, i| out[o] += inp[i]);
+    }
+
+    /// Accumulates the gradient of [DeviceScan::gather_add()] into `inp_grad`:
+    /// `inp_grad[.., idx(j), ..] += out_grad[.., j, ..]`.
+    fn gather_backward<R, F>(inp_grad: &mut T, out_grad: &R, mut idx: F)
+    where
+        R: CountElements<Dtype = f32>,
+        Self: DeviceScan<R, Axes>,
+        F: FnMut(usize) -> usize,
+    {
+        let out_grad = as_slice(out_grad);
+        let inp_grad = as_mut_slice(inp_grad);
+        foreach_gather::<Self, T, R, Axes, _>(&mut idx, &mut |o, i| inp_grad[i] += out_grad[o]);
+    }
+}
+
+/// Calls `f(o, i)` with the flat index `o` of every element of `R`, and the flat index `i`
+/// of the element of `T` it is gathered from.
+fn foreach_gather<D, T, R, Axes, F>(idx: &mut impl FnMut(usize) -> usize, f: &mut F)
+where
+    D: DeviceScan<T, Axes> + DeviceScan<R, Axes> + ?Sized,
+    T: CountElements<Dtype = f32>,
+    R: CountElements<Dtype = f32>,
+    F: FnMut(usize, usize),
+{
+    let inner = <D as DeviceScan<T, Axes>>::INNER;
+    let size = <D as DeviceScan<T, Axes>>::SIZE;
+    let out_size = <D as DeviceScan<R, Axes>>::SIZE;
+    for j in 0..out_size {
+        let k = idx(j);
+        debug_assert!(k < size);
+        for o in 0..<D as DeviceScan<T, Axes>>::OUTER {
+            for i in 0..inner {
+                f((o * out_size + j) * inner + i, (o * size + k) * inner + i);
+            }
+        }
+    }
+}
+
+/// Calls `f` with the flat indices of every lane along the scanned axis, ordered
+/// in the direction of the scan.
+fn foreach_lane<D, T, Axes, F>(reverse: bool, f: &mut F)
+where
+    D: DeviceScan<T, Axes> + ?Sized,
+    T: CountElements<Dtype = f32>,
+    F: FnMut(&[usize]),
+{
+    let mut lane = vec![0; D::SIZE];
+    for o in 0..D::OUTER {
+        for i in 0..D::INNER {
+            for (k, idx) in lane.iter_mut().enumerate() {
+                let k = if reverse { D::SIZE - 1 - k } else { k };
+                *idx = (o * D::SIZE + k) * D::INNER + i;
+            }
+            f(&lane);
+        }
+    }
+}
+
+macro_rules! impl_scan {
+    ($ArrTy:ty, $AxisTy:ty, $Outer:expr, $Size:expr, $Inner:expr, {$($Dims:tt),*}) => {
+impl<$(const $Dims: usize, )*> DeviceScan<$ArrTy, $AxisTy> for Cpu {
+    const OUTER: usize = $Outer;
+    const SIZE: usize = $Size;
+    const INNER: usize = $Inner;
+}
+    };
+}
+
+impl_scan!([f32; M], Axis<0>, 1, M, 1, { M });
+impl_scan!([[f32; N]; M], Axis<0>, 1, M, N, {M, N});
+impl_scan!([[f32; N]; M], Axis<1>, M, N, 1, {M, N});
+impl_scan!([[[f32; O]; N]; M], Axis<0>, 1, M, N * O, {M, N, O});
+impl_scan!([[[f32; O]; N]; M], Axis<1>, M, N, O, {M, N, O});
+impl_scan!([[[f32; O]; N]; M], Axis<2>, M * N, O, 1, {M, N, O});
+impl_scan!([[[[f32; P]; O]; N]; M], Axis<0>, 1, M, N * O * P, {M, N, O, P});
+impl_scan!([[[[f32; P]; O]; N]; M], Axis<1>, M, N, O * P, {M, N, O, P});
+impl_scan!([[[[f32; P]; O]; N]; M], Axis<2>, M * N, O, P, {M, N, O, P});
+impl_scan!([[[[f32; P]; O]; N]; M], Axis<3>, M * N * O, P, 1, {M, N, O, P});
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cumsum_1d() {
+        let mut out = [0.0; 4];
+        <Cpu as DeviceScan<_, Axis<0>>>::cumsum(&mut out, &[1.0, 2.0, 3.0, 4.0], 1.0, false);
+        assert_eq!(out, [1.0, 3.0, 6.0, 10.0]);
+        <Cpu as DeviceScan<_, Axis<0>>>::cumsum(&mut out, &[1.0, 2.0, 3.0, 4.0], 0.5, true);
+        assert_eq!(out, [3.25, 4.5, 5.0, 4.0]);
+    }
+
+    #[test]
+    fn test_cumsum_3d() {
+        let inp = [[[1.0, 2.0], [3.0, 4.0]], [[5.0, 6.0], [7.0, 8.0]]];
+        let mut out = [[[0.0; 2]; 2]; 2];
+        <Cpu as DeviceScan<_, Axis<0>>>::cumsum(&mut out, &inp, 1.0, false);
+        assert_eq!(out, [[[1.0, 2.0], [3.0, 4.0]], [[6.0, 8.0], [10.0, 12.0]]]);
+        <Cpu as DeviceScan<_, Axis<1>>>::cumsum(&mut out, &inp, 1.0, false);
+        assert_eq!(out, [[[1.0, 2.0], [4.0, 6.0]], [[5.0, 6.0], [12.0, 14.0]]]);
+        <Cpu as DeviceScan<_, Axis<2>>>::cumsum(&mut out, &inp, 1.0, false);
+        assert_eq!(out, [[[1.0, 3.0], [3.0, 7.0]], [[5.0, 11.0], [7.0, 15.0]]]);
+    }
+
+    #[test]
+    fn test_cumprod_2d() {
+        let inp = [[1.0, 2.0, 3.0], [-1.0, 0.0, 2.0]];
+        let mut out = [[0.0; 3]; 2];
+        <Cpu as DeviceScan<_, Axis<1>>>::cumprod(&mut out, &inp, false);
+        assert_eq!(out, [[1.0, 2.0, 6.0], [-1.0, 0.0, 0.0]]);
+        <Cpu as DeviceScan<_, Axis<0>>>::cumprod(&mut out, &inp, true);
+        assert_eq!(out, [[-1.0, 0.0, 6.0], [-1.0, 0.0, 2.0]]);
+    }
+
+    #[test]
+    fn test_cumprod_backward_with_zero() {
+        let mut grad = [0.0; 3];
+        <Cpu as DeviceScan<_, Axis<0>>>::cumprod_backward(
+            &mut grad,
+            &[2.0, 0.0, 3.0],
+            &[1.0; 3],
+            false,
+        );
+        // out = [x0, x0 * x1, x0 * x1 * x2]
+        assert_eq!(grad, [1.0, 2.0 + 6.0, 0.0]);
+    }
+
+    #[test]
+    fn test_gather_2d() {
+        let inp = [[1.0, 2.0], [3.0, 4.0]];
+        let mut out = [[0.0; 2]; 3];
+        <Cpu as DeviceScan<_, Axis<0>>>::gather_add(&mut out, &inp, |j| j % 2);
+        assert_eq!(out, [[1.0, 2.0], [3.0, 4.0], [1.0, 2.0]]);
+
+        let mut grad = [[0.0; 2]; 2];
+        <Cpu as DeviceScan<_, Axis<0>>>::gather_backward(&mut grad, &out, |j| j % 2);
+        assert_eq!(grad, [[2.0, 4.0], [3.0, 4.0]]);
+
+        let mut out = [[0.0; 2]; 2];
+        <Cpu as DeviceScan<_, Axis<1>>>::gather_add(&mut out, &inp, |j| 1 - j);
+        assert_eq!(out, [[2.0, 1.0], [4.0, 3.0]]);
+    }
+}
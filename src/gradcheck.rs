@@ -0,0 +1,371 @@
+//! Numerical gradient checking of backward passes with [gradcheck()] and [gradcheck_module()].
+//!
+//! The gradients from [crate::tensor_ops::backward()] are compared against central finite
+//! differences `(f(x + eps) - f(x - eps)) / (2 * eps)` of every element. The loss is
+//! evaluated in `f32` like everything else, but the differences are computed in `f64`.
+//!
+//! Since the loss is only `f32`, `eps` can't be too small, and the tolerances need to be
+//! much looser than what you'd use with [crate::tensor_ops::allclose()]. See [GradcheckConfig]
+//! for the defaults.
+
+use crate::arrays::{CountElements, HasArrayType, HasShape};
+use crate::devices::{as_mut_slice, as_slice, AllocateZeros, HasDevice};
+use crate::gradients::{
+    CanUpdateWithGradients, GradientProvider, Gradients, OwnedTape, UnusedTensors,
+};
+use crate::prelude::*;
+use crate::unique_id::HasUniqueId;
+
+/// Configuration of [gradcheck()] and [gradcheck_module()].
+///
+/// An analytical gradient `a` matches the numerical gradient `n` if
+/// `|a - n| <= atol + rtol * |n|`.
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// GradcheckConfig {
+///     eps: 1e-2,
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct GradcheckConfig {
+    /// Step size of the finite differences. Defaults to `1e-3`.
+    pub eps: f32,
+
+    /// Absolute tolerance. Defaults to `1e-3`.
+    pub atol: f32,
+
+    /// Relative tolerance. Defaults to `1e-2`.
+    pub rtol: f32,
+}
+
+impl Default for GradcheckConfig {
+    fn default() -> Self {
+        Self {
+            eps: 1e-3,
+            atol: 1e-3,
+            rtol: 1e-2,
+        }
+    }
+}
+
+/// A single element whose analytical gradient doesn't match the numerical gradient.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GradientMismatch {
+    /// The index of the parameter in the order they are visited by
+    /// [CanUpdateWithGradients::update()]. Always 0 for [gradcheck()].
+    pub param: usize,
+
+    /// The index of the element in the parameter.
+    pub index: Vec<usize>,
+
+    /// The gradient from backpropagation.
+    pub analytical: f32,
+
+    /// The gradient from finite differences.
+    pub numerical: f64,
+}
+
+/// The error returned by [gradcheck()] and [gradcheck_module()], with every element that
+/// didn't match.
+#[derive(Debug, Clone)]
+pub struct GradcheckError {
+    pub mismatches: Vec<GradientMismatch>,
+
+    /// The total number of elements that were checked.
+    pub num_checked: usize,
+
+    pub cfg: GradcheckConfig,
+}
+
+/// The number of mismatches that are printed by [GradcheckError]'s [std::fmt::Display].
+const MAX_MISMATCHES_SHOWN: usize = 10;
+
+impl std::fmt::Display for GradcheckError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "gradcheck failed (eps={}, atol={}, rtol={}): {} of {} gradients don't match",
+            self.cfg.eps,
+            self.cfg.atol,
+            self.cfg.rtol,
+            self.mismatches.len(),
+            self.num_checked
+        )?;
+        for m in self.mismatches.iter().take(MAX_MISMATCHES_SHOWN) {
+            write!(
+                f,
+                "\n  parameter {} at {:?}: analytical = {}, numerical = {}",
+                m.param, m.index, m.analytical, m.numerical
+            )?;
+        }
+        if self.mismatches.len() > MAX_MISMATCHES_SHOWN {
+            write!(f, "\n  ...")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for GradcheckError {}
+
+/// Checks the gradients of `f` with respect to `x`. `f` is called with a traced copy of `x`,
+/// and must return a scalar loss.
+///
+/// See [GradcheckConfig] for the tolerances, and [gradcheck_module()] to check the
+/// gradients of a module's parameters.
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let x: Tensor2D<2, 3> = tensor([[0.1, -0.5, 1.0], [2.0, 0.3, -1.2]]);
+/// let result = gradcheck(|x| x.tanh().square().sum(), &x, Default::default());
+/// assert!(result.is_ok());
+///
+/// // a broken backward pass: `square()` isn't recorded on the tape
+/// let result = gradcheck(
+///     |x| {
+///         let (x, tape) = x.split_tape();
+///         x.square().put_tape(tape).sum()
+///     },
+///     &x,
+///     Default::default(),
+/// );
+/// assert_eq!(result.unwrap_err().mismatches.len(), 6);
+/// ```
+pub fn gradcheck<X, F>(mut f: F, x: &X, cfg: GradcheckConfig) -> Result<(), GradcheckError>
+where
+    X: Tensor<Dtype = f32, Tape = NoneTape, NoTape = X> + PutTape<OwnedTape>,
+    F: FnMut(X::Output) -> Tensor0D<OwnedTape>,
+{
+    let mut grads = f(x.duplicate().put_tape(OwnedTape::default())).backward();
+    let analytical = vec![flat_gradient(x, grads.remove(x))];
+    check(analytical, cfg, |_, i, delta| {
+        let mut x = x.duplicate();
+        as_mut_slice(x.mut_data())[i] += delta;
+        *f(x.put_tape(OwnedTape::default())).data()
+    })
+}
+
+/// Checks the gradients of `f` with respect to all of the parameters of `module`.
+/// `f` is called with `module` (or a perturbed copy of it), and must return a scalar loss.
+///
+/// Parameters are perturbed through [CanUpdateWithGradients], so this works for
+/// any module, including custom ones. `module` itself is never modified.
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// # use rand::prelude::*;
+/// let mut rng = StdRng::seed_from_u64(0);
+/// let mut model: (Linear<3, 4>, Tanh, Linear<4, 2>) = Default::default();
+/// model.reset_params(&mut rng);
+/// let x: Tensor2D<5, 3> = TensorCreator::randn(&mut rng);
+/// let result = gradcheck_module(&model, |m| m.forward(x.trace()).square().mean(), Default::default());
+/// assert!(result.is_ok());
+/// ```
+pub fn gradcheck_module<M, F>(
+    module: &M,
+    mut f: F,
+    cfg: GradcheckConfig,
+) -> Result<(), GradcheckError>
+where
+    M: CanUpdateWithGradients + Clone,
+    F: FnMut(&M) -> Tensor0D<OwnedTape>,
+{
+    // gradients are stored by id, and cloning a module gives its parameters new ids
+    let mut module_ = module.clone();
+    let mut collect = CollectGradients {
+        gradients: f(&module_).backward(),
+        params: Vec::new(),
+    };
+    module_.update(&mut collect, &mut UnusedTensors::default());
+    check(collect.params, cfg, |param, i, delta| {
+        let mut module = module.clone();
+        let mut perturb = Perturb {
+            param,
+            index: i,
+            delta,
+            visited: 0,
+        };
+        module.update(&mut perturb, &mut UnusedTensors::default());
+        *f(&module).data()
+    })
+}
+
+/// The flattened analytical gradient of a parameter, and its shape.
+struct FlatGradient {
+    shape: &'static [usize],
+    values: Vec<f32>,
+}
+
+/// Parameters without a gradient weren't used, so their gradient is 0.
+fn flat_gradient<P: HasArrayType<Dtype = f32>>(
+    _: &P,
+    gradient: Option<Box<P::Array>>,
+) -> FlatGradient {
+    FlatGradient {
+        shape: P::Array::SHAPE,
+        values: match gradient {
+            Some(g) => as_slice(g.as_ref()).to_vec(),
+            None => vec![0.0; P::Array::NUM_ELEMENTS],
+        },
+    }
+}
+
+/// Compares `analytical` to the central differences of `loss(param, index, delta)`,
+/// which evaluates the loss with `delta` added to element `index` of parameter `param`.
+fn check<L>(
+    analytical: Vec<FlatGradient>,
+    cfg: GradcheckConfig,
+    mut loss: L,
+) -> Result<(), GradcheckError>
+where
+    L: FnMut(usize, usize, f32) -> f32,
+{
+    let mut mismatches = Vec::new();
+    let mut num_checked = 0;
+    for (param, grad) in analytical.iter().enumerate() {
+        for (i, &a) in grad.values.iter().enumerate() {
+            let plus = loss(param, i, cfg.eps) as f64;
+            let minus = loss(param, i, -cfg.eps) as f64;
+            let n = (plus - minus) / (2.0 * cfg.eps as f64);
+            let close = (a as f64 - n).abs() <= cfg.atol as f64 + cfg.rtol as f64 * n.abs();
+            if !close {
+                mismatches.push(GradientMismatch {
+                    param,
+                    index: unravel_index(i, grad.shape),
+                    analytical: a,
+                    numerical: n,
+                });
+            }
+            num_checked += 1;
+        }
+    }
+    if mismatches.is_empty() {
+        Ok(())
+    } else {
+        Err(GradcheckError {
+            mismatches,
+            num_checked,
+            cfg,
+        })
+    }
+}
+
+/// Converts the flat index `i` into an index for each axis of `shape`.
+pub(crate) fn unravel_index(mut i: usize, shape: &[usize]) -> Vec<usize> {
+    let mut index = vec![0; shape.len()];
+    for (idx, &dim) in index.iter_mut().zip(shape.iter()).rev() {
+        *idx = i % dim;
+        i /= dim;
+    }
+    index
+}
+
+/// Takes the gradient of every parameter out of [Gradients], without updating anything.
+struct CollectGradients {
+    gradients: Gradients,
+    params: Vec<FlatGradient>,
+}
+
+impl GradientProvider for CollectGradients {
+    fn gradient<P>(&mut self, p: &P) -> Option<Box<P::Array>>
+    where
+        P: HasUniqueId + HasArrayType<Dtype = f32> + HasDevice,
+    {
+        let gradient = self.gradients.remove(p);
+        self.params.push(flat_gradient(p, gradient));
+        None
+    }
+}
+
+/// Adds `delta` to element `index` of the `param`th parameter.
+struct Perturb {
+    param: usize,
+    index: usize,
+    delta: f32,
+    visited: usize,
+}
+
+impl GradientProvider for Perturb {
+    fn gradient<P>(&mut self, _: &P) -> Option<Box<P::Array>>
+    where
+        P: HasUniqueId + HasArrayType<Dtype = f32> + HasDevice,
+    {
+        self.visited += 1;
+        if self.visited - 1 == self.param {
+            // parameters are updated with `p -= gradient`
+            let mut g: Box<P::Array> = P::Device::zeros();
+            as_mut_slice(g.as_mut())[self.index] = -self.delta;
+            Some(g)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn test_gradcheck_ops() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let x: Tensor2D<3, 4> = TensorCreator::randn(&mut rng);
+        let cfg = Default::default();
+        gradcheck(|x| x.sigmoid().mean(), &x, cfg).unwrap();
+        gradcheck(|x| x.log_softmax::<Axis<1>>().sum(), &x, cfg).unwrap();
+        gradcheck(|x| x.cumsum::<Axis<0>>().square().mean(), &x, cfg).unwrap();
+    }
+
+    #[test]
+    fn test_gradcheck_reports_mismatches() {
+        let x: Tensor1D<3> = tensor([1.0, 2.0, 3.0]);
+        let err = gradcheck(
+            |x| {
+                let (x, tape) = x.split_tape();
+                x.square().put_tape(tape).sum()
+            },
+            &x,
+            Default::default(),
+        )
+        .unwrap_err();
+        assert_eq!(err.num_checked, 3);
+        assert_eq!(err.mismatches.len(), 3);
+        assert_eq!(err.mismatches[1].index, vec![1]);
+        assert!((err.mismatches[1].numerical - 4.0).abs() < 1e-2);
+        assert!(err
+            .to_string()
+            .starts_with("gradcheck failed (eps=0.001, atol=0.001, rtol=0.01): 3 of 3 gradients don't match\n  parameter 0 at [0]"));
+    }
+
+    #[test]
+    fn test_gradcheck_module() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut model: (Linear<2, 3>, LayerNorm1D<3>, Linear<3, 1>) = Default::default();
+        model.reset_params(&mut rng);
+        let x: Tensor2D<4, 2> = TensorCreator::randn(&mut rng);
+        let cfg = GradcheckConfig {
+            eps: 1e-2,
+            ..Default::default()
+        };
+        gradcheck_module(&model, |m| m.forward(x.trace()).exp().mean(), cfg).unwrap();
+    }
+
+    #[test]
+    fn test_gradcheck_module_unused_param() {
+        let model: (Linear<2, 2>, Linear<2, 2>) = Default::default();
+        let x: Tensor1D<2> = tensor([1.0, -1.0]);
+        // only the first linear layer is used, so all of the parameters have correct gradients
+        gradcheck_module(&model, |m| m.0.forward(x.trace()).sum(), Default::default()).unwrap();
+    }
+
+    #[test]
+    fn test_unravel_index() {
+        assert_eq!(unravel_index(0, &[]), Vec::<usize>::new());
+        assert_eq!(unravel_index(7, &[2, 4]), vec![1, 3]);
+    }
+}
@@ -1,10 +1,14 @@
 //! Implementations of [GradientTape] and generic Nd array containers via [Gradients].
 
 use std::collections::HashMap;
+use std::rc::Rc;
 
-use crate::arrays::HasArrayType;
-use crate::devices::{AllocateZeros, HasDevice};
-use crate::unique_id::{HasUniqueId, UniqueId};
+use crate::anomaly::{is_anomaly_enabled, FindNonFinite, NonFinite, RecordedOp};
+use crate::arrays::{HasArrayData, HasArrayType};
+use crate::devices::{AllocateZeros, Device, HasDevice};
+use crate::graphviz::{is_graph_recording_enabled, TapeGraph};
+use crate::prelude::*;
+use crate::unique_id::{unique_id, HasUniqueId, UniqueId};
 
 /// Records gradient computations to execute later.
 ///
@@ -37,11 +41,20 @@ use crate::unique_id::{HasUniqueId, UniqueId};
 ///
 /// This would not be possible if these chain rule operations were inside of GradientTape!
 #[derive(Default)]
-#[allow(clippy::type_complexity)]
 pub struct GradientTape {
-    operations: Vec<Box<dyn FnOnce(&mut Gradients)>>,
+    operations: Vec<(BackwardOp, Option<GraphOp>)>,
+
+    /// The most recently added operation, if anomaly detection is enabled. See [crate::anomaly].
+    last_op: Option<Rc<RecordedOp>>,
+
+    /// The name, inputs and output of every operation, if graph recording is enabled.
+    /// See [crate::graphviz].
+    pub(crate) graph: TapeGraph,
 }
 
+type BackwardOp = Box<dyn FnOnce(&mut Gradients)>;
+type GraphOp = Box<dyn FnOnce(&mut GradientGraph)>;
+
 impl std::fmt::Debug for GradientTape {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("GradientTape")
@@ -62,19 +75,87 @@ impl GradientTape {
     ///
     /// See src/tensor_ops for implementation examples.
     pub(crate) fn add_backward_op<F: 'static + FnOnce(&mut Gradients)>(&mut self, operation: F) {
-        self.operations.push(Box::new(operation));
+        let operation = self.record(operation);
+        self.operations.push((operation, None));
+    }
+
+    /// Same as [GradientTape::add_backward_op()], but also adds a version of the operation that
+    /// acts on a [GradientGraph], which is used for higher order gradients. Only one of
+    /// the two is ever executed.
+    pub(crate) fn add_backward_op_with_graph<F, G>(&mut self, operation: F, graph_operation: G)
+    where
+        F: 'static + FnOnce(&mut Gradients),
+        G: 'static + FnOnce(&mut GradientGraph),
+    {
+        let operation = self.record(operation);
+        self.operations
+            .push((operation, Some(Box::new(graph_operation))));
+    }
+
+    /// Records the operation in [GradientTape::graph] if graph recording is enabled (see
+    /// [crate::graphviz]). If anomaly detection is enabled, also records where `operation`
+    /// came from, and checks all the gradients it writes. See [crate::anomaly].
+    fn record<F: 'static + FnOnce(&mut Gradients)>(&mut self, operation: F) -> BackwardOp {
+        if is_graph_recording_enabled() {
+            self.graph.add_op::<F>();
+        }
+        if !is_anomaly_enabled() {
+            return Box::new(operation);
+        }
+        let op = Rc::new(RecordedOp::capture::<F>());
+        self.last_op = Some(op.clone());
+        Box::new(move |grads: &mut Gradients| {
+            // operations like [crate::nn::Checkpoint] run a nested tape in their backward,
+            // so the gradients written by the outer operation are restored afterwards.
+            let outer = grads.written.replace(Vec::new());
+            (operation)(grads);
+            let written = grads.written.take().unwrap_or_default();
+            for (id, find) in written.iter() {
+                if let Some(gradient) = grads.gradient_by_id.get(id) {
+                    op.check_backward(gradient.as_ref(), *find);
+                }
+            }
+            grads.written = outer.map(|mut outer| {
+                outer.extend(written);
+                outer
+            });
+        })
     }
 
     /// Compute the [Gradients]! This just runs all the operations on a new [Gradients] struct.
     ///
     /// Note that this method takes ownership of self, so it can't be called twice!
-    pub fn execute(mut self) -> Gradients {
+    pub fn execute(self) -> Gradients {
         let mut gradients: Gradients = Default::default();
-        for operation in self.operations.drain(..).rev() {
-            (operation)(&mut gradients);
-        }
+        self.execute_into(&mut gradients);
         gradients
     }
+
+    /// Same as [GradientTape::execute()], but accumulates into existing `gradients`.
+    pub(crate) fn execute_into(mut self, gradients: &mut Gradients) {
+        for (operation, _) in self.operations.drain(..).rev() {
+            (operation)(gradients);
+        }
+    }
+
+    /// Computes the gradients into `graph` by running the graph version of all the operations.
+    /// The normal backward operations are moved onto the tape of `graph`, since they
+    /// are needed to backprop from intermediate values to the inputs.
+    ///
+    /// **Panics** if an operation doesn't support higher order gradients.
+    pub(crate) fn execute_create_graph(mut self, mut graph: GradientGraph) -> GradientGraph {
+        let mut operations = Vec::with_capacity(self.operations.len());
+        for (operation, graph_operation) in self.operations.drain(..).rev() {
+            let graph_operation = graph_operation
+                .expect("Tried to create a gradient graph through an operation that doesn't support higher order gradients");
+            (graph_operation)(&mut graph);
+            operations.push((operation, None));
+        }
+        operations.reverse();
+        operations.append(&mut graph.tape.operations);
+        graph.tape.operations = operations;
+        graph
+    }
 }
 
 /// Contains a boxed [GradientTape]. When [Tape::add_backward_op] is called,
@@ -91,6 +172,25 @@ pub trait Tape {
     /// Whether this object currently owns the [GradientTape]. This is known at compile time.
     const OWNS_TAPE: bool;
     fn add_backward_op<F: 'static + FnOnce(&mut Gradients)>(&mut self, operation: F);
+
+    /// Called by [crate::tensor::PutTape::put_tape()] with the tensor that the tape is put on,
+    /// which is usually the result of the most recently added operation.
+    fn on_put_tape<T: HasUniqueId + HasArrayData<Dtype = f32> + HasDevice>(&mut self, _t: &T) {}
+
+    /// Records `t` as an input of the next operation that is added. Called by
+    /// [Tensor::split_tape()], so operations only need to call this for the
+    /// tensors they take by reference. See [crate::graphviz].
+    fn add_input<T: HasUniqueId + HasArrayType<Dtype = f32>>(&mut self, _t: &T) {}
+
+    /// Like [Tape::add_backward_op()], but also records how to differentiate the operation
+    /// when building a [GradientGraph]. By default `graph_operation` is ignored.
+    fn add_backward_op_with_graph<F, G>(&mut self, operation: F, _graph_operation: G)
+    where
+        F: 'static + FnOnce(&mut Gradients),
+        G: 'static + FnOnce(&mut GradientGraph),
+    {
+        self.add_backward_op(operation)
+    }
 }
 
 impl Tape for OwnedTape {
@@ -98,6 +198,32 @@ impl Tape for OwnedTape {
     fn add_backward_op<F: 'static + FnOnce(&mut Gradients)>(&mut self, operation: F) {
         self.0.add_backward_op(operation)
     }
+
+    fn add_backward_op_with_graph<F, G>(&mut self, operation: F, graph_operation: G)
+    where
+        F: 'static + FnOnce(&mut Gradients),
+        G: 'static + FnOnce(&mut GradientGraph),
+    {
+        self.0
+            .add_backward_op_with_graph(operation, graph_operation)
+    }
+
+    /// Records `t` as the output of the most recently added operation if graph recording
+    /// is enabled, and checks it if anomaly detection is enabled.
+    fn on_put_tape<T: HasUniqueId + HasArrayData<Dtype = f32> + HasDevice>(&mut self, t: &T) {
+        if is_graph_recording_enabled() {
+            self.0.graph.set_output(t);
+        }
+        if let Some(op) = self.0.last_op.take() {
+            op.check_forward::<T::Array, T::Device>(t.data());
+        }
+    }
+
+    fn add_input<T: HasUniqueId + HasArrayType<Dtype = f32>>(&mut self, t: &T) {
+        if is_graph_recording_enabled() {
+            self.0.graph.add_input(t);
+        }
+    }
 }
 
 impl Tape for NoneTape {
@@ -105,6 +231,72 @@ impl Tape for NoneTape {
     fn add_backward_op<F: 'static + FnOnce(&mut Gradients)>(&mut self, _operation: F) {}
 }
 
+/// Forward mode automatic differentiation. Stores the tangent of every tensor that
+/// this tape has been on, and computes the tangent of each result as soon as an
+/// operation is applied. Create with [crate::tensor::dual()], and get the tangents with
+/// [ForwardTape::tangent()].
+///
+/// Operations are differentiated with the same per operation code as
+/// [GradientGraph], which means only those operations are supported. Any other
+/// operation **panics**.
+///
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let x = tensor([1.0, 2.0, 3.0]);
+/// let y = x.dual(&tensor([1.0, 0.0, -1.0])).square();
+/// assert_eq!(y.tangent().data(), &[2.0, 0.0, -6.0]);
+/// ```
+#[derive(Default, Debug)]
+pub struct ForwardTape(pub(crate) Box<Gradients>);
+
+impl ForwardTape {
+    /// Returns the tangent of `t`, or all zeros if `t` doesn't depend on any tensor
+    /// with a tangent.
+    pub fn tangent<T: Tensor<Dtype = f32>>(&self, t: &T) -> T::NoTape {
+        let mut tangent = T::NoTape::zeros();
+        if let Some(data) = self.0.gradient_by_id.get(t.id()) {
+            tangent.mut_data().clone_from(data.downcast_ref().unwrap());
+        }
+        tangent
+    }
+}
+
+impl Tape for ForwardTape {
+    const OWNS_TAPE: bool = true;
+    fn add_backward_op<F: 'static + FnOnce(&mut Gradients)>(&mut self, _operation: F) {
+        panic!("Tried to compute tangents through an operation that doesn't support forward mode");
+    }
+
+    /// The graph version of an operation stores the first order gradients in slots, and
+    /// the backward pass through those is linear in the gradients of the inputs. So
+    /// backpropagating the tangents of the inputs from their slots gives the tangent of the
+    /// result in its slot.
+    fn add_backward_op_with_graph<F, G>(&mut self, _operation: F, graph_operation: G)
+    where
+        F: 'static + FnOnce(&mut Gradients),
+        G: 'static + FnOnce(&mut GradientGraph),
+    {
+        let mut graph: GradientGraph = Default::default();
+        (graph_operation)(&mut graph);
+
+        let mut gradients: Gradients = Default::default();
+        for (id, slot) in graph.slots.iter() {
+            if let Some(tangent) = self.0.gradient_by_id.remove(id) {
+                gradients.gradient_by_id.insert(*slot, tangent);
+            }
+        }
+        std::mem::take(&mut graph.tape).execute_into(&mut gradients);
+
+        // the slots of the inputs still contain their tangents, and the slot of the
+        // result now contains its tangent. inputs without a tangent get zeros.
+        for (id, slot) in graph.slots.iter() {
+            if let Some(tangent) = gradients.gradient_by_id.remove(slot) {
+                self.0.gradient_by_id.insert(*id, tangent);
+            }
+        }
+    }
+}
+
 /// A generic container for keeping variable sized arrays associated with a [UniqueId].
 ///
 /// You can:
@@ -122,6 +314,9 @@ impl Tape for NoneTape {
 #[derive(Debug, Default)]
 pub struct Gradients {
     gradient_by_id: HashMap<UniqueId, Box<dyn std::any::Any>>,
+
+    /// The gradients written by the currently running operation, if anomaly detection is enabled.
+    written: Option<Vec<(UniqueId, FindNonFinite)>>,
 }
 
 impl Gradients {
@@ -155,6 +350,21 @@ impl Gradients {
         (l_ref, r_ref)
     }
 
+    /// Same as [Gradients::mut_and_ref()], but `r` is allocated as zeros if missing.
+    /// Only for operations that support [GradientGraph], since their backward operations
+    /// are also run when backpropagating through a gradient, which doesn't have to depend
+    /// on every intermediate value.
+    pub(crate) fn mut_and_ref_or_zeros<L, R>(&mut self, l: &L, r: &R) -> (&mut L::Array, &R::Array)
+    where
+        L: HasUniqueId + HasArrayType + HasDevice,
+        R: HasUniqueId + HasArrayType + HasDevice,
+    {
+        self.gradient_by_id
+            .entry(*r.id())
+            .or_insert_with(|| R::Device::zeros::<R::Array>());
+        self.mut_and_ref(l, r)
+    }
+
     pub fn muts_and_ref<L1, L2, L3, R>(
         &mut self,
         l1: &L1,
@@ -217,6 +427,9 @@ impl Gradients {
         &mut self,
         t: &T,
     ) -> &mut T::Array {
+        if let Some(written) = self.written.as_mut() {
+            written.push((*t.id(), NonFinite::find_any::<T::Array, T::Device>));
+        }
         self.gradient_by_id
             .entry(*t.id())
             .or_insert_with(|| T::Device::zeros::<T::Array>())
@@ -248,6 +461,114 @@ impl Gradients {
             .downcast_ref()
             .unwrap()
     }
+
+    /// Returns the data associated with `id`, if there is any.
+    pub(crate) fn ref_gradient_by_id(&self, id: &UniqueId) -> Option<&dyn std::any::Any> {
+        self.gradient_by_id.get(id).map(|g| g.as_ref())
+    }
+
+    /// Removes the data associated with `id`, if there is any.
+    pub(crate) fn remove_by_id(&mut self, id: &UniqueId) {
+        self.gradient_by_id.remove(id);
+    }
+
+    /// Returns a copy of the data associated with `t`, or zeros if there is none.
+    pub(crate) fn clone_gradient<T: HasUniqueId + HasArrayType + HasDevice>(
+        &mut self,
+        t: &T,
+    ) -> Box<T::Array> {
+        let mut data: Box<T::Array> = T::Device::zeros();
+        data.as_mut().clone_from(self.mut_gradient(t));
+        data
+    }
+}
+
+/// Gradients that can themselves be differentiated, created by
+/// [crate::tensor_ops::backward_create_graph()].
+///
+/// Each gradient is tracked on a new tape, which is owned by this object. To compute a
+/// function of the gradients (e.g. a gradient penalty), get the gradients with
+/// [GradientGraph::gradient()], then put the tape from [GradientGraph::into_tape()] onto one of them:
+///
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let x = tensor([1.0, 2.0, 3.0]);
+/// let y = x.trace().powi(3).sum();
+/// let mut graph = y.backward_create_graph();
+/// let dx: Tensor1D<3> = graph.gradient(&x);
+/// assert_eq!(dx.data(), &[3.0, 12.0, 27.0]);
+///
+/// // second derivatives
+/// let gradients = dx.put_tape(graph.into_tape()).sum().backward();
+/// assert_eq!(gradients.ref_gradient(&x), &[6.0, 12.0, 18.0]);
+/// ```
+///
+/// Only some operations support this, and creating the graph **panics** if any other
+/// operation is used. The supported operations are:
+/// 1. Element wise functions (e.g. [crate::tensor_ops::relu()], [crate::tensor_ops::tanh()], [crate::tensor_ops::powi()])
+/// 2. [crate::tensor_ops::add()], [crate::tensor_ops::sub()], [crate::tensor_ops::mul()], [crate::tensor_ops::div()],
+///    [crate::tensor_ops::maximum()] and [crate::tensor_ops::minimum()], including broadcasting
+/// 3. Arithmetic with scalars (e.g. [crate::tensor_ops::add_scalar()])
+/// 4. [crate::tensor_ops::sum()], [crate::tensor_ops::mean()] and [crate::tensor_ops::BroadcastTo]
+/// 5. [crate::tensor_ops::matmul()], [crate::tensor_ops::matmul_transpose()], [crate::tensor_ops::vecmat_mul()]
+///    and [crate::tensor_ops::vecmat_mul_transpose()], which means [crate::nn::Linear] is supported
+///
+/// Notably, these are **not** supported:
+/// - [crate::tensor_ops::max()], [crate::tensor_ops::min()] and [crate::tensor_ops::prod()]
+/// - [crate::tensor_ops::SelectTo], [crate::tensor_ops::PermuteTo] and [crate::tensor_ops::einsum2()]
+/// - Convolutions and pooling, so `Conv2D` isn't supported
+/// - [crate::nn::Checkpoint], [crate::tensor_ops::register_hook()] and [crate::tensor_ops::custom_op()]
+/// - [crate::tensor_ops::atan2()], cumulative ops (e.g. [crate::tensor_ops::cumsum()]),
+///   [crate::tensor_ops::flip()] and the other repeating ops, norms (e.g. [crate::tensor_ops::norm()]),
+///   matrix ops (e.g. [crate::tensor_ops::diag()]) and linear algebra (e.g. [crate::tensor_ops::inverse()]),
+///   [crate::tensor_ops::sparse_matmul()], [crate::tensor_ops::fft()] and [crate::tensor_ops::interpolate()]
+#[derive(Debug, Default)]
+pub struct GradientGraph {
+    /// Maps the id of a tensor to the id that its gradient is stored under.
+    slots: HashMap<UniqueId, UniqueId>,
+    gradients: Gradients,
+    tape: GradientTape,
+}
+
+impl GradientGraph {
+    /// Returns a fake tensor that the gradient of `t` is stored under. The gradient of
+    /// `t` is a different value than `t` in the new tape, so it needs a different id.
+    pub(crate) fn slot<T: HasUniqueId>(&mut self, t: &T) -> PhantomTensor<T> {
+        PhantomTensor::new(*self.slots.entry(*t.id()).or_insert_with(unique_id))
+    }
+
+    /// The gradients of all slots.
+    pub(crate) fn gradients(&mut self) -> &mut Gradients {
+        &mut self.gradients
+    }
+
+    /// Adds an operation to the new tape. Every operation acting on the gradients
+    /// has to add the operation that backprops through itself.
+    pub(crate) fn add_backward_op<F: 'static + FnOnce(&mut Gradients)>(&mut self, operation: F) {
+        self.tape.add_backward_op(operation);
+    }
+
+    /// Returns the gradient of `t` as a tensor that is tracked by the tape of `self`.
+    /// If `t` wasn't used, this is all zeros.
+    pub fn gradient<T: Tensor<Dtype = f32>>(&mut self, t: &T) -> T::NoTape {
+        let slot = match self.slots.get(t.id()) {
+            Some(&id) => PhantomTensor::<T::NoTape>::new(id),
+            None => return T::NoTape::zeros(),
+        };
+        let gradient = T::NoTape::new_boxed(self.gradients.clone_gradient(&slot));
+        let phantom_gradient = gradient.phantom();
+        self.tape.add_backward_op(move |grads| {
+            let (slot_grad, gradient_grad) = grads.mut_and_ref(&slot, &phantom_gradient);
+            T::Device::add(slot_grad, gradient_grad);
+        });
+        gradient
+    }
+
+    /// Returns the tape that tracks the gradients, which can be put on the result of
+    /// [GradientGraph::gradient()].
+    pub fn into_tape(self) -> OwnedTape {
+        OwnedTape(Box::new(self.tape))
+    }
 }
 
 /// Represents something that can return a gradient for a given key.
@@ -344,4 +665,22 @@ mod tests {
         let g = tape.execute();
         assert_eq!(g.ref_gradient(&t1), &[1.0; 5]);
     }
+
+    #[test]
+    fn test_default_add_backward_op_with_graph() {
+        /// A tape that only implements the required methods.
+        #[derive(Default)]
+        struct CountingTape(usize);
+
+        impl Tape for CountingTape {
+            const OWNS_TAPE: bool = true;
+            fn add_backward_op<F: 'static + FnOnce(&mut Gradients)>(&mut self, _: F) {
+                self.0 += 1;
+            }
+        }
+
+        let mut tape = CountingTape::default();
+        tape.add_backward_op_with_graph(|_| {}, |_| {});
+        assert_eq!(tape.0, 1);
+    }
 }
@@ -0,0 +1,344 @@
+//! Exporting the operations recorded on a [crate::gradients::GradientTape] to
+//! [Graphviz](https://graphviz.org) DOT, to visualize the structure of a model.
+//!
+//! Enable recording with [set_record_graph()]. While enabled (on the current thread), tapes
+//! record the name, inputs and output of each operation added to them. Get a copy of what
+//! was recorded so far with `.graph()` on any tensor with [crate::gradients::OwnedTape],
+//! and write it with [TapeGraph::save_dot()]. Passing the [Gradients] after backward labels
+//! every tensor with the norm of its gradient.
+//!
+//! ```rust
+//! # use dfdx::prelude::*;
+//! set_record_graph(true);
+//! let model: (Linear<3, 2>, ReLU) = Default::default();
+//! let loss = model.forward(Tensor1D::zeros().traced()).square().mean();
+//! let graph = loss.graph();
+//! let gradients = loss.backward();
+//! let dot: String = graph.to_dot(Some(&gradients));
+//! assert!(dot.starts_with("digraph {"));
+//! ```
+//!
+//! Then render it with e.g. `dot -Tsvg graph.dot -o graph.svg`.
+//!
+//! Inputs are the tensors whose tape was moved into the operation, and the tensors that
+//! operations take by reference (e.g. the weight of [crate::nn::Linear]).
+//! Operations with more than one output (e.g. [crate::tensor_ops::fft()]) only
+//! record the one that the tape was put on.
+//!
+//! Recording is disabled by default, since it allocates for every operation.
+
+use std::any::Any;
+use std::cell::Cell;
+use std::fmt::Write as _;
+use std::path::Path;
+
+use crate::arrays::{CountElements, HasArrayType, HasShape};
+use crate::devices::as_slice;
+use crate::gradients::{Gradients, OwnedTape};
+use crate::prelude::*;
+use crate::unique_id::{HasUniqueId, UniqueId};
+
+thread_local! {
+    static RECORD_GRAPH: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Enables or disables recording the operations on tapes on the current thread.
+/// See [crate::graphviz].
+pub fn set_record_graph(enabled: bool) {
+    RECORD_GRAPH.with(|r| r.set(enabled));
+}
+
+/// Whether recording the operations on tapes is enabled on the current thread.
+pub fn is_graph_recording_enabled() -> bool {
+    RECORD_GRAPH.with(|r| r.get())
+}
+
+/// The operations recorded on a tape. See [crate::graphviz].
+#[derive(Debug, Clone, Default)]
+pub struct TapeGraph {
+    ops: Vec<OpNode>,
+
+    /// The inputs of the next operation that is added.
+    pending_inputs: Vec<TensorNode>,
+}
+
+/// An operation recorded on a tape, with its inputs and output.
+#[derive(Clone)]
+pub struct OpNode {
+    type_name: &'static str,
+    inputs: Vec<TensorNode>,
+    output: Option<TensorNode>,
+}
+
+/// A tensor that is an input or output of an [OpNode].
+#[derive(Clone, Copy)]
+pub struct TensorNode {
+    id: UniqueId,
+    shape: &'static [usize],
+    norm: fn(&dyn Any) -> f32,
+}
+
+impl TapeGraph {
+    /// The recorded operations, in the order they were applied.
+    pub fn ops(&self) -> &[OpNode] {
+        &self.ops
+    }
+
+    /// Records `t` as an input of the next operation.
+    pub(crate) fn add_input<T: HasUniqueId + HasArrayType<Dtype = f32>>(&mut self, t: &T) {
+        if !self.pending_inputs.iter().any(|n| &n.id == t.id()) {
+            self.pending_inputs.push(TensorNode::new(t));
+        }
+    }
+
+    /// Records an operation whose backward operation has type `F`, with all the pending inputs.
+    pub(crate) fn add_op<F>(&mut self) {
+        self.ops.push(OpNode {
+            type_name: std::any::type_name::<F>(),
+            inputs: std::mem::take(&mut self.pending_inputs),
+            output: None,
+        });
+    }
+
+    /// Records `t` as the output of the most recent operation, if it doesn't have one yet.
+    /// Inputs that weren't used by an operation are discarded.
+    pub(crate) fn set_output<T: HasUniqueId + HasArrayType<Dtype = f32>>(&mut self, t: &T) {
+        if let Some(op) = self.ops.last_mut() {
+            if op.output.is_none() {
+                op.output = Some(TensorNode::new(t));
+            }
+        }
+        self.pending_inputs.clear();
+    }
+
+    /// The graph in the DOT language. Tensors are boxes labeled with their id and shape,
+    /// and operations are ellipses labeled with their name. If `gradients` is given,
+    /// tensors that have a gradient are also labeled with its L2 norm.
+    pub fn to_dot(&self, gradients: Option<&Gradients>) -> String {
+        let mut tensors: Vec<TensorNode> = Vec::new();
+        for op in self.ops.iter() {
+            for t in op.inputs.iter().chain(op.output.iter()) {
+                if !tensors.iter().any(|n| n.id == t.id) {
+                    tensors.push(*t);
+                }
+            }
+        }
+
+        let mut dot = String::from("digraph {\n");
+        for t in tensors.iter() {
+            let mut label = format!("#{} {:?}", t.id.as_u64(), t.shape);
+            if let Some(g) = gradients.and_then(|g| g.ref_gradient_by_id(&t.id)) {
+                write!(label, "\\ngrad norm = {}", (t.norm)(g)).unwrap();
+            }
+            writeln!(
+                dot,
+                "    t{} [label=\"{label}\", shape=box];",
+                t.id.as_u64()
+            )
+            .unwrap();
+        }
+        for (i, op) in self.ops.iter().enumerate() {
+            writeln!(dot, "    op{i} [label=\"{}\", shape=ellipse];", op.name()).unwrap();
+            for t in op.inputs.iter() {
+                writeln!(dot, "    t{} -> op{i};", t.id.as_u64()).unwrap();
+            }
+            if let Some(t) = op.output {
+                writeln!(dot, "    op{i} -> t{};", t.id.as_u64()).unwrap();
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Writes [TapeGraph::to_dot()] to the file at `path`.
+    pub fn save_dot<P: AsRef<Path>>(
+        &self,
+        path: P,
+        gradients: Option<&Gradients>,
+    ) -> std::io::Result<()> {
+        std::fs::write(path, self.to_dot(gradients))
+    }
+}
+
+impl OpNode {
+    /// The name of the function that added the operation, e.g. `matmul`.
+    pub fn name(&self) -> &'static str {
+        crate::anomaly::op_name(self.type_name)
+    }
+
+    /// The tensors the operation was applied to.
+    pub fn inputs(&self) -> &[TensorNode] {
+        &self.inputs
+    }
+
+    /// The result of the operation, if the tape was put on it.
+    pub fn output(&self) -> Option<&TensorNode> {
+        self.output.as_ref()
+    }
+}
+
+impl std::fmt::Debug for OpNode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OpNode")
+            .field("name", &self.name())
+            .field("inputs", &self.inputs)
+            .field("output", &self.output)
+            .finish()
+    }
+}
+
+impl TensorNode {
+    fn new<T: HasUniqueId + HasArrayType<Dtype = f32>>(t: &T) -> Self {
+        Self {
+            id: *t.id(),
+            shape: T::Array::SHAPE,
+            norm: norm::<T::Array>,
+        }
+    }
+
+    /// The id of the tensor.
+    pub fn id(&self) -> &UniqueId {
+        &self.id
+    }
+
+    /// The shape of the tensor.
+    pub fn shape(&self) -> &'static [usize] {
+        self.shape
+    }
+}
+
+impl std::fmt::Debug for TensorNode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TensorNode")
+            .field("id", &self.id)
+            .field("shape", &self.shape)
+            .finish()
+    }
+}
+
+/// The L2 norm of a gradient of type `A`.
+fn norm<A: 'static + CountElements<Dtype = f32>>(g: &dyn Any) -> f32 {
+    let g: &A = g.downcast_ref().unwrap();
+    as_slice(g).iter().map(|x| x * x).sum::<f32>().sqrt()
+}
+
+macro_rules! tensor_impl {
+    ($typename:ident, [$($Vs:tt),*]) => {
+impl<$(const $Vs: usize, )*> $typename<$($Vs, )* OwnedTape> {
+    /// A copy of the operations recorded on the tape so far, which is empty unless
+    /// [set_record_graph()] was enabled. See [crate::graphviz].
+    pub fn graph(&self) -> TapeGraph {
+        self.tape.0.graph.clone()
+    }
+}
+    };
+}
+
+tensor_impl!(Tensor0D, []);
+tensor_impl!(Tensor1D, [M]);
+tensor_impl!(Tensor2D, [M, N]);
+tensor_impl!(Tensor3D, [M, N, O]);
+tensor_impl!(Tensor4D, [M, N, O, P]);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_disabled_by_default() {
+        let y = tensor([1.0, 2.0]).traced().exp().sum();
+        assert!(y.graph().ops().is_empty());
+        set_record_graph(true);
+        assert!(is_graph_recording_enabled());
+        set_record_graph(false);
+        let y = tensor([1.0, 2.0]).traced().exp().sum();
+        assert!(y.graph().ops().is_empty());
+    }
+
+    #[test]
+    fn test_records_unary_ops() {
+        set_record_graph(true);
+        let x = tensor([1.0, 2.0, 3.0]);
+        let y = x.trace().exp().sum();
+        let graph = y.graph();
+        let names: Vec<_> = graph.ops().iter().map(OpNode::name).collect();
+        assert_eq!(names, ["exp", "sum"]);
+
+        let exp = &graph.ops()[0];
+        assert_eq!(exp.inputs().len(), 1);
+        assert_eq!(exp.inputs()[0].id(), x.id());
+        assert_eq!(exp.inputs()[0].shape(), &[3]);
+        let sum = &graph.ops()[1];
+        assert_eq!(sum.inputs()[0].id(), exp.output().unwrap().id());
+        assert_eq!(sum.output().unwrap().id(), y.id());
+        assert_eq!(sum.output().unwrap().shape(), &[] as &[usize]);
+    }
+
+    #[test]
+    fn test_records_reference_inputs() {
+        set_record_graph(true);
+        let model: Linear<3, 2> = Default::default();
+        let x: Tensor2D<4, 3> = TensorCreator::zeros();
+        let y = model.forward(x.trace());
+        let graph = y.graph();
+        let inputs: Vec<_> = graph
+            .ops()
+            .iter()
+            .flat_map(|op| op.inputs().iter().map(|t| *t.id()))
+            .collect();
+        assert!(inputs.contains(x.id()));
+        assert!(inputs.contains(model.weight.id()));
+        assert!(inputs.contains(model.bias.id()));
+        assert_eq!(graph.ops().last().unwrap().output().unwrap().id(), y.id());
+    }
+
+    #[test]
+    fn test_split_without_op_is_not_an_input() {
+        set_record_graph(true);
+        let x = tensor([1.0, 2.0]);
+        let (_, tape) = x.trace().split_tape();
+        let y = tensor([3.0, 4.0]).put_tape(tape).relu();
+        let graph = y.graph();
+        assert_eq!(graph.ops().len(), 1);
+        assert_ne!(graph.ops()[0].inputs()[0].id(), x.id());
+    }
+
+    #[test]
+    fn test_to_dot() {
+        set_record_graph(true);
+        let x = tensor([1.0, 2.0]);
+        let y = x.trace().square().sum();
+        let (x_id, y_id) = (x.id().as_u64(), y.id().as_u64());
+        let dot = y.graph().to_dot(None);
+        assert!(dot.starts_with("digraph {\n"));
+        assert!(dot.ends_with("}\n"));
+        assert!(dot.contains(&format!("t{x_id} [label=\"#{x_id} [2]\", shape=box];")));
+        assert!(dot.contains(&format!("t{y_id} [label=\"#{y_id} []\", shape=box];")));
+        assert!(dot.contains("op0 [label=\"square\", shape=ellipse];"));
+        assert!(dot.contains("op1 [label=\"sum\", shape=ellipse];"));
+        assert!(dot.contains(&format!("t{x_id} -> op0;")));
+        assert!(dot.contains(&format!("op1 -> t{y_id};")));
+    }
+
+    #[test]
+    fn test_dot_gradient_norms() {
+        set_record_graph(true);
+        let x = tensor([3.0, 4.0]);
+        let y = (x.trace() * 0.5).sum();
+        let graph = y.graph();
+        let gradients = y.backward();
+        let dot = graph.to_dot(Some(&gradients));
+        let x_id = x.id().as_u64();
+        assert!(dot.contains(&format!("#{x_id} [2]\\ngrad norm = {}", 0.5f32.sqrt())));
+    }
+
+    #[test]
+    fn test_save_dot() {
+        set_record_graph(true);
+        let y = tensor([1.0, 2.0]).traced().tanh();
+        let file = tempfile::NamedTempFile::new().expect("failed to create tempfile");
+        y.graph().save_dot(file.path(), None).unwrap();
+        let dot = std::fs::read_to_string(file.path()).unwrap();
+        assert_eq!(dot, y.graph().to_dot(None));
+    }
+}
@@ -6,7 +6,7 @@
 //! 2. A large library of tensor operations (matrix multiplication, arithmetic, activation functions, etc).
 //! 3. Safe & easy to use neural network building blocks.
 //! 4. Standard deep learning optimizers such as Sgd and Adam.
-//! 5. Reverse mode auto differentiation implementation.
+//! 5. Reverse mode auto differentiation implementation, and forward mode with [crate::gradients::ForwardTape].
 //! 6. Serialization to/from `.npy` and `.npz` for transferring models to/from python.
 //!
 //! # A quick tutorial
@@ -94,10 +94,13 @@
 //! opt.update(&mut model, gradients);
 //! ```
 
+pub mod anomaly;
 pub mod arrays;
 pub mod data;
 pub mod devices;
+pub mod gradcheck;
 pub mod gradients;
+pub mod graphviz;
 pub mod losses;
 pub mod nn;
 pub mod numpy;
@@ -108,9 +111,12 @@ pub mod unique_id;
 
 /// Contains all public exports.
 pub mod prelude {
+    pub use crate::anomaly::set_detect_anomaly;
     pub use crate::arrays::{AllAxes, Axes2, Axes3, Axes4, Axis, HasArrayData};
     pub use crate::devices::HasDevice;
-    pub use crate::gradients::{NoneTape, OwnedTape};
+    pub use crate::gradcheck::{gradcheck, gradcheck_module, GradcheckConfig};
+    pub use crate::gradients::{ForwardTape, NoneTape, OwnedTape};
+    pub use crate::graphviz::set_record_graph;
     pub use crate::losses::*;
     pub use crate::nn::*;
     pub use crate::optim::*;
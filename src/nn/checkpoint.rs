@@ -0,0 +1,279 @@
+use crate::devices::Device;
+use crate::gradients::{CanUpdateWithGradients, GradientProvider, OwnedTape, Tape, UnusedTensors};
+use crate::prelude::*;
+use std::io::{Read, Seek, Write};
+use std::rc::Rc;
+use zip::{result::ZipResult, ZipArchive, ZipWriter};
+
+/// Activation checkpointing around `M`: the forward pass through `M` doesn't record anything on
+/// the tape, so none of the intermediate values inside `M` are kept until backward. Instead,
+/// the forward pass is recomputed from the input during backward. This trades an extra
+/// forward pass of `M` for the memory of its intermediate values.
+///
+/// `M` is stored in an [Rc] so the tape can hold onto it until backward. This means the
+/// parameters of `M` can't be mutated (e.g. by an optimizer) while a tape that
+/// went through `M` is still alive.
+///
+/// [ModuleMut::forward_mut()] calls [Module::forward()] of `M`, since the forward pass
+/// has to be exactly the same when recomputed. So modules that need [ModuleMut] with a tape
+/// (e.g. [Dropout]) can't be checkpointed.
+///
+/// # Generics
+/// - `M`: The underlying module to checkpoint.
+///
+/// # Examples
+/// ```rust
+/// # use dfdx::prelude::*;
+/// type Model = Repeated<Checkpoint<(Linear<10, 10>, ReLU)>, 5>;
+/// let model: Model = Default::default();
+/// let y: Tensor1D<10, OwnedTape> = model.forward(Tensor1D::zeros().traced());
+/// let gradients = y.mean().backward();
+/// ```
+#[derive(Debug, Default)]
+pub struct Checkpoint<M>(pub Rc<M>);
+
+impl<M> Checkpoint<M> {
+    /// Mutable access to `M`.
+    ///
+    /// **Panics** if a tape that still needs `M` for backward is alive.
+    pub fn module_mut(&mut self) -> &mut M {
+        Rc::get_mut(&mut self.0)
+            .expect("Tried to mutate a Checkpoint while a tape still needs it for backward")
+    }
+}
+
+impl<M: Clone> Clone for Checkpoint<M> {
+    /// Clones `M`, so the two checkpoints don't share parameters.
+    fn clone(&self) -> Self {
+        Self(Rc::new(M::clone(&self.0)))
+    }
+}
+
+impl<M: CanUpdateWithGradients> CanUpdateWithGradients for Checkpoint<M> {
+    /// Pass through to `M`'s [CanUpdateWithGradients].
+    fn update<G: GradientProvider>(&mut self, grads: &mut G, unused: &mut UnusedTensors) {
+        self.module_mut().update(grads, unused);
+    }
+}
+
+impl<M: ResetParams> ResetParams for Checkpoint<M> {
+    /// Pass through to `M`'s [ResetParams].
+    fn reset_params<R: rand::Rng>(&mut self, rng: &mut R) {
+        self.module_mut().reset_params(rng);
+    }
+}
+
+macro_rules! checkpoint_impl {
+    ($typename:ident, [$($Vs:tt),*]) => {
+impl<$(const $Vs: usize, )* Inner> Module<$typename<$($Vs, )* NoneTape>> for Checkpoint<Inner>
+where
+    Inner: Module<$typename<$($Vs, )* NoneTape>>,
+{
+    type Output = Inner::Output;
+    /// Nothing is recorded, so this is just `M`'s forward.
+    fn forward(&self, x: $typename<$($Vs, )* NoneTape>) -> Self::Output {
+        self.0.forward(x)
+    }
+}
+
+impl<$(const $Vs: usize, )* Inner, Out> Module<$typename<$($Vs, )* OwnedTape>> for Checkpoint<Inner>
+where
+    Inner: 'static
+        + Module<$typename<$($Vs, )* NoneTape>, Output = Out::NoTape>
+        + Module<$typename<$($Vs, )* OwnedTape>, Output = Out>,
+    Out: Tensor<Dtype = f32, Tape = OwnedTape>,
+{
+    type Output = Out;
+    /// Calls forward on `M` without the tape, and adds an operation that recomputes
+    /// the forward with the tape during backward.
+    fn forward(&self, x: $typename<$($Vs, )* OwnedTape>) -> Self::Output {
+        let (x, mut tape) = x.split_tape();
+        let result: Out::NoTape = self.0.forward(x.duplicate());
+        let module = self.0.clone();
+        let _result = result.phantom();
+        tape.add_backward_op(move |grads| {
+            let (y, mut recomputed) = module.forward(x.traced()).split_tape();
+            let _y = y.phantom();
+            recomputed.add_backward_op(move |grads| {
+                let (y_grad, result_grad) = grads.mut_and_ref(&_y, &_result);
+                Out::Device::add(y_grad, result_grad);
+            });
+            recomputed.0.execute_into(grads);
+        });
+        result.put_tape(tape)
+    }
+}
+    };
+}
+
+checkpoint_impl!(Tensor0D, []);
+checkpoint_impl!(Tensor1D, [M]);
+checkpoint_impl!(Tensor2D, [M, N]);
+checkpoint_impl!(Tensor3D, [M, N, O]);
+checkpoint_impl!(Tensor4D, [M, N, O, P]);
+
+impl<T, M> ModuleMut<T> for Checkpoint<M>
+where
+    Self: Module<T>,
+{
+    type Output = <Self as Module<T>>::Output;
+    fn forward_mut(&mut self, input: T) -> Self::Output {
+        self.forward(input)
+    }
+}
+
+impl<M: SaveToNpz> SaveToNpz for Checkpoint<M> {
+    /// Pass through to `M`'s [SaveToNpz].
+    fn write<W: Write + Seek>(&self, p: &str, w: &mut ZipWriter<W>) -> ZipResult<()> {
+        self.0.write(p, w)
+    }
+}
+
+impl<M: LoadFromNpz> LoadFromNpz for Checkpoint<M> {
+    /// Pass through to `M`'s [LoadFromNpz].
+    fn read<R: Read + Seek>(&mut self, p: &str, r: &mut ZipArchive<R>) -> Result<(), NpzError> {
+        self.module_mut().read(p, r)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nn::tests::SimpleGradients;
+    use crate::tests::assert_close;
+    use rand::{prelude::StdRng, SeedableRng};
+    use tempfile::NamedTempFile;
+
+    type Mlp = (Linear<3, 5>, Tanh, Linear<5, 2>);
+
+    #[test]
+    fn test_checkpoint_forward() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut model: Checkpoint<Mlp> = Default::default();
+        model.reset_params(&mut rng);
+        let x: Tensor2D<4, 3> = TensorCreator::randn(&mut rng);
+        let y1 = model.forward(x.clone());
+        let y2 = model.forward(x.trace());
+        let y3 = model.0.forward(x.trace());
+        assert_eq!(y1.data(), y2.data());
+        assert_eq!(y1.data(), y3.data());
+    }
+
+    #[test]
+    fn test_checkpoint_gradients_match() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut model: Checkpoint<Mlp> = Default::default();
+        model.reset_params(&mut rng);
+        let x: Tensor2D<4, 3> = TensorCreator::randn(&mut rng);
+
+        let g1 = model
+            .forward(x.trace())
+            .square()
+            .mean::<_, AllAxes>()
+            .backward();
+        let g2 = model
+            .0
+            .forward(x.trace())
+            .square()
+            .mean::<_, AllAxes>()
+            .backward();
+        let inner = &model.0;
+        assert_eq!(g1.ref_gradient(&x), g2.ref_gradient(&x));
+        assert_eq!(
+            g1.ref_gradient(&inner.0.weight),
+            g2.ref_gradient(&inner.0.weight)
+        );
+        assert_eq!(
+            g1.ref_gradient(&inner.0.bias),
+            g2.ref_gradient(&inner.0.bias)
+        );
+        assert_eq!(
+            g1.ref_gradient(&inner.2.weight),
+            g2.ref_gradient(&inner.2.weight)
+        );
+        assert_eq!(
+            g1.ref_gradient(&inner.2.bias),
+            g2.ref_gradient(&inner.2.bias)
+        );
+    }
+
+    #[test]
+    fn test_nested_checkpoints() {
+        let mut rng = StdRng::seed_from_u64(2);
+        let mut model: (
+            Checkpoint<Mlp>,
+            Checkpoint<(Linear<2, 2>, Checkpoint<ReLU>)>,
+        ) = Default::default();
+        model.reset_params(&mut rng);
+        let x: Tensor1D<3> = TensorCreator::randn(&mut rng);
+        gradcheck(|x| model.forward(x).square().sum(), &x, Default::default()).unwrap();
+        gradcheck_module(
+            &model,
+            |m| m.forward(x.trace()).square().sum(),
+            Default::default(),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_checkpoint_update() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let mut model: Checkpoint<Linear<3, 2>> = Default::default();
+        model.reset_params(&mut rng);
+        let x: Tensor1D<3> = TensorCreator::randn(&mut rng);
+        let g = model.forward(x.trace()).sum().backward();
+        let mut unused = Default::default();
+        model.update(&mut SimpleGradients(g), &mut unused);
+        assert!(unused.is_empty());
+    }
+
+    #[test]
+    #[should_panic = "still needs it for backward"]
+    fn test_checkpoint_update_with_tape_alive() {
+        let mut model: Checkpoint<Linear<3, 2>> = Default::default();
+        let _y = model.forward(Tensor1D::zeros().traced());
+        model.update(
+            &mut SimpleGradients(Default::default()),
+            &mut Default::default(),
+        );
+    }
+
+    #[test]
+    fn test_checkpoint_clone_is_independent() {
+        let mut rng = StdRng::seed_from_u64(4);
+        let model: Checkpoint<Linear<3, 2>> = Default::default();
+        let mut cloned = model.clone();
+        cloned.reset_params(&mut rng);
+        assert_eq!(model.0.weight.data(), &[[0.0; 3]; 2]);
+        assert_ne!(cloned.0.weight.data(), &[[0.0; 3]; 2]);
+    }
+
+    #[test]
+    fn test_save_load_checkpoint() {
+        let mut rng = StdRng::seed_from_u64(5);
+        let mut saved_model: Checkpoint<Linear<5, 3>> = Default::default();
+        saved_model.reset_params(&mut rng);
+
+        let file = NamedTempFile::new().expect("failed to create tempfile");
+        assert!(saved_model.save(file.path().to_str().unwrap()).is_ok());
+
+        let mut loaded_model: Checkpoint<Linear<5, 3>> = Default::default();
+        assert!(loaded_model.load(file.path().to_str().unwrap()).is_ok());
+        assert_eq!(loaded_model.0.weight.data(), saved_model.0.weight.data());
+        assert_eq!(loaded_model.0.bias.data(), saved_model.0.bias.data());
+    }
+
+    #[test]
+    fn test_checkpoint_npz_keys_match_module() {
+        let mut rng = StdRng::seed_from_u64(6);
+        let mut saved_model: Checkpoint<Linear<5, 3>> = Default::default();
+        saved_model.reset_params(&mut rng);
+
+        let file = NamedTempFile::new().expect("failed to create tempfile");
+        assert!(saved_model.save(file.path().to_str().unwrap()).is_ok());
+
+        let mut loaded_model: Linear<5, 3> = Default::default();
+        assert!(loaded_model.load(file.path().to_str().unwrap()).is_ok());
+        assert_close(loaded_model.weight.data(), saved_model.0.weight.data());
+    }
+}
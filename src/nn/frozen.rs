@@ -0,0 +1,224 @@
+use crate::arrays::HasArrayType;
+use crate::gradients::{CanUpdateWithGradients, GradientProvider, Tape, UnusedTensors};
+use crate::prelude::*;
+use crate::unique_id::HasUniqueId;
+use std::io::{Read, Seek, Write};
+use zip::{result::ZipResult, ZipArchive, ZipWriter};
+
+/// Freezes the parameters of `M`: optimizers skip them entirely, so they are never
+/// updated, and they are **not** reported as unused in [UnusedParamsError]. This is
+/// useful for fine-tuning part of a pretrained model.
+///
+/// No gradients are stored for the frozen parameters. [Module] runs on a copy of `M` whose
+/// parameters have new ids (the data is shared, so this doesn't copy them). [ModuleMut] runs
+/// on `M` itself, so its state (e.g. the rng of [Dropout]) is still updated, and removes the
+/// gradients of the frozen parameters once the backward operations of `M` have run.
+///
+/// Gradients still flow through `M` to its input, so modules before a frozen module are still
+/// trained. Since the operations in `M` still compute the gradients of its parameters when the
+/// input has a tape, it's cheaper to pass frozen modules at the start of a model a tensor
+/// without a tape, and only start tracing after them.
+///
+/// [ResetParams], [SaveToNpz] and [LoadFromNpz] pass through to `M`, so pretrained
+/// parameters can be loaded into a frozen module, and the keys match those of `M`.
+///
+/// # Generics
+/// - `M`: The module whose parameters are frozen.
+///
+/// # Examples
+/// ```rust
+/// # use dfdx::prelude::*;
+/// type Model = (Frozen<(Linear<5, 5>, ReLU)>, Linear<5, 2>);
+/// let mut model: Model = Default::default();
+/// let mut opt: Sgd<Model> = Default::default();
+/// let x: Tensor1D<5> = Tensor1D::zeros();
+/// let loss = model.forward(x.trace()).square().mean();
+/// // the frozen parameters don't cause an `UnusedParamsError`
+/// opt.update(&mut model, loss.backward()).expect("");
+/// ```
+///
+/// Only tracing after the frozen module:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// # let model: (Frozen<(Linear<5, 5>, ReLU)>, Linear<5, 2>) = Default::default();
+/// let x: Tensor1D<5> = Tensor1D::zeros();
+/// let features = model.0.forward(x);
+/// let loss = model.1.forward(features.traced()).square().mean();
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct Frozen<M>(pub M);
+
+impl<M> CanUpdateWithGradients for Frozen<M> {
+    /// Does nothing, since the parameters are frozen.
+    fn update<G: GradientProvider>(&mut self, _: &mut G, _: &mut UnusedTensors) {}
+}
+
+impl<M: ResetParams> ResetParams for Frozen<M> {
+    /// Pass through to `M`'s [ResetParams].
+    fn reset_params<R: rand::Rng>(&mut self, rng: &mut R) {
+        self.0.reset_params(rng);
+    }
+}
+
+impl<T, M: Module<T> + Clone> Module<T> for Frozen<M> {
+    type Output = M::Output;
+    /// Calls `M`'s [Module] on a copy of `M`, so gradients aren't stored for its parameters.
+    fn forward(&self, x: T) -> Self::Output {
+        self.0.clone().forward(x)
+    }
+}
+
+impl<T, M> ModuleMut<T> for Frozen<M>
+where
+    T: Tensor<Dtype = f32>,
+    M: ModuleMut<T> + CanUpdateWithGradients,
+{
+    type Output = M::Output;
+    /// Calls `M`'s [ModuleMut], and removes the gradients of its parameters during backward.
+    fn forward_mut(&mut self, x: T) -> Self::Output {
+        if !T::Tape::OWNS_TAPE {
+            return self.0.forward_mut(x);
+        }
+        // all the parameters are missing from `NoGradients`, so this collects their ids
+        let mut params: UnusedTensors = Default::default();
+        self.0.update(&mut NoGradients, &mut params);
+        let (x, mut tape) = x.split_tape();
+        // added before the operations of `M`, so this runs after all their backward operations
+        tape.add_backward_op(move |grads| {
+            for id in params.ids.iter() {
+                grads.remove_by_id(id);
+            }
+        });
+        self.0.forward_mut(x.put_tape(tape))
+    }
+}
+
+/// A [GradientProvider] without any gradients.
+struct NoGradients;
+
+impl GradientProvider for NoGradients {
+    fn gradient<P>(&mut self, _: &P) -> Option<Box<P::Array>>
+    where
+        P: HasUniqueId + HasArrayType<Dtype = f32> + HasDevice,
+    {
+        None
+    }
+}
+
+impl<M: SaveToNpz> SaveToNpz for Frozen<M> {
+    /// Pass through to `M`'s [SaveToNpz].
+    fn write<W: Write + Seek>(&self, p: &str, w: &mut ZipWriter<W>) -> ZipResult<()> {
+        self.0.write(p, w)
+    }
+}
+
+impl<M: LoadFromNpz> LoadFromNpz for Frozen<M> {
+    /// Pass through to `M`'s [LoadFromNpz].
+    fn read<R: Read + Seek>(&mut self, p: &str, r: &mut ZipArchive<R>) -> Result<(), NpzError> {
+        self.0.read(p, r)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nn::tests::SimpleGradients;
+    use rand::{prelude::StdRng, SeedableRng};
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn test_frozen_params_not_updated() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut model: (Linear<3, 3>, Frozen<Linear<3, 3>>, Linear<3, 2>) = Default::default();
+        model.reset_params(&mut rng);
+        let frozen = model.1.clone();
+        let first = model.0.clone();
+        let last = model.2.clone();
+
+        let mut opt: Sgd<_> = Default::default();
+        let x: Tensor1D<3> = TensorCreator::randn(&mut rng);
+        let loss = model.forward(x.trace()).square().mean();
+        opt.update(&mut model, loss.backward()).expect("");
+
+        assert_eq!(model.1 .0.weight.data(), frozen.0.weight.data());
+        assert_eq!(model.1 .0.bias.data(), frozen.0.bias.data());
+        assert_ne!(model.0.weight.data(), first.weight.data());
+        assert_ne!(model.2.weight.data(), last.weight.data());
+    }
+
+    #[test]
+    fn test_frozen_params_not_unused() {
+        let mut model: (Frozen<Linear<3, 3>>, Linear<3, 2>) = Default::default();
+        let mut unused: UnusedTensors = Default::default();
+        model.update(&mut SimpleGradients(Default::default()), &mut unused);
+        assert_eq!(unused.len(), 2);
+        assert!(unused.ids.contains(model.1.weight.id()));
+        assert!(unused.ids.contains(model.1.bias.id()));
+    }
+
+    #[test]
+    fn test_frozen_forward_and_gradients_match() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut model: (Linear<3, 4>, Tanh, Linear<4, 2>) = Default::default();
+        model.reset_params(&mut rng);
+        let frozen = (model.0.clone(), model.1, Frozen(model.2.clone()));
+        let x: Tensor2D<5, 3> = TensorCreator::randn(&mut rng);
+
+        let y1 = model.forward(x.trace());
+        let y2 = frozen.forward(x.trace());
+        assert_eq!(y1.data(), y2.data());
+
+        let g1 = y1.square().mean::<_, AllAxes>().backward();
+        let g2 = y2.square().mean::<_, AllAxes>().backward();
+        assert_eq!(g1.ref_gradient(&x), g2.ref_gradient(&x));
+        assert_eq!(
+            g1.ref_gradient(&model.0.weight),
+            g2.ref_gradient(&frozen.0.weight)
+        );
+    }
+
+    #[test]
+    fn test_frozen_params_have_no_gradients() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let mut model: (Linear<3, 4>, Frozen<Linear<4, 2>>) = Default::default();
+        model.reset_params(&mut rng);
+        let x: Tensor1D<3> = TensorCreator::randn(&mut rng);
+        let y1 = model.forward(x.trace());
+        let y2 = model.forward_mut(x.trace());
+        for y in [y1, y2] {
+            let gradients = y.square().mean().backward();
+            let frozen = &model.1 .0;
+            assert!(gradients.ref_gradient_by_id(frozen.weight.id()).is_none());
+            assert!(gradients.ref_gradient_by_id(frozen.bias.id()).is_none());
+            assert!(gradients.ref_gradient_by_id(model.0.weight.id()).is_some());
+            assert!(gradients.ref_gradient_by_id(x.id()).is_some());
+        }
+    }
+
+    #[test]
+    fn test_frozen_forward_mut_updates_state() {
+        let mut frozen = Frozen(Dropout::new(0.5, 0));
+        let mut dropout = Dropout::new(0.5, 0);
+        let x: Tensor1D<100> = TensorCreator::ones();
+        let y1 = frozen.forward_mut(x.trace());
+        let y2 = frozen.forward_mut(x.trace());
+        assert_ne!(y1.data(), y2.data());
+        assert_eq!(y1.data(), dropout.forward_mut(x.trace()).data());
+        assert_eq!(y2.data(), dropout.forward_mut(x.trace()).data());
+    }
+
+    #[test]
+    fn test_save_load_frozen() {
+        let mut rng = StdRng::seed_from_u64(2);
+        let mut saved_model: Linear<5, 3> = Default::default();
+        saved_model.reset_params(&mut rng);
+
+        let file = NamedTempFile::new().expect("failed to create tempfile");
+        assert!(saved_model.save(file.path().to_str().unwrap()).is_ok());
+
+        let mut loaded_model: Frozen<Linear<5, 3>> = Default::default();
+        assert!(loaded_model.load(file.path().to_str().unwrap()).is_ok());
+        assert_eq!(loaded_model.0.weight.data(), saved_model.weight.data());
+        assert_eq!(loaded_model.0.bias.data(), saved_model.bias.data());
+    }
+}
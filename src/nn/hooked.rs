@@ -0,0 +1,205 @@
+use crate::gradients::{CanUpdateWithGradients, GradientProvider, UnusedTensors};
+use crate::prelude::*;
+use std::io::{Read, Seek, Write};
+use zip::{result::ZipResult, ZipArchive, ZipWriter};
+
+/// Registers `hook` as a backward hook on the output of `module` every forward, using
+/// [register_hook()]. The hook receives the gradient of the output, and returns the
+/// gradient to backprop through `module`.
+///
+/// Since every forward needs its own copy of the hook, `F` must be [Clone]. Closures that only
+/// capture [std::rc::Rc]s are, and so are function pointers, which can be used to name the
+/// type of the model.
+///
+/// # Generics
+/// - `M`: The module whose output is hooked.
+/// - `F`: The hook.
+///
+/// # Examples
+/// Gradient reversal:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// type Model = (Linear<2, 2>, Hooked<ReLU, fn(Tensor1D<2>) -> Tensor1D<2>>);
+/// let model: Model = (Default::default(), Hooked { module: ReLU, hook: negate });
+/// let x = tensor([1.0, -1.0]);
+/// let y = model.forward(x.trace());
+/// ```
+///
+/// Per layer gradient logging:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// # use std::{cell::RefCell, rc::Rc};
+/// let log = Rc::new(RefCell::new(Vec::new()));
+/// let log_ = log.clone();
+/// let model = Hooked {
+///     module: Linear::<3, 2>::default(),
+///     hook: move |g: Tensor1D<2>| {
+///         log_.borrow_mut().push(*g.data());
+///         g
+///     },
+/// };
+/// let _ = model.forward(Tensor1D::zeros().traced()).sum().backward();
+/// assert_eq!(log.borrow().as_slice(), &[[1.0, 1.0]]);
+/// ```
+#[derive(Clone)]
+pub struct Hooked<M, F> {
+    pub module: M,
+    pub hook: F,
+}
+
+impl<M: std::fmt::Debug, F> std::fmt::Debug for Hooked<M, F> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Hooked")
+            .field("module", &self.module)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<M: CanUpdateWithGradients, F> CanUpdateWithGradients for Hooked<M, F> {
+    /// Pass through to `M`'s [CanUpdateWithGradients].
+    fn update<G: GradientProvider>(&mut self, grads: &mut G, unused: &mut UnusedTensors) {
+        self.module.update(grads, unused);
+    }
+}
+
+impl<M: ResetParams, F> ResetParams for Hooked<M, F> {
+    /// Pass through to `M`'s [ResetParams].
+    fn reset_params<R: rand::Rng>(&mut self, rng: &mut R) {
+        self.module.reset_params(rng);
+    }
+}
+
+impl<T, M, F> Module<T> for Hooked<M, F>
+where
+    M: Module<T>,
+    M::Output: Tensor<Dtype = f32>,
+    F: 'static + Clone + FnOnce(<M::Output as Tensor>::NoTape) -> <M::Output as Tensor>::NoTape,
+{
+    type Output = M::Output;
+    /// Calls forward on `M`, and registers a copy of the hook on the output.
+    fn forward(&self, x: T) -> Self::Output {
+        register_hook(self.module.forward(x), self.hook.clone())
+    }
+}
+
+impl<T, M, F> ModuleMut<T> for Hooked<M, F>
+where
+    M: ModuleMut<T>,
+    M::Output: Tensor<Dtype = f32>,
+    F: 'static + Clone + FnOnce(<M::Output as Tensor>::NoTape) -> <M::Output as Tensor>::NoTape,
+{
+    type Output = M::Output;
+    /// Calls forward_mut on `M`, and registers a copy of the hook on the output.
+    fn forward_mut(&mut self, x: T) -> Self::Output {
+        register_hook(self.module.forward_mut(x), self.hook.clone())
+    }
+}
+
+impl<M: SaveToNpz, F> SaveToNpz for Hooked<M, F> {
+    /// Pass through to `M`'s [SaveToNpz].
+    fn write<W: Write + Seek>(&self, p: &str, w: &mut ZipWriter<W>) -> ZipResult<()> {
+        self.module.write(p, w)
+    }
+}
+
+impl<M: LoadFromNpz, F> LoadFromNpz for Hooked<M, F> {
+    /// Pass through to `M`'s [LoadFromNpz].
+    fn read<R: Read + Seek>(&mut self, p: &str, r: &mut ZipArchive<R>) -> Result<(), NpzError> {
+        self.module.read(p, r)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::assert_close;
+    use rand::{prelude::StdRng, SeedableRng};
+    use std::{cell::RefCell, rc::Rc};
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn test_gradient_reversal() {
+        let mut rng = StdRng::seed_from_u64(0);
+        type Reversal = Hooked<Tanh, fn(Tensor2D<4, 2>) -> Tensor2D<4, 2>>;
+        let reversal: Reversal = Hooked {
+            module: Tanh,
+            hook: negate,
+        };
+        let mut model: (Linear<3, 2>, Reversal) = (Default::default(), reversal);
+        model.reset_params(&mut rng);
+        let x: Tensor2D<4, 3> = TensorCreator::randn(&mut rng);
+
+        let y1 = model.forward(x.trace());
+        let y2 = model.1.module.forward(model.0.forward(x.trace()));
+        assert_eq!(y1.data(), y2.data());
+
+        let g1 = y1.square().mean::<_, AllAxes>().backward();
+        let g2 = y2.square().mean::<_, AllAxes>().backward();
+        let w = &model.0.weight;
+        assert_close(
+            g1.ref_gradient(w),
+            negate(tensor(*g2.ref_gradient(w))).data(),
+        );
+        assert_close(
+            g1.ref_gradient(&x),
+            negate(tensor(*g2.ref_gradient(&x))).data(),
+        );
+    }
+
+    #[test]
+    fn test_hook_called_every_forward() {
+        let calls = Rc::new(RefCell::new(0));
+        let calls_ = calls.clone();
+        let mut model = Hooked {
+            module: Linear::<2, 2>::default(),
+            hook: move |g: Tensor1D<2>| {
+                *calls_.borrow_mut() += 1;
+                g
+            },
+        };
+        let _ = model.forward(Tensor1D::zeros().traced()).sum().backward();
+        let _ = model
+            .forward_mut(Tensor1D::zeros().traced())
+            .sum()
+            .backward();
+        let _ = model.forward(Tensor1D::zeros());
+        assert_eq!(*calls.borrow(), 2);
+    }
+
+    #[test]
+    fn test_gradient_clipping_hook() {
+        let model = Hooked {
+            module: Linear::<2, 2>::default(),
+            hook: |g: Tensor1D<2>| g.clamp(-0.5, 0.5),
+        };
+        let gradients = model.forward(Tensor1D::zeros().traced()).sum().backward();
+        assert_eq!(gradients.ref_gradient(&model.module.bias), &[0.5, 0.5]);
+    }
+
+    #[test]
+    fn test_save_load_hooked() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut saved_model = Hooked {
+            module: Linear::<5, 3>::default(),
+            hook: negate::<Tensor1D<3>>,
+        };
+        saved_model.reset_params(&mut rng);
+
+        let file = NamedTempFile::new().expect("failed to create tempfile");
+        assert!(saved_model.save(file.path().to_str().unwrap()).is_ok());
+
+        let mut loaded_model = Hooked {
+            module: Linear::<5, 3>::default(),
+            hook: negate::<Tensor1D<3>>,
+        };
+        assert!(loaded_model.load(file.path().to_str().unwrap()).is_ok());
+        assert_eq!(
+            loaded_model.module.weight.data(),
+            saved_model.module.weight.data()
+        );
+        assert_eq!(
+            loaded_model.module.bias.data(),
+            saved_model.module.bias.data()
+        );
+    }
+}
@@ -76,8 +76,11 @@
 //! ```
 
 mod activations;
+mod checkpoint;
 mod dropout;
+mod frozen;
 mod generalized_residual;
+mod hooked;
 mod impl_module_for_tuples;
 mod layer_norm;
 mod linear;
@@ -89,8 +92,11 @@ mod residual;
 mod split_into;
 
 pub use activations::*;
+pub use checkpoint::*;
 pub use dropout::*;
+pub use frozen::*;
 pub use generalized_residual::*;
+pub use hooked::*;
 pub use impl_module_for_tuples::*;
 pub use layer_norm::*;
 pub use linear::*;
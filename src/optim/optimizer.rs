@@ -26,6 +26,8 @@ pub trait Optimizer<M: CanUpdateWithGradients> {
 /// An error indicating that a parameter was not used in gradient
 /// computation, and was therefore not present in [Gradients]
 /// while a [CanUpdateWithGradients] was trying to update it.
+///
+/// Parameters that are intentionally not trained should be wrapped in [crate::nn::Frozen].
 #[derive(Debug)]
 pub struct UnusedParamsError(UnusedTensors);
 
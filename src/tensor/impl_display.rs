@@ -0,0 +1,166 @@
+use super::*;
+use crate::arrays::{HasArrayData, HasShape};
+use crate::devices::as_slice;
+use crate::gradients::Tape;
+use std::fmt::{Display, Formatter, Result};
+
+/// The default number of digits after the decimal point.
+const DEFAULT_PRECISION: usize = 4;
+
+/// Tensors with more elements than this are summarized.
+const SUMMARIZE_THRESHOLD: usize = 1000;
+
+/// The number of elements at the start and end of each axis that are shown when summarizing.
+const EDGE_ITEMS: usize = 3;
+
+/// Writes the type of `t` (with tape `H`), followed by its data in numpy style nested brackets.
+/// See the "Printing tensors" section of [crate::tensor] for the format options.
+fn fmt_tensor<T: HasArrayData<Dtype = f32>, H>(t: &T, name: &str, f: &mut Formatter<'_>) -> Result
+where
+    T::Array: HasShape,
+{
+    let shape = T::Array::SHAPE;
+    write!(f, "{}<", name)?;
+    for dim in shape {
+        write!(f, "{}, ", dim)?;
+    }
+    writeln!(f, "{}>", type_name::<H>())?;
+
+    let data = as_slice(t.data());
+    let precision = f.precision().unwrap_or(DEFAULT_PRECISION);
+    let summarize = !f.alternate() && data.len() > SUMMARIZE_THRESHOLD;
+    let width = data
+        .iter()
+        .map(|x| format!("{:.*}", precision, x).len())
+        .max()
+        .unwrap_or(0);
+    let opts = Options {
+        precision,
+        width,
+        summarize,
+    };
+    fmt_nested(f, data, shape, 0, &opts)
+}
+
+struct Options {
+    precision: usize,
+    width: usize,
+    summarize: bool,
+}
+
+/// Writes `data` with `shape` as nested brackets, where `depth` is the number of brackets
+/// already opened.
+fn fmt_nested(
+    f: &mut Formatter<'_>,
+    data: &[f32],
+    shape: &[usize],
+    depth: usize,
+    opts: &Options,
+) -> Result {
+    if shape.is_empty() {
+        return write!(f, "{:>w$.p$}", data[0], w = opts.width, p = opts.precision);
+    }
+
+    let len = shape[0];
+    let stride = data.len().checked_div(len).unwrap_or(0);
+    let separator = if shape.len() == 1 {
+        ", ".to_string()
+    } else {
+        format!(",{}{}", "\n".repeat(shape.len() - 1), " ".repeat(depth + 1))
+    };
+
+    write!(f, "[")?;
+    for i in 0..len {
+        if opts.summarize && len > 2 * EDGE_ITEMS && i == EDGE_ITEMS {
+            write!(f, "...{}", separator)?;
+        }
+        if opts.summarize && len > 2 * EDGE_ITEMS && (EDGE_ITEMS..len - EDGE_ITEMS).contains(&i) {
+            continue;
+        }
+        let sub = &data[i * stride..(i + 1) * stride];
+        fmt_nested(f, sub, &shape[1..], depth + 1, opts)?;
+        if i + 1 < len {
+            write!(f, "{}", separator)?;
+        }
+    }
+    write!(f, "]")
+}
+
+/// The name of `T` without its module path.
+fn type_name<T>() -> &'static str {
+    let name = std::any::type_name::<T>();
+    let end = name.find('<').unwrap_or(name.len());
+    match name[..end].rfind("::") {
+        Some(start) => &name[start + 2..],
+        None => name,
+    }
+}
+
+macro_rules! tensor_impl {
+    ($typename:ident, [$($Vs:tt),*]) => {
+impl<$(const $Vs: usize, )* H: Tape> Display for $typename<$($Vs, )* H> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        fmt_tensor::<_, H>(self, stringify!($typename), f)
+    }
+}
+    };
+}
+
+tensor_impl!(Tensor0D, []);
+tensor_impl!(Tensor1D, [M]);
+tensor_impl!(Tensor2D, [M, N]);
+tensor_impl!(Tensor3D, [M, N, O]);
+tensor_impl!(Tensor4D, [M, N, O, P]);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display_0d() {
+        assert_eq!(format!("{}", tensor(1.5)), "Tensor0D<NoneTape>\n1.5000");
+        assert_eq!(format!("{:.0}", tensor(-2.0)), "Tensor0D<NoneTape>\n-2");
+    }
+
+    #[test]
+    fn test_display_1d() {
+        let t = tensor([1.0, -10.0, 0.25]);
+        assert_eq!(
+            format!("{:.2}", t),
+            "Tensor1D<3, NoneTape>\n[  1.00, -10.00,   0.25]"
+        );
+    }
+
+    #[test]
+    fn test_display_3d() {
+        let t: Tensor3D<2, 2, 2> = TensorCreator::arange();
+        assert_eq!(
+            format!("{:.0}", t.traced()),
+            "Tensor3D<2, 2, 2, OwnedTape>\n[[[0, 1],\n  [2, 3]],\n\n [[4, 5],\n  [6, 7]]]"
+        );
+    }
+
+    #[test]
+    fn test_display_summarized() {
+        let t: Tensor2D<2, 1000> = TensorCreator::arange();
+        let s = format!("{:.0}", t);
+        assert_eq!(
+            s,
+            "Tensor2D<2, 1000, NoneTape>\n[[   0,    1,    2, ...,  997,  998,  999],\n [1000, 1001, 1002, ..., 1997, 1998, 1999]]"
+        );
+
+        let s = format!("{:#.0}", t);
+        assert!(!s.contains("..."));
+        assert!(s.contains(" 500,  501,"));
+    }
+
+    #[test]
+    fn test_display_summarized_outer_axis() {
+        let t: Tensor2D<1001, 1> = TensorCreator::zeros();
+        let s = format!("{:.0}", t);
+        assert_eq!(
+            s,
+            "Tensor2D<1001, 1, NoneTape>\n[[0],\n [0],\n [0],\n ...,\n [0],\n [0],\n [0]]"
+        );
+    }
+}
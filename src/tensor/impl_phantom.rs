@@ -12,6 +12,16 @@ pub struct PhantomTensor<T> {
     marker: PhantomData<*const T>,
 }
 
+impl<T> PhantomTensor<T> {
+    /// Creates a [PhantomTensor] with a specific [UniqueId].
+    pub(crate) fn new(id: UniqueId) -> Self {
+        Self {
+            id,
+            marker: PhantomData,
+        }
+    }
+}
+
 impl<T> HasUniqueId for PhantomTensor<T> {
     fn id(&self) -> &UniqueId {
         &self.id
@@ -14,7 +14,8 @@ where
     HOut: Tape,
 {
     type Output = $typename<$($Vs, )* HOut>;
-    fn put_tape(self, tape: HOut) -> Self::Output {
+    fn put_tape(self, mut tape: HOut) -> Self::Output {
+        tape.on_put_tape(&self);
         Self::Output { id: self.id, data: self.data, tape }
     }
 }
@@ -33,10 +33,10 @@ impl<$(const $Vs: usize, )* H: Tape> Tensor for $struct<$($Vs, )* H> {
     type NoTape = $struct<$($Vs, )* NoneTape>;
 
     fn split_tape(self) -> (Self::NoTape, Self::Tape) {
-        (
-            Self::NoTape { id: self.id, data: self.data, tape: Default::default() },
-            self.tape,
-        )
+        let t = Self::NoTape { id: self.id, data: self.data, tape: Default::default() };
+        let mut tape = self.tape;
+        tape.add_input(&t);
+        (t, tape)
     }
 
     fn duplicate(&self) -> Self::NoTape {
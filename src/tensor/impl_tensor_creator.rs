@@ -1,10 +1,15 @@
 use super::*;
-use crate::devices::{AllocateZeros, FillElements};
+use crate::arrays::CountElements;
+use crate::devices::{AllocateZeros, DeviceMatrix, FillElements};
 use crate::gradients::NoneTape;
 use crate::prelude::*;
 use crate::unique_id::unique_id;
 use rand::prelude::Distribution;
-use rand_distr::{num_traits::One, Standard, StandardNormal};
+use rand_distr::uniform::SampleUniform;
+use rand_distr::{
+    num_traits::Float, num_traits::NumCast, num_traits::One, Normal, Standard, StandardNormal,
+    Uniform,
+};
 
 /// Something that can be created - currently only implemented for tensors with no tapes.
 pub trait TensorCreator: Sized + HasDevice {
@@ -29,6 +34,86 @@ pub trait TensorCreator: Sized + HasDevice {
         Self::new_boxed(Self::Device::filled(&mut |v| *v = One::one()))
     }
 
+    /// Creates a tensor filled with `value`.
+    ///
+    /// ```rust
+    /// # use dfdx::prelude::*;
+    /// let t: Tensor2D<2, 3> = TensorCreator::full(0.5);
+    /// assert_eq!(t.data(), &[[0.5; 3]; 2]);
+    /// ```
+    fn full(value: Self::Dtype) -> Self
+    where
+        Self::Dtype: Clone,
+    {
+        Self::new_boxed(Self::Device::filled(&mut |v| *v = value.clone()))
+    }
+
+    /// Creates a tensor filled with `0, 1, 2, ...` in row major order, so the
+    /// last axis changes the fastest.
+    ///
+    /// ```rust
+    /// # use dfdx::prelude::*;
+    /// let t: Tensor2D<2, 3> = TensorCreator::arange();
+    /// assert_eq!(t.data(), &[[0.0, 1.0, 2.0], [3.0, 4.0, 5.0]]);
+    /// ```
+    fn arange() -> Self
+    where
+        Self::Dtype: Float,
+    {
+        let mut i = 0;
+        Self::new_boxed(Self::Device::filled(&mut |v| {
+            *v = <Self::Dtype as NumCast>::from(i).unwrap();
+            i += 1;
+        }))
+    }
+
+    /// Creates a tensor filled with evenly spaced values from `start` to `end` (inclusive),
+    /// in row major order like [TensorCreator::arange()].
+    ///
+    /// ```rust
+    /// # use dfdx::prelude::*;
+    /// let t: Tensor1D<5> = TensorCreator::linspace(0.0, 1.0);
+    /// assert_eq!(t.data(), &[0.0, 0.25, 0.5, 0.75, 1.0]);
+    ///
+    /// let t: Tensor2D<2, 2> = TensorCreator::linspace(-1.0, 2.0);
+    /// assert_eq!(t.data(), &[[-1.0, 0.0], [1.0, 2.0]]);
+    /// ```
+    fn linspace(start: Self::Dtype, end: Self::Dtype) -> Self
+    where
+        Self::Dtype: Float,
+    {
+        let num_steps = Self::Array::NUM_ELEMENTS.max(2) - 1;
+        let step = (end - start) / <Self::Dtype as NumCast>::from(num_steps).unwrap();
+        let mut i = 0;
+        Self::new_boxed(Self::Device::filled(&mut |v| {
+            *v = if i == num_steps {
+                end
+            } else {
+                start + step * <Self::Dtype as NumCast>::from(i).unwrap()
+            };
+            i += 1;
+        }))
+    }
+
+    /// Creates a tensor with 1s on the diagonal of the last two axes, and 0s everywhere else.
+    /// For [Tensor3D] and [Tensor4D] this is a batch of identity matrices.
+    ///
+    /// ```rust
+    /// # use dfdx::prelude::*;
+    /// let t: Tensor2D<2, 3> = TensorCreator::eye();
+    /// assert_eq!(t.data(), &[[1.0, 0.0, 0.0], [0.0, 1.0, 0.0]]);
+    /// ```
+    fn eye() -> Self
+    where
+        Self::Dtype: One,
+        Self::Device: DeviceMatrix<Self::Array>,
+    {
+        let mut data: Box<Self::Array> = Self::Device::filled(&mut |v| *v = One::one());
+        Self::Device::triangle(data.as_mut(), 0, true);
+        Self::Device::triangle(data.as_mut(), 0, false);
+        Self::new_boxed(data)
+    }
+
     /// Creates a tensor filled with values sampled from [Standard] distribution.
     fn rand<R: rand::Rng>(rng: &mut R) -> Self
     where
@@ -46,6 +131,72 @@ pub trait TensorCreator: Sized + HasDevice {
             *v = StandardNormal.sample(rng)
         }))
     }
+
+    /// Creates a tensor filled with values sampled uniformly from `[low, high)`.
+    ///
+    /// Panics if `low >= high`.
+    fn uniform<R: rand::Rng>(rng: &mut R, low: Self::Dtype, high: Self::Dtype) -> Self
+    where
+        Self::Dtype: SampleUniform,
+    {
+        let dist = Uniform::new(low, high);
+        Self::new_boxed(Self::Device::filled(&mut |v| *v = dist.sample(rng)))
+    }
+
+    /// Creates a tensor filled with values sampled from a [Normal] distribution
+    /// with `mean` and standard deviation `std`.
+    ///
+    /// Panics if `std` is not finite or negative.
+    fn normal<R: rand::Rng>(rng: &mut R, mean: Self::Dtype, std: Self::Dtype) -> Self
+    where
+        Self::Dtype: Float,
+        StandardNormal: Distribution<Self::Dtype>,
+    {
+        let dist = Normal::new(mean, std).unwrap();
+        Self::new_boxed(Self::Device::filled(&mut |v| *v = dist.sample(rng)))
+    }
+
+    /// Creates a tensor filled with values sampled from a [Normal] distribution with `mean` and
+    /// standard deviation `std`, where values further than `2 * std` from `mean` are resampled.
+    ///
+    /// **Pytorch equivalent**: `torch.nn.init.trunc_normal_(t, mean, std, mean - 2 * std, mean + 2 * std)`
+    fn truncated_normal<R: rand::Rng>(rng: &mut R, mean: Self::Dtype, std: Self::Dtype) -> Self
+    where
+        Self::Dtype: Float,
+        StandardNormal: Distribution<Self::Dtype>,
+    {
+        let two = Self::Dtype::one() + Self::Dtype::one();
+        Self::new_boxed(Self::Device::filled(&mut |v| {
+            let z = loop {
+                let z: Self::Dtype = StandardNormal.sample(rng);
+                if z.abs() <= two {
+                    break z;
+                }
+            };
+            *v = mean + std * z
+        }))
+    }
+
+    /// Creates a tensor filled with integers sampled uniformly from `[low, high)`.
+    ///
+    /// Panics if `low >= high`.
+    ///
+    /// ```rust
+    /// # use dfdx::prelude::*;
+    /// # use rand::prelude::*;
+    /// let mut rng = StdRng::seed_from_u64(0);
+    /// let t: Tensor1D<100> = TensorCreator::randint(&mut rng, -2, 3);
+    /// assert!(t.data().iter().all(|&x| x.fract() == 0.0 && (-2.0..3.0).contains(&x)));
+    /// ```
+    fn randint<R: rand::Rng>(rng: &mut R, low: isize, high: isize) -> Self
+    where
+        Self::Dtype: Float,
+    {
+        let dist = Uniform::new(low, high);
+        Self::new_boxed(Self::Device::filled(&mut |v| {
+            *v = <Self::Dtype as NumCast>::from(dist.sample(rng)).unwrap()
+        }))
+    }
 }
 
 macro_rules! tensor_impl {
@@ -112,6 +263,18 @@ mod tests {
         assert_eq!(Tensor2D::<3, 2>::ones().data(), &[[1.0; 2]; 3]);
     }
 
+    #[test]
+    fn test_eye() {
+        assert_eq!(
+            Tensor2D::<3, 3>::eye().data(),
+            &[[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]]
+        );
+        assert_eq!(
+            Tensor3D::<2, 3, 2>::eye().data(),
+            &[[[1.0, 0.0], [0.0, 1.0], [0.0, 0.0]]; 2]
+        );
+    }
+
     #[test]
     fn test_new() {
         let t = [[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]];
@@ -131,4 +294,72 @@ mod tests {
         let mut rng = thread_rng();
         let _t = Tensor1D::<1000>::randn(&mut rng);
     }
+
+    #[test]
+    fn test_full() {
+        assert_eq!(Tensor0D::full(-1.0).data(), &-1.0);
+        assert_eq!(Tensor3D::<2, 1, 2>::full(3.0).data(), &[[[3.0; 2]]; 2]);
+    }
+
+    #[test]
+    fn test_arange() {
+        assert_eq!(Tensor0D::arange().data(), &0.0);
+        assert_eq!(
+            Tensor3D::<2, 2, 2>::arange().data(),
+            &[[[0.0, 1.0], [2.0, 3.0]], [[4.0, 5.0], [6.0, 7.0]]]
+        );
+    }
+
+    #[test]
+    fn test_linspace() {
+        assert_eq!(Tensor0D::linspace(2.0, 3.0).data(), &2.0);
+        assert_eq!(Tensor1D::<2>::linspace(2.0, 3.0).data(), &[2.0, 3.0]);
+        let t = Tensor1D::<11>::linspace(0.0, 0.3);
+        assert_eq!(t.data()[0], 0.0);
+        assert_eq!(t.data()[10], 0.3);
+        assert!((t.data()[3] - 0.09).abs() < 1e-6);
+        assert_eq!(
+            Tensor2D::<2, 3>::linspace(5.0, 0.0).data(),
+            &[[5.0, 4.0, 3.0], [2.0, 1.0, 0.0]]
+        );
+    }
+
+    #[test]
+    fn fuzz_test_uniform() {
+        let mut rng = thread_rng();
+        for &v in Tensor1D::<1000>::uniform(&mut rng, -3.0, -2.0).data() {
+            assert!((-3.0..-2.0).contains(&v));
+        }
+    }
+
+    #[test]
+    fn test_normal() {
+        let mut rng = thread_rng();
+        let t = Tensor1D::<10000>::normal(&mut rng, 3.0, 0.5);
+        let mean: Tensor0D = t.clone().mean();
+        let std: Tensor0D = t.stddev(0.0);
+        assert!((mean.data() - 3.0).abs() < 0.05);
+        assert!((std.data() - 0.5).abs() < 0.05);
+    }
+
+    #[test]
+    fn fuzz_test_truncated_normal() {
+        let mut rng = thread_rng();
+        let t = Tensor1D::<1000>::truncated_normal(&mut rng, -1.0, 0.1);
+        for &v in t.data() {
+            assert!((-1.2..=-0.8).contains(&v));
+        }
+    }
+
+    #[test]
+    fn fuzz_test_randint() {
+        let mut rng = thread_rng();
+        let t = Tensor2D::<10, 100>::randint(&mut rng, 0, 4);
+        let mut seen = [false; 4];
+        for &v in t.data().iter().flatten() {
+            assert_eq!(v.fract(), 0.0);
+            seen[v as usize] = true;
+        }
+        assert_eq!(seen, [true; 4]);
+    }
 }
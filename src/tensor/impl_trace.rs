@@ -1,5 +1,6 @@
 use super::*;
-use crate::gradients::{NoneTape, OwnedTape};
+use crate::arrays::HasArrayData;
+use crate::gradients::{ForwardTape, NoneTape, OwnedTape};
 
 /// Transforms a [NoneTape] tensor to an [OwnedTape] tensor by cloning.
 /// Clones `t` using [Tensor::duplicate()] (to preserve id), and then
@@ -18,6 +19,16 @@ pub fn traced<T: Tensor<Tape = OwnedTape>>(t: T::NoTape) -> T {
     t.put_tape(OwnedTape::default())
 }
 
+/// Transforms a [NoneTape] tensor to a [ForwardTape] tensor with `tangent` as its tangent.
+/// Clones `t` using [Tensor::duplicate()] (to preserve id).
+///
+/// The tangents of results can then be retrieved with [ForwardTape::tangent()].
+pub fn dual<T: Tensor<Tape = ForwardTape>>(t: &T::NoTape, tangent: &T::NoTape) -> T {
+    let mut tape = ForwardTape::default();
+    tape.0.mut_gradient(t).clone_from(tangent.data());
+    t.duplicate().put_tape(tape)
+}
+
 macro_rules! tensor_impl {
     ($typename:ident, [$($Vs:tt),*]) => {
 impl<$(const $Vs: usize, )*> $typename<$($Vs, )* NoneTape> {
@@ -32,6 +43,19 @@ impl<$(const $Vs: usize, )*> $typename<$($Vs, )* NoneTape> {
     pub fn traced(self) -> $typename<$($Vs, )* OwnedTape> {
         traced(self)
     }
+
+    /// Clones `self` and returns a copy with [ForwardTape] as the [crate::gradients::Tape],
+    /// which carries `tangent` as the tangent of `self`.
+    pub fn dual(&self, tangent: &Self) -> $typename<$($Vs, )* ForwardTape> {
+        dual(self, tangent)
+    }
+}
+
+impl<$(const $Vs: usize, )*> $typename<$($Vs, )* ForwardTape> {
+    /// Returns the tangent of `self`. See [ForwardTape::tangent()].
+    pub fn tangent(&self) -> $typename<$($Vs, )* NoneTape> {
+        self.tape.tangent(self)
+    }
 }
     };
 }
@@ -26,20 +26,31 @@
 //! let t: Tensor1D<3> = TensorCreator::new([1.0, 2.0, 3.0]);
 //! ```
 //!
-//! 2. Filled with 0s or 1s use [TensorCreator::zeros()] and [TensorCreator::ones()].
+//! 2. Filled with 0s, 1s, or any value use [TensorCreator::zeros()], [TensorCreator::ones()], and [TensorCreator::full()].
 //! ```rust
 //! # use dfdx::prelude::*;
 //! let t: Tensor1D<5> = TensorCreator::zeros();
 //! let q: Tensor2D<3, 2> = TensorCreator::ones();
+//! let r: Tensor2D<3, 2> = TensorCreator::full(0.5);
 //! ```
 //!
-//! 3. Filled with random data use [TensorCreator::rand()] and [TensorCreator::randn()].
+//! 3. Filled with ranges of values use [TensorCreator::arange()] and [TensorCreator::linspace()].
+//! ```rust
+//! # use dfdx::prelude::*;
+//! let a: Tensor2D<2, 3> = TensorCreator::arange(); // [[0, 1, 2], [3, 4, 5]]
+//! let b: Tensor1D<5> = TensorCreator::linspace(0.0, 1.0); // [0, 0.25, 0.5, 0.75, 1]
+//! ```
+//!
+//! 4. Filled with random data use [TensorCreator::rand()], [TensorCreator::randn()], [TensorCreator::uniform()],
+//!    [TensorCreator::normal()], [TensorCreator::truncated_normal()], and [TensorCreator::randint()].
 //! ```rust
 //! # use dfdx::prelude::*;
 //! # use rand::prelude::*;
 //! let mut rng = StdRng::seed_from_u64(0);
 //! let a = Tensor1D::<3>::rand(&mut rng); // uniform random data
 //! let b = Tensor2D::<4, 3>::randn(&mut rng); // gaussian random data
+//! let c = Tensor2D::<4, 3>::uniform(&mut rng, -1.0, 1.0);
+//! let d = Tensor2D::<4, 3>::normal(&mut rng, 0.0, 0.02);
 //! ```
 //!
 //! # Accessing or modifying underlying data
@@ -55,6 +66,33 @@
 //! assert_eq!(t.data(), &[0.0, 2.0, 0.0]);
 //! ```
 //!
+//! # Printing tensors
+//!
+//! All tensors implement [std::fmt::Display], which prints the type of the tensor,
+//! followed by its data in numpy style nested brackets. The number of digits after the
+//! decimal point can be changed with the usual format options (e.g. `{:.2}`), and defaults to 4.
+//!
+//! ```rust
+//! # use dfdx::prelude::*;
+//! let t = tensor([[1.0, 2.0, 3.0], [4.0, -5.0, 6.5]]);
+//! assert_eq!(
+//!     format!("{:.1}", t.trace()),
+//!     "Tensor2D<2, 3, OwnedTape>\n[[ 1.0,  2.0,  3.0],\n [ 4.0, -5.0,  6.5]]"
+//! );
+//! ```
+//!
+//! Tensors with more than 1000 elements are summarized by only printing the first and last
+//! 3 elements of each long axis. Use the alternate flag (`{:#}`) to print everything.
+//!
+//! ```rust
+//! # use dfdx::prelude::*;
+//! let t: Tensor1D<2000> = TensorCreator::arange();
+//! assert_eq!(
+//!     format!("{:.0}", t),
+//!     "Tensor1D<2000, NoneTape>\n[   0,    1,    2, ..., 1997, 1998, 1999]"
+//! );
+//! ```
+//!
 //! # Tracking gradients
 //!
 //! Use the [trace()] or [traced()] methods to add [crate::gradients::OwnedTape] to the [Tensor].
@@ -77,6 +115,7 @@
 //! 2. [Tensor::duplicate()] is implemented for all tensors, it copies the [crate::unique_id::UniqueId], and returns a tensor with no tape.
 
 mod impl_default;
+mod impl_display;
 mod impl_has_array;
 mod impl_has_device;
 mod impl_has_unique_id;
@@ -88,6 +127,7 @@ mod impl_tensor_creator;
 mod impl_trace;
 mod impl_update_with_grads;
 mod into_tensor;
+mod sparse;
 mod structs;
 
 pub use impl_default::*;
@@ -102,4 +142,5 @@ pub use impl_tensor_creator::*;
 pub use impl_trace::*;
 pub use impl_update_with_grads::*;
 pub use into_tensor::*;
+pub use sparse::*;
 pub use structs::*;
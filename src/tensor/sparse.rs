@@ -0,0 +1,88 @@
+use super::*;
+use crate::gradients::{NoneTape, OwnedTape, Tape};
+use std::rc::Rc;
+
+/// A sparse 2d tensor with shape (M, N) in coordinate (COO) format. Only `Z` entries are
+/// stored, each as a `(row, col)` index and a value. Entries that aren't stored are zero,
+/// and duplicate indices are summed.
+///
+/// The values are a [Tensor1D] with tape `Tape`, so gradients can flow into them, while the
+/// indices are constant.
+///
+/// See [crate::tensor_ops::sparse_matmul()], [crate::tensor_ops::to_sparse()] and
+/// [crate::tensor_ops::to_dense()] for operations on sparse tensors.
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let adj: CooTensor2D<3, 3, 2> = CooTensor2D::new([(0, 1), (2, 0)], tensor([1.0, 0.5]));
+/// assert_eq!(adj.to_dense().data(), &[[0.0, 1.0, 0.0], [0.0; 3], [0.5, 0.0, 0.0]]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct CooTensor2D<const M: usize, const N: usize, const Z: usize, Tape = NoneTape> {
+    pub(crate) indices: Rc<[(usize, usize); Z]>,
+    pub(crate) values: Tensor1D<Z, Tape>,
+}
+
+impl<const M: usize, const N: usize, const Z: usize, H: Tape> CooTensor2D<M, N, Z, H> {
+    /// Creates a sparse tensor where `values[i]` is stored at `indices[i]`.
+    ///
+    /// **Panics** if any of the indices are out of bounds.
+    pub fn new(indices: [(usize, usize); Z], values: Tensor1D<Z, H>) -> Self {
+        for &(i, j) in indices.iter() {
+            assert!(
+                i < M && j < N,
+                "Index ({}, {}) is out of bounds for a {}x{} sparse tensor",
+                i,
+                j,
+                M,
+                N
+            );
+        }
+        Self {
+            indices: Rc::new(indices),
+            values,
+        }
+    }
+
+    /// The `(row, col)` index of each stored value.
+    pub fn indices(&self) -> &[(usize, usize); Z] {
+        self.indices.as_ref()
+    }
+
+    /// The stored values. Gradients of the values can be accessed with this.
+    pub fn values(&self) -> &Tensor1D<Z, H> {
+        &self.values
+    }
+
+    /// Removes the tape from the values.
+    pub fn split_tape(self) -> (CooTensor2D<M, N, Z, NoneTape>, H) {
+        let (values, tape) = self.values.split_tape();
+        let indices = self.indices;
+        (CooTensor2D { indices, values }, tape)
+    }
+
+    /// Replaces the tape of the values with `tape`.
+    pub fn put_tape<HOut: Tape>(self, tape: HOut) -> CooTensor2D<M, N, Z, HOut> {
+        let (values, _) = self.values.split_tape();
+        CooTensor2D {
+            indices: self.indices,
+            values: values.put_tape(tape),
+        }
+    }
+}
+
+impl<const M: usize, const N: usize, const Z: usize> CooTensor2D<M, N, Z, NoneTape> {
+    /// Clones `self` and returns a copy with [OwnedTape] as the tape of the values.
+    pub fn trace(&self) -> CooTensor2D<M, N, Z, OwnedTape> {
+        CooTensor2D {
+            indices: self.indices.clone(),
+            values: self.values.trace(),
+        }
+    }
+
+    /// Takes ownership of `self` and inserts [OwnedTape] as the tape of the values.
+    pub fn traced(self) -> CooTensor2D<M, N, Z, OwnedTape> {
+        self.put_tape(OwnedTape::default())
+    }
+}
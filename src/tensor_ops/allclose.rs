@@ -0,0 +1,283 @@
+use crate::arrays::{HasArrayData, HasShape};
+use crate::devices::as_slice;
+use crate::prelude::*;
+
+/// The default relative tolerance of [assert_tensor_close!].
+pub const DEFAULT_RTOL: f32 = 1e-5;
+
+/// The default absolute tolerance of [assert_tensor_close!].
+pub const DEFAULT_ATOL: f32 = 1e-6;
+
+/// Something with `f32` data that can be compared with [allclose()], [max_abs_diff()]
+/// and [assert_tensor_close!]. Implemented for tensors, nd arrays of `f32`
+/// (e.g. the result of [HasArrayData::data()] or [crate::gradients::Gradients::ref_gradient()]),
+/// and references to them.
+pub trait AllClose {
+    /// The size of each axis.
+    fn shape(&self) -> &'static [usize];
+
+    /// All the values in row major order.
+    fn flat_data(&self) -> &[f32];
+}
+
+macro_rules! array_impl {
+    ($ArrTy:ty, {$($Dims:tt),*}) => {
+impl<$(const $Dims: usize, )*> AllClose for $ArrTy {
+    fn shape(&self) -> &'static [usize] {
+        <$ArrTy as HasShape>::SHAPE
+    }
+    fn flat_data(&self) -> &[f32] {
+        as_slice(self)
+    }
+}
+    };
+}
+
+array_impl!(f32, {});
+array_impl!([f32; M], { M });
+array_impl!([[f32; N]; M], {M, N});
+array_impl!([[[f32; O]; N]; M], {M, N, O});
+array_impl!([[[[f32; P]; O]; N]; M], {M, N, O, P});
+
+impl<T: AllClose + ?Sized> AllClose for &T {
+    fn shape(&self) -> &'static [usize] {
+        (**self).shape()
+    }
+    fn flat_data(&self) -> &[f32] {
+        (**self).flat_data()
+    }
+}
+
+macro_rules! tensor_impl {
+    ($typename:ident, [$($Vs:tt),*]) => {
+impl<$(const $Vs: usize, )* H> AllClose for $typename<$($Vs, )* H> {
+    fn shape(&self) -> &'static [usize] {
+        self.data().shape()
+    }
+    fn flat_data(&self) -> &[f32] {
+        self.data().flat_data()
+    }
+}
+    };
+}
+
+tensor_impl!(Tensor0D, []);
+tensor_impl!(Tensor1D, [M]);
+tensor_impl!(Tensor2D, [M, N]);
+tensor_impl!(Tensor3D, [M, N, O]);
+tensor_impl!(Tensor4D, [M, N, O, P]);
+
+/// Returns true if every element of `a` is close to the corresponding element of `b`:
+/// `|a - b| <= atol + rtol * |b|`. `NaN`s are never close to anything.
+///
+/// `a` and `b` can be any mix of tensors (with any tape) and arrays, but must have the same shape.
+///
+/// **Pytorch equivalent**: `torch.allclose(a, b, rtol, atol)`
+///
+/// **Related functions**: [max_abs_diff()], [assert_tensor_close!]
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let a = tensor([1.0, 2.0, 3.0]);
+/// let b = tensor([1.0, 2.0, 3.001]);
+/// assert!(allclose(&a, &b, 1e-3, 0.0));
+/// assert!(!allclose(a.data(), b.data(), 1e-4, 0.0));
+/// ```
+#[track_caller]
+pub fn allclose<A, B>(a: &A, b: &B, rtol: f32, atol: f32) -> bool
+where
+    A: AllClose + ?Sized,
+    B: AllClose + ?Sized,
+{
+    assert_same_shape(a, b);
+    a.flat_data()
+        .iter()
+        .zip(b.flat_data().iter())
+        .all(|(a, b)| is_close(*a, *b, rtol, atol))
+}
+
+/// The largest absolute difference between elements of `a` and `b`. Returns `NaN`
+/// if any of the differences are `NaN`.
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let a = tensor([[1.0, 2.0], [3.0, 4.0]]);
+/// let b = tensor([[1.0, 2.5], [3.0, 3.0]]);
+/// assert_eq!(max_abs_diff(&a, &b), 1.0);
+/// ```
+#[track_caller]
+pub fn max_abs_diff<A, B>(a: &A, b: &B) -> f32
+where
+    A: AllClose + ?Sized,
+    B: AllClose + ?Sized,
+{
+    assert_same_shape(a, b);
+    a.flat_data()
+        .iter()
+        .zip(b.flat_data().iter())
+        .map(|(a, b)| (a - b).abs())
+        .fold(0.0, |acc, d| if d.is_nan() || d > acc { d } else { acc })
+}
+
+#[track_caller]
+fn assert_same_shape<A: AllClose + ?Sized, B: AllClose + ?Sized>(a: &A, b: &B) {
+    assert_eq!(
+        a.shape(),
+        b.shape(),
+        "Can't compare tensors of different shapes"
+    );
+}
+
+fn is_close(a: f32, b: f32, rtol: f32, atol: f32) -> bool {
+    a == b || (a - b).abs() <= atol + rtol * b.abs()
+}
+
+/// Asserts that two tensors (or nd arrays) are close with [allclose()]. Uses
+/// [DEFAULT_RTOL] and [DEFAULT_ATOL] unless `rtol` and `atol` are given.
+///
+/// On failure, the panic message lists the number of mismatched elements, the
+/// [max_abs_diff()], and the indices & values of the first few mismatched elements.
+///
+/// Examples:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let t = tensor([[1.0, 2.0], [3.0, 4.0]]);
+/// let r = t.trace().square();
+/// assert_tensor_close!(r, tensor([[1.0, 4.0], [9.0, 16.0]]));
+///
+/// let g = r.sum::<_, AllAxes>().backward();
+/// assert_tensor_close!(g.ref_gradient(&t), &[[2.0, 4.0], [6.0, 8.0]], rtol = 0.0, atol = 1e-6);
+/// ```
+///
+/// ```should_panic
+/// # use dfdx::prelude::*;
+/// // panics with:
+/// // assertion `left ≈ right` failed (rtol=0.00001, atol=0.000001)
+/// //   1 of 2 elements differ, max abs diff = 0.5
+/// //   at [1]: left = 2, right = 2.5
+/// assert_tensor_close!(tensor([1.0, 2.0]), tensor([1.0, 2.5]));
+/// ```
+#[macro_export]
+macro_rules! assert_tensor_close {
+    ($lhs:expr, $rhs:expr $(,)?) => {
+        $crate::tensor_ops::assert_allclose(
+            &$lhs,
+            &$rhs,
+            $crate::tensor_ops::DEFAULT_RTOL,
+            $crate::tensor_ops::DEFAULT_ATOL,
+        )
+    };
+    ($lhs:expr, $rhs:expr, rtol = $rtol:expr, atol = $atol:expr $(,)?) => {
+        $crate::tensor_ops::assert_allclose(&$lhs, &$rhs, $rtol, $atol)
+    };
+}
+
+pub use crate::assert_tensor_close;
+
+/// The number of mismatched elements that [assert_allclose()] prints.
+const MAX_MISMATCHES_SHOWN: usize = 5;
+
+/// Implementation of [assert_tensor_close!].
+#[doc(hidden)]
+#[track_caller]
+pub fn assert_allclose<A, B>(a: &A, b: &B, rtol: f32, atol: f32)
+where
+    A: AllClose + ?Sized,
+    B: AllClose + ?Sized,
+{
+    if allclose(a, b, rtol, atol) {
+        return;
+    }
+
+    let a_data = a.flat_data();
+    let b_data = b.flat_data();
+    let mismatches: Vec<usize> = (0..a_data.len())
+        .filter(|&i| !is_close(a_data[i], b_data[i], rtol, atol))
+        .collect();
+
+    let mut msg = format!(
+        "assertion `left ≈ right` failed (rtol={}, atol={})\n  {} of {} elements differ, max abs diff = {}",
+        rtol,
+        atol,
+        mismatches.len(),
+        a_data.len(),
+        max_abs_diff(a, b),
+    );
+    for &i in mismatches.iter().take(MAX_MISMATCHES_SHOWN) {
+        msg += &format!(
+            "\n  at {:?}: left = {}, right = {}",
+            unravel_index(i, a.shape()),
+            a_data[i],
+            b_data[i]
+        );
+    }
+    if mismatches.len() > MAX_MISMATCHES_SHOWN {
+        msg += "\n  ...";
+    }
+    panic!("{}", msg);
+}
+
+/// Converts the flat index `i` into an index for each axis of `shape`.
+fn unravel_index(mut i: usize, shape: &[usize]) -> Vec<usize> {
+    let mut index = vec![0; shape.len()];
+    for (idx, &dim) in index.iter_mut().zip(shape.iter()).rev() {
+        *idx = i % dim;
+        i /= dim;
+    }
+    index
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_allclose() {
+        assert!(allclose(&1.0, &1.0, 0.0, 0.0));
+        assert!(allclose(&[1.0, 100.0], &[1.1, 110.0], 0.1, 0.0));
+        assert!(!allclose(&[1.0, 100.0], &[1.2, 110.0], 0.1, 0.0));
+        assert!(allclose(&[0.0, 1e-7], &[1e-7, 0.0], 0.0, 1e-7));
+        assert!(!allclose(&f32::NAN, &f32::NAN, 1.0, 1.0));
+        assert!(allclose(&f32::INFINITY, &f32::INFINITY, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_max_abs_diff() {
+        let a: Tensor3D<2, 2, 2> = TensorCreator::arange();
+        let b = a.clone() * 2.0;
+        assert_eq!(max_abs_diff(&a, &b), 7.0);
+        assert!(max_abs_diff(&[1.0, f32::NAN, 2.0], &[1.0, 0.0, 0.0]).is_nan());
+    }
+
+    #[test]
+    fn test_assert_tensor_close() {
+        let t = tensor([[1.0, 2.0], [3.0, 4.0]]);
+        assert_tensor_close!(t.trace(), t);
+        assert_tensor_close!(t.data(), &[[1.0, 2.0], [3.0, 4.0 + 1e-6]]);
+        assert_tensor_close!(*t.data(), [[1.1, 2.2], [3.3, 4.4]], rtol = 0.1, atol = 0.0);
+    }
+
+    #[test]
+    #[should_panic = "assertion `left ≈ right` failed (rtol=0.00001, atol=0.000001)
+  7 of 8 elements differ, max abs diff = 7
+  at [0, 0, 1]: left = 1, right = 2
+  at [0, 1, 0]: left = 2, right = 4
+  at [0, 1, 1]: left = 3, right = 6
+  at [1, 0, 0]: left = 4, right = 8
+  at [1, 0, 1]: left = 5, right = 10
+  ..."]
+    fn test_assert_tensor_close_message() {
+        let a: Tensor3D<2, 2, 2> = TensorCreator::arange();
+        let b = a.clone() * 2.0;
+        assert_tensor_close!(a, b);
+    }
+
+    #[test]
+    fn test_unravel_index() {
+        assert_eq!(unravel_index(0, &[]), Vec::<usize>::new());
+        assert_eq!(unravel_index(5, &[2, 3]), vec![1, 2]);
+        assert_eq!(unravel_index(13, &[2, 3, 4]), vec![1, 0, 1]);
+    }
+}
@@ -1,5 +1,5 @@
-use super::utils::move_tape_and_add_backward_op;
-use crate::gradients::Tape;
+use super::utils::move_tape_and_add_backward_op_with_graph;
+use crate::gradients::{GradientGraph, Tape};
 use crate::{
     devices::{Device, ForEachElement},
     prelude::*,
@@ -17,12 +17,17 @@ use std::ops::{Add, Div, Mul, Sub};
 /// ```
 pub fn add_scalar<T: Tensor<Dtype = f32>>(t: T, val: T::Dtype) -> T {
     let result = T::NoTape::new_boxed(T::Device::map(t.data(), |x| x + val));
-    move_tape_and_add_backward_op(t, result, move |t, result, grads| {
-        let (t_grad, result_grad) = grads.mut_and_ref(&t, &result);
-        T::Device::foreach_mr(t_grad, result_grad, &mut |t, r| {
-            *t += r;
-        });
-    })
+    move_tape_and_add_backward_op_with_graph(
+        t,
+        result,
+        move |t, result, grads| {
+            let (t_grad, result_grad) = grads.mut_and_ref_or_zeros(&t, &result);
+            T::Device::foreach_mr(t_grad, result_grad, &mut |t, r| {
+                *t += r;
+            });
+        },
+        move |t, result, graph| scalar_graph_op(t, result, graph, |g| *g),
+    )
 }
 
 /// `t - val`. `val` is used for all elements of `t`.
@@ -36,12 +41,17 @@ pub fn add_scalar<T: Tensor<Dtype = f32>>(t: T, val: T::Dtype) -> T {
 /// ```
 pub fn sub_scalar<T: Tensor<Dtype = f32>>(t: T, val: T::Dtype) -> T {
     let result = T::NoTape::new_boxed(T::Device::map(t.data(), |x| x - val));
-    move_tape_and_add_backward_op(t, result, move |t, result, grads| {
-        let (t_grad, result_grad) = grads.mut_and_ref(&t, &result);
-        T::Device::foreach_mr(t_grad, result_grad, &mut |t, r| {
-            *t += r;
-        });
-    })
+    move_tape_and_add_backward_op_with_graph(
+        t,
+        result,
+        move |t, result, grads| {
+            let (t_grad, result_grad) = grads.mut_and_ref_or_zeros(&t, &result);
+            T::Device::foreach_mr(t_grad, result_grad, &mut |t, r| {
+                *t += r;
+            });
+        },
+        move |t, result, graph| scalar_graph_op(t, result, graph, |g| *g),
+    )
 }
 
 /// `t * val`. `val` is used for all elements of `t`.
@@ -55,12 +65,17 @@ pub fn sub_scalar<T: Tensor<Dtype = f32>>(t: T, val: T::Dtype) -> T {
 /// ```
 pub fn mul_scalar<T: Tensor<Dtype = f32>>(t: T, val: T::Dtype) -> T {
     let result = T::NoTape::new_boxed(T::Device::map(t.data(), |x| x * val));
-    move_tape_and_add_backward_op(t, result, move |t, result, grads| {
-        let (t_grad, result_grad) = grads.mut_and_ref(&t, &result);
-        T::Device::foreach_mr(t_grad, result_grad, &mut |t, r| {
-            *t += r * val;
-        });
-    })
+    move_tape_and_add_backward_op_with_graph(
+        t,
+        result,
+        move |t, result, grads| {
+            let (t_grad, result_grad) = grads.mut_and_ref_or_zeros(&t, &result);
+            T::Device::foreach_mr(t_grad, result_grad, &mut |t, r| {
+                *t += r * val;
+            });
+        },
+        move |t, result, graph| scalar_graph_op(t, result, graph, move |g| g * val),
+    )
 }
 
 /// `t / val`. `val` is used for all elements of `t`.
@@ -74,12 +89,36 @@ pub fn mul_scalar<T: Tensor<Dtype = f32>>(t: T, val: T::Dtype) -> T {
 /// ```
 pub fn div_scalar<T: Tensor<Dtype = f32>>(t: T, val: T::Dtype) -> T {
     let result = T::NoTape::new_boxed(T::Device::map(t.data(), |x| x / val));
-    move_tape_and_add_backward_op(t, result, move |t, result, grads| {
-        let (t_grad, result_grad) = grads.mut_and_ref(&t, &result);
-        T::Device::foreach_mr(t_grad, result_grad, &mut |t, r| {
-            *t += r / val;
-        });
-    })
+    move_tape_and_add_backward_op_with_graph(
+        t,
+        result,
+        move |t, result, grads| {
+            let (t_grad, result_grad) = grads.mut_and_ref_or_zeros(&t, &result);
+            T::Device::foreach_mr(t_grad, result_grad, &mut |t, r| {
+                *t += r / val;
+            });
+        },
+        move |t, result, graph| scalar_graph_op(t, result, graph, move |g| g / val),
+    )
+}
+
+/// The [GradientGraph] version of the backward op of the scalar operations, which are all
+/// linear. `df` maps the gradient of the result to the gradient of the input.
+fn scalar_graph_op<T, F>(t: T, result: PhantomTensor<T>, graph: &mut GradientGraph, mut df: F)
+where
+    T: 'static + Tensor<Dtype = f32>,
+    F: 'static + FnMut(&f32) -> f32,
+{
+    let t_slot = graph.slot(&t);
+    let result_slot = graph.slot(&result);
+    let (t_grad, result_grad) = graph
+        .gradients()
+        .mut_and_ref_or_zeros(&t_slot, &result_slot);
+    T::Device::foreach_mr(t_grad, result_grad, &mut |t, r| *t += df(r));
+    graph.add_backward_op(move |grads| {
+        let (result_grad, t_grad) = grads.mut_and_ref_or_zeros(&result_slot, &t_slot);
+        T::Device::foreach_mr(result_grad, t_grad, &mut |r, t| *r += df(t));
+    });
 }
 
 macro_rules! scalar_ops_impl {
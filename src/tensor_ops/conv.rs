@@ -21,6 +21,8 @@ impl<const C: usize, const H: usize, const W: usize, T: Tape> Tensor3D<C, H, W,
 
         let f = filters.clone();
         let (x, mut tape) = self.split_tape();
+        tape.add_input(filters);
+        tape.add_input(bias);
         let phf = filters.phantom();
         let phb = bias.phantom();
         let phr = result.phantom();
@@ -51,6 +53,8 @@ impl<const B: usize, const C: usize, const H: usize, const W: usize, T: Tape>
         let f = filters.clone();
 
         let (x, mut tape) = self.split_tape();
+        tape.add_input(filters);
+        tape.add_input(bias);
         let phf = filters.phantom();
         let phb = bias.phantom();
         let phr = result.phantom();
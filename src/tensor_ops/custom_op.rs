@@ -0,0 +1,308 @@
+use crate::arrays::HasArrayType;
+use crate::devices::{AllocateZeros, Device};
+use crate::gradients::{NoneTape, Tape};
+use crate::prelude::*;
+
+/// A differentiable operation with a single input, defined outside of dfdx.
+///
+/// Implementors only work with arrays: [CustomOp::forward()] fills in the output array,
+/// and [CustomOp::backward()] accumulates the gradient of the input. Moving the tape,
+/// allocating the output tensor with a new id, and looking up gradients are all done by
+/// [custom_op()].
+///
+/// `Inp` and [CustomOp::Output] are the [NoneTape] versions of the tensor types; the tape
+/// of the input is moved onto the output.
+///
+/// Example of `x^3`:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// struct Cube;
+/// impl<const N: usize> CustomOp<Tensor1D<N>> for Cube {
+///     type Output = Tensor1D<N>;
+///     fn forward(&mut self, inp: &[f32; N], out: &mut [f32; N]) {
+///         for (o, x) in out.iter_mut().zip(inp.iter()) {
+///             *o = x * x * x;
+///         }
+///     }
+///     fn backward(&mut self, inp: &[f32; N], _: &[f32; N], out_grad: &[f32; N], inp_grad: &mut [f32; N]) {
+///         for i in 0..N {
+///             inp_grad[i] += 3.0 * inp[i] * inp[i] * out_grad[i];
+///         }
+///     }
+/// }
+///
+/// let x = tensor([1.0, 2.0, -3.0]);
+/// let y = custom_op(x.trace(), Cube);
+/// assert_eq!(y.data(), &[1.0, 8.0, -27.0]);
+/// let gradients = y.sum().backward();
+/// assert_eq!(gradients.ref_gradient(&x), &[3.0, 12.0, 27.0]);
+/// ```
+pub trait CustomOp<Inp: HasArrayType<Dtype = f32>>: 'static {
+    /// The output tensor type, without a tape.
+    type Output: Tensor<Dtype = f32, Tape = NoneTape> + TensorCreator;
+
+    /// Computes the output from `inp`. `out` starts out as all zeros.
+    fn forward(&mut self, inp: &Inp::Array, out: &mut <Self::Output as HasArrayType>::Array);
+
+    /// **Adds** the gradient of the input to `inp_grad`, given the input, the output
+    /// computed by [CustomOp::forward()], and the gradient of the output.
+    fn backward(
+        &mut self,
+        inp: &Inp::Array,
+        out: &<Self::Output as HasArrayType>::Array,
+        out_grad: &<Self::Output as HasArrayType>::Array,
+        inp_grad: &mut Inp::Array,
+    );
+}
+
+/// A differentiable operation with two inputs, defined outside of dfdx. See [CustomOp]
+/// for the single input version.
+///
+/// Like other binary operations, the tape is taken from `lhs`, and `rhs` must not have a tape.
+///
+/// Example of `x * y^2`:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// struct MulSquare;
+/// impl<const N: usize> CustomBinaryOp<Tensor1D<N>, Tensor1D<N>> for MulSquare {
+///     type Output = Tensor1D<N>;
+///     fn forward(&mut self, x: &[f32; N], y: &[f32; N], out: &mut [f32; N]) {
+///         for i in 0..N {
+///             out[i] = x[i] * y[i] * y[i];
+///         }
+///     }
+///     fn backward(
+///         &mut self,
+///         x: &[f32; N],
+///         y: &[f32; N],
+///         _: &[f32; N],
+///         out_grad: &[f32; N],
+///         x_grad: &mut [f32; N],
+///         y_grad: &mut [f32; N],
+///     ) {
+///         for i in 0..N {
+///             x_grad[i] += y[i] * y[i] * out_grad[i];
+///             y_grad[i] += 2.0 * x[i] * y[i] * out_grad[i];
+///         }
+///     }
+/// }
+///
+/// let x = tensor([1.0, 2.0]);
+/// let y = tensor([3.0, -1.0]);
+/// let r = custom_binary_op(x.trace(), &y, MulSquare);
+/// assert_eq!(r.data(), &[9.0, 2.0]);
+/// let gradients = r.sum().backward();
+/// assert_eq!(gradients.ref_gradient(&x), &[9.0, 1.0]);
+/// assert_eq!(gradients.ref_gradient(&y), &[6.0, -4.0]);
+/// ```
+pub trait CustomBinaryOp<Lhs: HasArrayType<Dtype = f32>, Rhs: HasArrayType<Dtype = f32>>:
+    'static
+{
+    /// The output tensor type, without a tape.
+    type Output: Tensor<Dtype = f32, Tape = NoneTape> + TensorCreator;
+
+    /// Computes the output from `lhs` and `rhs`. `out` starts out as all zeros.
+    fn forward(
+        &mut self,
+        lhs: &Lhs::Array,
+        rhs: &Rhs::Array,
+        out: &mut <Self::Output as HasArrayType>::Array,
+    );
+
+    /// **Adds** the gradients of both inputs to `lhs_grad` and `rhs_grad`. Both start out
+    /// as zeros, so it is fine to assign to them instead.
+    #[allow(clippy::too_many_arguments)]
+    fn backward(
+        &mut self,
+        lhs: &Lhs::Array,
+        rhs: &Rhs::Array,
+        out: &<Self::Output as HasArrayType>::Array,
+        out_grad: &<Self::Output as HasArrayType>::Array,
+        lhs_grad: &mut Lhs::Array,
+        rhs_grad: &mut Rhs::Array,
+    );
+}
+
+/// Applies a [CustomOp] to `t`, moving the tape of `t` onto the result.
+pub fn custom_op<T, Op>(t: T, mut op: Op) -> <Op::Output as PutTape<T::Tape>>::Output
+where
+    T: Tensor<Dtype = f32>,
+    Op: CustomOp<T::NoTape>,
+    Op::Output: PutTape<T::Tape>,
+{
+    let mut out = Op::Output::zeros();
+    op.forward(t.data(), out.mut_data());
+    let (t, mut tape) = t.split_tape();
+    let out_data = out.duplicate();
+    tape.add_backward_op(move |grads| {
+        let (t_grad, out_grad) = grads.mut_and_ref(&t, &out_data);
+        op.backward(t.data(), out_data.data(), out_grad, t_grad);
+    });
+    out.put_tape(tape)
+}
+
+/// Applies a [CustomBinaryOp] to `lhs` and `rhs`, moving the tape of `lhs` onto the result.
+pub fn custom_binary_op<L, R, Op>(
+    lhs: L,
+    rhs: &R,
+    mut op: Op,
+) -> <Op::Output as PutTape<L::Tape>>::Output
+where
+    L: Tensor<Dtype = f32>,
+    R: Tensor<Dtype = f32, Tape = NoneTape>,
+    Op: CustomBinaryOp<L::NoTape, R>,
+    Op::Output: PutTape<L::Tape>,
+{
+    let mut out = Op::Output::zeros();
+    op.forward(lhs.data(), rhs.data(), out.mut_data());
+    let (lhs, mut tape) = lhs.split_tape();
+    tape.add_input(rhs);
+    let rhs = rhs.duplicate();
+    let out_data = out.duplicate();
+    tape.add_backward_op(move |grads| {
+        // NOTE: lhs & rhs may be the same tensor, so the gradients are computed into
+        // separate buffers first.
+        let mut lhs_grad: Box<L::Array> = L::Device::zeros();
+        let mut rhs_grad: Box<R::Array> = R::Device::zeros();
+        op.backward(
+            lhs.data(),
+            rhs.data(),
+            out_data.data(),
+            grads.ref_gradient(&out_data),
+            lhs_grad.as_mut(),
+            rhs_grad.as_mut(),
+        );
+        L::Device::add(grads.mut_gradient(&lhs), lhs_grad.as_ref());
+        R::Device::add(grads.mut_gradient(&rhs), rhs_grad.as_ref());
+    });
+    out.put_tape(tape)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assert_tensor_close;
+
+    struct Cube;
+    impl<const M: usize, const N: usize> CustomOp<Tensor2D<M, N>> for Cube {
+        type Output = Tensor2D<M, N>;
+        fn forward(&mut self, inp: &[[f32; N]; M], out: &mut [[f32; N]; M]) {
+            for m in 0..M {
+                for n in 0..N {
+                    out[m][n] = inp[m][n].powi(3);
+                }
+            }
+        }
+        fn backward(
+            &mut self,
+            inp: &[[f32; N]; M],
+            _: &[[f32; N]; M],
+            out_grad: &[[f32; N]; M],
+            inp_grad: &mut [[f32; N]; M],
+        ) {
+            for m in 0..M {
+                for n in 0..N {
+                    inp_grad[m][n] += 3.0 * inp[m][n].powi(2) * out_grad[m][n];
+                }
+            }
+        }
+    }
+
+    /// Sums the rows of a matrix and adds a vector, to check ops that change shapes.
+    struct RowSumPlus;
+    impl<const M: usize, const N: usize> CustomBinaryOp<Tensor2D<M, N>, Tensor1D<M>> for RowSumPlus {
+        type Output = Tensor1D<M>;
+        fn forward(&mut self, lhs: &[[f32; N]; M], rhs: &[f32; M], out: &mut [f32; M]) {
+            for m in 0..M {
+                out[m] = lhs[m].iter().sum::<f32>() + rhs[m];
+            }
+        }
+        fn backward(
+            &mut self,
+            _: &[[f32; N]; M],
+            _: &[f32; M],
+            _: &[f32; M],
+            out_grad: &[f32; M],
+            lhs_grad: &mut [[f32; N]; M],
+            rhs_grad: &mut [f32; M],
+        ) {
+            for m in 0..M {
+                lhs_grad[m].iter_mut().for_each(|g| *g += out_grad[m]);
+                rhs_grad[m] += out_grad[m];
+            }
+        }
+    }
+
+    struct Mul;
+    impl<const N: usize> CustomBinaryOp<Tensor1D<N>, Tensor1D<N>> for Mul {
+        type Output = Tensor1D<N>;
+        fn forward(&mut self, lhs: &[f32; N], rhs: &[f32; N], out: &mut [f32; N]) {
+            for i in 0..N {
+                out[i] = lhs[i] * rhs[i];
+            }
+        }
+        fn backward(
+            &mut self,
+            lhs: &[f32; N],
+            rhs: &[f32; N],
+            _: &[f32; N],
+            out_grad: &[f32; N],
+            lhs_grad: &mut [f32; N],
+            rhs_grad: &mut [f32; N],
+        ) {
+            for i in 0..N {
+                lhs_grad[i] += rhs[i] * out_grad[i];
+                rhs_grad[i] += lhs[i] * out_grad[i];
+            }
+        }
+    }
+
+    #[test]
+    fn test_custom_op_matches_builtin() {
+        let x = tensor([[0.5, -1.0, 2.0], [1.5, 0.0, -0.25]]);
+        let a = custom_op(x.trace(), Cube);
+        let b = x.trace().powi(3);
+        assert_tensor_close!(a, b);
+        let ga = a.mean::<_, AllAxes>().backward();
+        let gb = b.mean::<_, AllAxes>().backward();
+        assert_tensor_close!(ga.ref_gradient(&x), gb.ref_gradient(&x));
+    }
+
+    #[test]
+    fn test_custom_op_gradcheck() {
+        let x = tensor([[0.5, -1.0, 2.0], [1.5, 0.3, -0.25]]);
+        gradcheck(
+            |x| custom_op(x, Cube).sum::<_, AllAxes>(),
+            &x,
+            Default::default(),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_custom_op_without_tape() {
+        let x: Tensor2D<1, 2> = tensor([[2.0, -1.0]]);
+        let r: Tensor2D<1, 2> = custom_op(x, Cube);
+        assert_eq!(r.data(), &[[8.0, -1.0]]);
+    }
+
+    #[test]
+    fn test_custom_binary_op_different_shapes() {
+        let x = tensor([[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]);
+        let y = tensor([0.5, -1.0]);
+        let r: Tensor1D<2, OwnedTape> = custom_binary_op(x.trace(), &y, RowSumPlus);
+        assert_eq!(r.data(), &[6.5, 14.0]);
+        let gradients = (r * &tensor([1.0, 2.0])).sum().backward();
+        assert_eq!(gradients.ref_gradient(&x), &[[1.0; 3], [2.0; 3]]);
+        assert_eq!(gradients.ref_gradient(&y), &[1.0, 2.0]);
+    }
+
+    #[test]
+    fn test_custom_binary_op_same_tensor() {
+        let x = tensor([1.0, -2.0, 3.0]);
+        let r = custom_binary_op(x.trace(), &x.duplicate(), Mul);
+        assert_eq!(r.data(), &[1.0, 4.0, 9.0]);
+        let gradients = r.sum().backward();
+        assert_eq!(gradients.ref_gradient(&x), &[2.0, -4.0, 6.0]);
+    }
+}
@@ -0,0 +1,417 @@
+use super::utils::{move_tape_and_add_backward_binop, move_tape_and_add_backward_op};
+use crate::arrays::HasShape;
+use crate::devices::DeviceEinsum;
+use crate::prelude::*;
+use std::marker::PhantomData;
+
+/// Einstein summation of 1 or 2 tensors. The equation is a string literal, with one
+/// character per axis of each tensor, e.g. `"ij,jk->ik"` is matrix multiplication.
+///
+/// The type of the output must be known, and the shapes of all tensors are checked
+/// against the equation at compile time.
+///
+/// **Pytorch equivalent**: `torch.einsum(equation, lhs, rhs)`
+///
+/// Labels can't be repeated within a single tensor (so diagonals are not supported),
+/// and every label in the output must be in at least one of the inputs.
+///
+/// Examples:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let q: Tensor3D<2, 3, 4> = TensorCreator::zeros();
+/// let k: Tensor3D<2, 5, 4> = TensorCreator::zeros();
+/// let scores: Tensor3D<2, 3, 5> = einsum!("bqd,bkd->bqk", q, &k);
+/// ```
+///
+/// Bilinear form `x^T A y`:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let x = tensor([1.0, 2.0]);
+/// let a = tensor([[1.0, 0.0, 1.0], [0.0, 1.0, 0.0]]);
+/// let y = tensor([1.0, 2.0, 3.0]);
+/// let xa: Tensor1D<3> = einsum!("i,ij->j", x, &a);
+/// let r: Tensor0D = einsum!("j,j->", xa, &y);
+/// assert_eq!(r.data(), &8.0);
+/// ```
+///
+/// Single tensor reductions & permutations:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let t = tensor([[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]);
+/// let r: Tensor2D<3, 2> = einsum!("ij->ji", t.clone());
+/// assert_eq!(r.data(), &[[1.0, 4.0], [2.0, 5.0], [3.0, 6.0]]);
+/// let r: Tensor1D<3> = einsum!("ij->j", t);
+/// assert_eq!(r.data(), &[5.0, 7.0, 9.0]);
+/// ```
+///
+/// Mismatched shapes fail to compile:
+/// ```compile_fail
+/// # use dfdx::prelude::*;
+/// let a: Tensor2D<2, 3> = TensorCreator::zeros();
+/// let b: Tensor2D<4, 5> = TensorCreator::zeros();
+/// let c: Tensor2D<2, 5> = einsum!("ij,jk->ik", a, &b);
+/// ```
+#[macro_export]
+macro_rules! einsum {
+    ($equation:literal, $t:expr) => {{
+        struct Equation;
+        impl $crate::tensor_ops::EinsumEquation for Equation {
+            const EQUATION: $crate::tensor_ops::Equation =
+                $crate::tensor_ops::Equation::parse($equation);
+        }
+        $crate::tensor_ops::einsum1::<Equation, _, _>($t)
+    }};
+    ($equation:literal, $lhs:expr, $rhs:expr) => {{
+        struct Equation;
+        impl $crate::tensor_ops::EinsumEquation for Equation {
+            const EQUATION: $crate::tensor_ops::Equation =
+                $crate::tensor_ops::Equation::parse($equation);
+        }
+        $crate::tensor_ops::einsum2::<Equation, _, _, _>($lhs, $rhs)
+    }};
+}
+
+pub use crate::einsum;
+
+/// The labels of each axis of a single tensor in an [Equation].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Labels {
+    labels: [u8; 4],
+    len: usize,
+}
+
+impl Labels {
+    const EMPTY: Self = Self {
+        labels: [0; 4],
+        len: 0,
+    };
+
+    pub fn as_slice(&self) -> &[u8] {
+        &self.labels[..self.len]
+    }
+
+    const fn contains(&self, label: u8) -> bool {
+        let mut i = 0;
+        while i < self.len {
+            if self.labels[i] == label {
+                return true;
+            }
+            i += 1;
+        }
+        false
+    }
+}
+
+/// A parsed einsum equation like `"bij,bjk->bik"`. See [einsum!].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Equation {
+    inputs: [Labels; 2],
+    num_inputs: usize,
+    output: Labels,
+}
+
+impl Equation {
+    /// Parses `equation`, panicking if it is invalid. When used in a const context
+    /// the panic is a compile error.
+    pub const fn parse(equation: &str) -> Self {
+        let bytes = equation.as_bytes();
+        let mut labels = [Labels::EMPTY; 3];
+        let mut operand = 0;
+        let mut i = 0;
+        while i < bytes.len() {
+            let c = bytes[i];
+            if c == b',' {
+                assert!(operand == 0, "einsum: only 1 or 2 inputs are supported");
+                operand = 1;
+            } else if c == b'-' {
+                assert!(operand < 2, "einsum: `->` can only appear once");
+                assert!(
+                    i + 1 < bytes.len() && bytes[i + 1] == b'>',
+                    "einsum: expected `->`"
+                );
+                operand = 2;
+                i += 1;
+            } else if c != b' ' {
+                assert!(
+                    c.is_ascii_alphabetic(),
+                    "einsum: labels must be ascii letters"
+                );
+                assert!(
+                    labels[operand].len < 4,
+                    "einsum: at most 4 axes are supported"
+                );
+                assert!(
+                    !labels[operand].contains(c),
+                    "einsum: labels can't be repeated within a tensor"
+                );
+                labels[operand].labels[labels[operand].len] = c;
+                labels[operand].len += 1;
+            }
+            i += 1;
+        }
+        assert!(operand == 2, "einsum: equation must contain `->`");
+
+        let num_inputs = if bytes_contain(bytes, b',') { 2 } else { 1 };
+        let mut i = 0;
+        while i < labels[2].len {
+            let c = labels[2].labels[i];
+            assert!(
+                labels[0].contains(c) || labels[1].contains(c),
+                "einsum: every output label must be in an input"
+            );
+            i += 1;
+        }
+
+        Self {
+            inputs: [labels[0], labels[1]],
+            num_inputs,
+            output: labels[2],
+        }
+    }
+
+    /// Panics if the shapes of the tensors don't match the labels of the equation,
+    /// or if labels with the same character have different sizes.
+    pub const fn check(&self, num_inputs: usize, lhs: &[usize], rhs: &[usize], out: &[usize]) {
+        assert!(
+            self.num_inputs == num_inputs,
+            "einsum: wrong number of inputs for equation"
+        );
+        let shapes = [lhs, rhs, out];
+        let labels = [self.inputs[0], self.inputs[1], self.output];
+        let mut sizes = [0; 128];
+        let mut seen = [false; 128];
+        let mut t = 0;
+        while t < 3 {
+            assert!(
+                labels[t].len == shapes[t].len(),
+                "einsum: number of labels doesn't match number of axes"
+            );
+            let mut i = 0;
+            while i < labels[t].len {
+                let c = labels[t].labels[i] as usize;
+                if seen[c] {
+                    assert!(
+                        sizes[c] == shapes[t][i],
+                        "einsum: axes with the same label have different sizes"
+                    );
+                } else {
+                    seen[c] = true;
+                    sizes[c] = shapes[t][i];
+                }
+                i += 1;
+            }
+            t += 1;
+        }
+    }
+}
+
+const fn bytes_contain(bytes: &[u8], c: u8) -> bool {
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == c {
+            return true;
+        }
+        i += 1;
+    }
+    false
+}
+
+/// A type level einsum [Equation]. This is implemented by [einsum!] for you.
+pub trait EinsumEquation {
+    const EQUATION: Equation;
+}
+
+/// Evaluating these constants during monomorphization turns shape
+/// mismatches into compile errors.
+struct ShapeCheck<E, L, R, O>(PhantomData<(E, L, R, O)>);
+
+impl<E: EinsumEquation, L: HasShape, R: HasShape, O: HasShape> ShapeCheck<E, L, R, O> {
+    const UNARY: () = E::EQUATION.check(1, L::SHAPE, R::SHAPE, O::SHAPE);
+    const BINARY: () = E::EQUATION.check(2, L::SHAPE, R::SHAPE, O::SHAPE);
+}
+
+/// Einstein summation of a single tensor using equation `E`. Use [einsum!] instead of
+/// calling this directly.
+pub fn einsum1<E: EinsumEquation, T, O>(t: T) -> O
+where
+    T: Tensor<Dtype = f32>,
+    O: Tensor<Dtype = f32, Tape = T::Tape>,
+    T::Device: DeviceEinsum<T::Array, f32, O::Array> + DeviceEinsum<O::Array, f32, T::Array>,
+{
+    let () = ShapeCheck::<E, T::Array, f32, O::Array>::UNARY;
+    let [inp, _] = E::EQUATION.inputs;
+    let out = E::EQUATION.output;
+
+    let mut result = O::NoTape::zeros();
+    T::Device::einsum(
+        t.data(),
+        inp.as_slice(),
+        &1.0,
+        &[],
+        result.mut_data(),
+        out.as_slice(),
+    );
+    move_tape_and_add_backward_op(t, result, move |t, result, grads| {
+        let (t_grad, result_grad) = grads.mut_and_ref(&t, &result);
+        T::Device::einsum(
+            result_grad,
+            out.as_slice(),
+            &1.0,
+            &[],
+            t_grad,
+            inp.as_slice(),
+        );
+    })
+}
+
+/// Einstein summation of two tensors using equation `E`. Use [einsum!] instead of
+/// calling this directly.
+pub fn einsum2<E: EinsumEquation, L, R, O>(lhs: L, rhs: &R) -> O
+where
+    L: Tensor<Dtype = f32>,
+    R: 'static + Tensor<Dtype = f32> + Clone,
+    O: Tensor<Dtype = f32, Tape = L::Tape>,
+    L::Device: DeviceEinsum<L::Array, R::Array, O::Array>
+        + DeviceEinsum<O::Array, R::Array, L::Array>
+        + DeviceEinsum<L::Array, O::Array, R::Array>,
+{
+    let () = ShapeCheck::<E, L::Array, R::Array, O::Array>::BINARY;
+    let [l, r] = E::EQUATION.inputs;
+    let o = E::EQUATION.output;
+
+    let mut result = O::NoTape::zeros();
+    L::Device::einsum(
+        lhs.data(),
+        l.as_slice(),
+        rhs.data(),
+        r.as_slice(),
+        result.mut_data(),
+        o.as_slice(),
+    );
+
+    let rhs_ = rhs.clone();
+    move_tape_and_add_backward_binop(lhs, rhs, result, move |lhs, rhs, result, grads| {
+        let (lhs_grad, result_grad) = grads.mut_and_ref(&lhs, &result);
+        L::Device::einsum(
+            result_grad,
+            o.as_slice(),
+            rhs_.data(),
+            r.as_slice(),
+            lhs_grad,
+            l.as_slice(),
+        );
+
+        let (rhs_grad, result_grad) = grads.mut_and_ref(&rhs, &result);
+        L::Device::einsum(
+            lhs.data(),
+            l.as_slice(),
+            result_grad,
+            o.as_slice(),
+            rhs_grad,
+            r.as_slice(),
+        );
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{assert_close, AssertClose};
+    use rand::thread_rng;
+
+    #[test]
+    fn test_parse() {
+        const EQ: Equation = Equation::parse("bij, bjk -> bik");
+        assert_eq!(EQ.num_inputs, 2);
+        assert_eq!(EQ.inputs[0].as_slice(), b"bij");
+        assert_eq!(EQ.inputs[1].as_slice(), b"bjk");
+        assert_eq!(EQ.output.as_slice(), b"bik");
+
+        const EQ1: Equation = Equation::parse("ij->");
+        assert_eq!(EQ1.num_inputs, 1);
+        assert_eq!(EQ1.output.as_slice(), b"");
+    }
+
+    #[test]
+    #[should_panic = "every output label must be in an input"]
+    fn test_parse_missing_output_label() {
+        Equation::parse("ij->ik");
+    }
+
+    #[test]
+    #[should_panic = "labels can't be repeated within a tensor"]
+    fn test_parse_repeated_label() {
+        Equation::parse("ii->i");
+    }
+
+    #[test]
+    fn test_einsum_matches_matmul() {
+        let mut rng = thread_rng();
+        let a: Tensor3D<2, 3, 4> = TensorCreator::randn(&mut rng);
+        let b: Tensor3D<2, 4, 5> = TensorCreator::randn(&mut rng);
+        let r1: Tensor3D<2, 3, 5, _> = einsum!("bij,bjk->bik", a.trace(), &b);
+        let r2: Tensor3D<2, 3, 5, _> = matmul(a.trace(), &b);
+        r1.data().assert_close(r2.data(), 1e-6);
+        let g1 = r1.exp().mean::<_, AllAxes>().backward();
+        let g2 = r2.exp().mean::<_, AllAxes>().backward();
+        g1.ref_gradient(&a).assert_close(g2.ref_gradient(&a), 1e-6);
+        g1.ref_gradient(&b).assert_close(g2.ref_gradient(&b), 1e-6);
+    }
+
+    #[test]
+    fn test_einsum_attention_scores() {
+        let mut rng = thread_rng();
+        let q: Tensor4D<2, 3, 4, 5> = TensorCreator::randn(&mut rng);
+        let k: Tensor4D<2, 3, 6, 5> = TensorCreator::randn(&mut rng);
+        let r1: Tensor4D<2, 3, 4, 6, _> = einsum!("bhqd,bhkd->bhqk", q.trace(), &k);
+        let r2: Tensor4D<2, 3, 4, 6, _> = matmul_transpose(q.trace(), &k);
+        r1.data().assert_close(r2.data(), 1e-6);
+        let g1 = r1.exp().mean::<_, AllAxes>().backward();
+        let g2 = r2.exp().mean::<_, AllAxes>().backward();
+        g1.ref_gradient(&q).assert_close(g2.ref_gradient(&q), 1e-6);
+        g1.ref_gradient(&k).assert_close(g2.ref_gradient(&k), 1e-6);
+    }
+
+    #[test]
+    fn test_einsum_outer_product() {
+        let a = tensor([1.0, 2.0]);
+        let b = tensor([-1.0, 0.5, 2.0]);
+        let r: Tensor2D<2, 3, _> = einsum!("i,j->ij", a.trace(), &b);
+        assert_eq!(r.data(), &[[-1.0, 0.5, 2.0], [-2.0, 1.0, 4.0]]);
+        let g = r.sum::<_, AllAxes>().backward();
+        assert_eq!(g.ref_gradient(&a), &[1.5; 2]);
+        assert_eq!(g.ref_gradient(&b), &[3.0; 3]);
+    }
+
+    #[test]
+    fn test_einsum_sum_only_in_one_input() {
+        let a = tensor([[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]);
+        let b = tensor([1.0, -1.0]);
+        let r: Tensor0D<_> = einsum!("ij,i->", a.trace(), &b);
+        assert_eq!(r.data(), &-9.0);
+        let g = r.backward();
+        assert_eq!(g.ref_gradient(&a), &[[1.0; 3], [-1.0; 3]]);
+        assert_eq!(g.ref_gradient(&b), &[6.0, 15.0]);
+    }
+
+    #[test]
+    fn test_einsum_unary_permute() {
+        let t = tensor([[[1.0, 2.0], [3.0, 4.0], [5.0, 6.0]]]);
+        let r: Tensor3D<2, 1, 3, _> = einsum!("abc->cab", t.trace());
+        assert_eq!(r.data(), &[[[1.0, 3.0, 5.0]], [[2.0, 4.0, 6.0]]]);
+        let g = r.exp().sum::<_, AllAxes>().backward();
+        assert_close(
+            g.ref_gradient(&t),
+            &t.data().map(|x| x.map(|x| x.map(f32::exp))),
+        );
+    }
+
+    #[test]
+    fn test_einsum_unary_reduce() {
+        let t = tensor([[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]);
+        let r: Tensor1D<2, _> = einsum!("ij->i", t.trace());
+        assert_eq!(r.data(), &[6.0, 15.0]);
+        let g = (r * &tensor([1.0, 2.0])).sum::<_, AllAxes>().backward();
+        assert_eq!(g.ref_gradient(&t), &[[1.0; 3], [2.0; 3]]);
+    }
+}
@@ -0,0 +1,497 @@
+//! Discrete fourier transforms along the last axis of tensors: [fft()], [ifft()], [rfft()],
+//! [irfft()], and [stft()].
+//!
+//! # Complex tensors
+//!
+//! Complex tensors are represented by a pair of tensors with the same shape: `(re, im)`.
+//! Functions that take a complex tensor take `re` by value and `im` by reference, just like
+//! the other binary operations (e.g. [add()]), and gradients flow into both of them.
+//!
+//! Only one tensor can own the tape, so functions that return a complex tensor put the tape
+//! on `re`. Gradients still flow back through `im`, as long as it is used in operations
+//! that are recorded on the tape. Use [complex_abs()] for magnitudes, or move the tape
+//! over to `im` when you need to use it by value:
+//! ```rust
+//! # use dfdx::prelude::*;
+//! let x: Tensor1D<4> = tensor([1.0, 2.0, 3.0, 4.0]);
+//! let (re, im): (Tensor1D<3, OwnedTape>, Tensor1D<3>) = x.trace().rfft();
+//! let (re_sq, tape) = re.square().split_tape();
+//! let power = im.put_tape(tape).square() + &re_sq;
+//! assert_tensor_close!(power, tensor([100.0, 8.0, 4.0]));
+//! ```
+//!
+//! # Normalization
+//!
+//! Forward transforms are unnormalized, and inverse transforms are scaled by `1 / N`.
+//! This is the default (`norm="backward"`) in pytorch and numpy.
+
+use super::utils::{binary_map, move_tape_and_add_backward_op};
+use crate::arrays::{HasArrayType, HasLastAxis};
+use crate::devices::{AllocateZeros, Cpu, Device, DeviceFft, ForEachElement};
+use crate::gradients::Tape;
+use crate::prelude::*;
+use std::f32::consts::PI;
+
+/// Enables [rfft()] and [irfft()], where `R` is `Self` with a last axis of size `N / 2 + 1`,
+/// and `N` is the size of the last axis of `Self`.
+///
+/// Const generic expressions aren't supported yet, so the size of the last axis of `R` is
+/// checked at runtime.
+///
+/// This trait can't be used directly as it doesn't contain any methods.
+pub trait RfftTo<R: HasArrayType<Dtype = f32>>: Tensor<Dtype = f32> {}
+
+macro_rules! impl_rfft {
+    ($SrcTy:ty, $DstTy:ty, {$($Dims:tt),*}) => {
+impl<$(const $Dims: usize, )* H: Tape> RfftTo<$DstTy> for $SrcTy {}
+    };
+}
+
+impl_rfft!(Tensor1D<N, H>, Tensor1D<K, H>, {N, K});
+impl_rfft!(Tensor2D<M, N, H>, Tensor2D<M, K, H>, {M, N, K});
+impl_rfft!(Tensor3D<M, N, O, H>, Tensor3D<M, N, K, H>, {M, N, O, K});
+impl_rfft!(Tensor4D<M, N, O, P, H>, Tensor4D<M, N, O, K, H>, {M, N, O, P, K});
+
+/// Complex discrete fourier transform along the last axis of `(re, im)`.
+///
+/// **Pytorch equivalent**: `torch.fft.fft(torch.complex(re, im))`
+///
+/// **Related functions**: [ifft()], [rfft()]
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let re = tensor([[1.0, 0.0, 0.0], [1.0, 1.0, 1.0]]);
+/// let im = tensor([[0.0; 3]; 2]);
+/// let (re, im) = fft(re, &im);
+/// assert_tensor_close!(re, tensor([[1.0; 3], [3.0, 0.0, 0.0]]), rtol = 0.0, atol = 1e-6);
+/// assert_tensor_close!(im, tensor([[0.0; 3]; 2]), rtol = 0.0, atol = 1e-6);
+/// ```
+pub fn fft<T: Tensor<Dtype = f32>>(re: T, im: &T::NoTape) -> (T, T::NoTape) {
+    complex_dft(re, im, false)
+}
+
+/// Inverse of [fft()] along the last axis of `(re, im)`, scaled by `1 / N`.
+///
+/// **Pytorch equivalent**: `torch.fft.ifft(torch.complex(re, im))`
+///
+/// **Related functions**: [fft()], [irfft()]
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let re = tensor([1.0, 2.0, 3.0, 4.0]);
+/// let im = tensor([0.0, -1.0, 0.5, 0.0]);
+/// let (f_re, f_im) = fft(re.clone(), &im);
+/// let (re2, im2) = ifft(f_re, &f_im);
+/// assert_tensor_close!(re2, re);
+/// assert_tensor_close!(im2, im, rtol = 0.0, atol = 1e-6);
+/// ```
+pub fn ifft<T: Tensor<Dtype = f32>>(re: T, im: &T::NoTape) -> (T, T::NoTape) {
+    complex_dft(re, im, true)
+}
+
+fn complex_dft<T: Tensor<Dtype = f32>>(re: T, im: &T::NoTape, inverse: bool) -> (T, T::NoTape) {
+    let scale = if inverse {
+        1.0 / <T::Array as HasLastAxis>::SIZE as f32
+    } else {
+        1.0
+    };
+
+    let mut re_out = T::NoTape::zeros();
+    let mut im_out = T::NoTape::zeros();
+    re_out.mut_data().clone_from(re.data());
+    im_out.mut_data().clone_from(im.data());
+    Cpu::fft(re_out.mut_data(), im_out.mut_data(), inverse);
+    if inverse {
+        T::Device::foreach_m(re_out.mut_data(), &mut |x| *x *= scale);
+        T::Device::foreach_m(im_out.mut_data(), &mut |x| *x *= scale);
+    }
+
+    let phantom_im = im.phantom();
+    let phantom_im_out = im_out.phantom();
+    let re_out = move_tape_and_add_backward_op(re, re_out, move |re, re_out, grads| {
+        // the adjoint of a DFT is the DFT in the other direction
+        let mut re_grad: Box<T::Array> = T::Device::zeros();
+        let mut im_grad: Box<T::Array> = T::Device::zeros();
+        re_grad.as_mut().clone_from(grads.mut_gradient(&re_out));
+        im_grad
+            .as_mut()
+            .clone_from(grads.mut_gradient(&phantom_im_out));
+        Cpu::fft(re_grad.as_mut(), im_grad.as_mut(), !inverse);
+        if inverse {
+            T::Device::foreach_m(re_grad.as_mut(), &mut |x| *x *= scale);
+            T::Device::foreach_m(im_grad.as_mut(), &mut |x| *x *= scale);
+        }
+        T::Device::add(grads.mut_gradient(&re), re_grad.as_ref());
+        T::Device::add(grads.mut_gradient(&phantom_im), im_grad.as_ref());
+    });
+    (re_out, im_out)
+}
+
+/// Discrete fourier transform of the real tensor `t` along its last axis. Only the
+/// first `N / 2 + 1` frequencies are returned, since the rest are the complex conjugates of them.
+///
+/// **Pytorch equivalent**: `torch.fft.rfft(t)`
+///
+/// **Related functions**: [irfft()], [fft()], [stft()]
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let t: Tensor1D<4> = tensor([1.0, 2.0, 3.0, 4.0]);
+/// let (re, im): (Tensor1D<3>, Tensor1D<3>) = t.rfft();
+/// assert_tensor_close!(re, tensor([10.0, -2.0, -2.0]));
+/// assert_tensor_close!(im, tensor([0.0, 2.0, 0.0]), rtol = 0.0, atol = 1e-6);
+/// ```
+pub fn rfft<T, R>(t: T) -> (R, R::NoTape)
+where
+    T: RfftTo<R>,
+    R: Tensor<Dtype = f32, Tape = T::Tape>,
+{
+    check_num_frequencies::<T, R>();
+    let mut re = R::NoTape::zeros();
+    let mut im = R::NoTape::zeros();
+    <Cpu as DeviceFft<T::Array>>::rfft(t.data(), re.mut_data(), im.mut_data());
+
+    let phantom_im = im.phantom();
+    let re = move_tape_and_add_backward_op::<_, R, _>(t, re, move |t, re, grads| {
+        let mut re_grad: Box<R::Array> = R::Device::zeros();
+        let mut im_grad: Box<R::Array> = R::Device::zeros();
+        re_grad.as_mut().clone_from(grads.mut_gradient(&re));
+        im_grad.as_mut().clone_from(grads.mut_gradient(&phantom_im));
+        Cpu::rfft_backward(re_grad.as_ref(), im_grad.as_ref(), grads.mut_gradient(&t));
+    });
+    (re, im)
+}
+
+/// Inverse of [rfft()]: the real signal `T` with the frequencies `(re, im)` along its
+/// last axis, scaled by `1 / N`. The imaginary parts of the first frequency (and the last one if
+/// `N` is even) are ignored, since they are always zero for real signals.
+///
+/// **Pytorch equivalent**: `torch.fft.irfft(torch.complex(re, im), n=N)`
+///
+/// **Related functions**: [rfft()], [ifft()]
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let re = tensor([10.0, -2.0, -2.0]);
+/// let im = tensor([0.0, 2.0, 0.0]);
+/// let t: Tensor1D<4> = irfft(re, &im);
+/// assert_tensor_close!(t, tensor([1.0, 2.0, 3.0, 4.0]));
+/// ```
+pub fn irfft<R, T>(re: R, im: &R::NoTape) -> T
+where
+    R: Tensor<Dtype = f32>,
+    T: RfftTo<R, Tape = R::Tape>,
+{
+    check_num_frequencies::<T, R>();
+    let mut t = T::NoTape::zeros();
+    <Cpu as DeviceFft<T::Array>>::irfft(re.data(), im.data(), t.mut_data());
+
+    let phantom_im = im.phantom();
+    move_tape_and_add_backward_op(re, t, move |re, t, grads| {
+        let mut re_grad: Box<R::Array> = R::Device::zeros();
+        let mut im_grad: Box<R::Array> = R::Device::zeros();
+        Cpu::irfft_backward(grads.ref_gradient(&t), re_grad.as_mut(), im_grad.as_mut());
+        R::Device::add(grads.mut_gradient(&re), re_grad.as_ref());
+        R::Device::add(grads.mut_gradient(&phantom_im), im_grad.as_ref());
+    })
+}
+
+fn check_num_frequencies<T: HasArrayType, R: HasArrayType>() {
+    let n = <T::Array as HasLastAxis>::SIZE;
+    let k = <R::Array as HasLastAxis>::SIZE;
+    assert_eq!(
+        k,
+        n / 2 + 1,
+        "A real signal of length {} has {} frequencies, but found {}",
+        n,
+        n / 2 + 1,
+        k
+    );
+}
+
+/// The magnitude `sqrt(re^2 + im^2)` of the complex tensor `(re, im)`.
+///
+/// The derivatives are `re / |z|` for `re` and `im / |z|` for `im`, and `0` where `|z|` is `0`.
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let r = complex_abs(tensor([3.0, 0.0, -1.0]), &tensor([4.0, 0.0, 0.0]));
+/// assert_eq!(r.data(), &[5.0, 0.0, 1.0]);
+/// ```
+pub fn complex_abs<T: Tensor<Dtype = f32>>(re: T, im: &T::NoTape) -> T {
+    fn f(re: &f32, im: &f32) -> f32 {
+        re.hypot(*im)
+    }
+    fn dfdre(re: &f32, im: &f32) -> f32 {
+        let abs = re.hypot(*im);
+        if abs == 0.0 {
+            0.0
+        } else {
+            re / abs
+        }
+    }
+    fn dfdim(re: &f32, im: &f32) -> f32 {
+        dfdre(im, re)
+    }
+    binary_map(re, im, f, dfdre, dfdim)
+}
+
+/// Short time fourier transform of `signal`: the [rfft()] of each frame of length `N`, multiplied
+/// by `window`. Frames start every `hop` elements, and there are `F = (L - N) / hop + 1` of them.
+/// If `(L - N)` isn't a multiple of `hop`, the trailing samples after the last frame are dropped
+/// (and get a gradient of `0.0`). Returns the frequencies as `(re, im)` with shape `(F, N / 2 + 1)`.
+///
+/// Gradients flow into both `signal` and `window`.
+///
+/// **Pytorch equivalent**: `torch.stft(signal, N, hop, window=window, center=False, return_complex=True).T`
+///
+/// **Related functions**: [hann_window()], [complex_abs()]
+///
+/// **Panics** if `hop` is 0, or `F` isn't the number of frames.
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let signal: Tensor1D<10> = TensorCreator::ones();
+/// let (re, im): (Tensor2D<4, 3>, Tensor2D<4, 3>) = stft(signal, &hann_window::<4>(), 2);
+/// let spectrogram = complex_abs(re, &im);
+/// assert_tensor_close!(
+///     spectrogram,
+///     tensor([[2.0, 1.0, 0.0]; 4]),
+///     rtol = 0.0,
+///     atol = 1e-5,
+/// );
+/// ```
+pub fn stft<const L: usize, const N: usize, const F: usize, const K: usize, H: Tape>(
+    signal: Tensor1D<L, H>,
+    window: &Tensor1D<N>,
+    hop: usize,
+) -> (Tensor2D<F, K, H>, Tensor2D<F, K>) {
+    let frames: Tensor2D<F, N, H> = split_frames(signal, hop);
+    rfft(mul(frames, window))
+}
+
+/// Splits `signal` into `F` frames of length `N`, that start every `hop` elements.
+fn split_frames<const L: usize, const N: usize, const F: usize, H: Tape>(
+    signal: Tensor1D<L, H>,
+    hop: usize,
+) -> Tensor2D<F, N, H> {
+    assert!(
+        hop > 0 && L >= N && F == (L - N) / hop + 1,
+        "Can't split a signal of length {} into {} frames of length {} with a hop of {}",
+        L,
+        F,
+        N,
+        hop
+    );
+    let mut frames: Tensor2D<F, N> = TensorCreator::zeros();
+    for (i, frame) in frames.mut_data().iter_mut().enumerate() {
+        frame.copy_from_slice(&signal.data()[i * hop..i * hop + N]);
+    }
+    move_tape_and_add_backward_op::<_, Tensor2D<F, N, H>, _>(
+        signal,
+        frames,
+        move |signal, frames, grads| {
+            let (signal_grad, frames_grad) = grads.mut_and_ref(&signal, &frames);
+            for (i, frame_grad) in frames_grad.iter().enumerate() {
+                for (g, f) in signal_grad[i * hop..].iter_mut().zip(frame_grad.iter()) {
+                    *g += f;
+                }
+            }
+        },
+    )
+}
+
+/// The periodic [hann window](https://en.wikipedia.org/wiki/Hann_function) of length `N`:
+/// `0.5 - 0.5 * cos(2 * pi * n / N)`.
+///
+/// **Pytorch equivalent**: `torch.hann_window(N)`
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// assert_tensor_close!(hann_window::<4>(), tensor([0.0, 0.5, 1.0, 0.5]), rtol = 0.0, atol = 1e-6);
+/// ```
+pub fn hann_window<const N: usize>() -> Tensor1D<N> {
+    let mut window: Tensor1D<N> = TensorCreator::zeros();
+    for (i, w) in window.mut_data().iter_mut().enumerate() {
+        *w = 0.5 - 0.5 * (2.0 * PI * i as f32 / N as f32).cos();
+    }
+    window
+}
+
+macro_rules! tensor_impl {
+    ($typename:ident, [$($Vs:tt),*]) => {
+impl<$(const $Vs: usize, )* H: Tape> $typename<$($Vs, )* H> {
+    /// Calls [fft()] on `self` and `im`.
+    pub fn fft(self, im: &<Self as Tensor>::NoTape) -> (Self, <Self as Tensor>::NoTape) {
+        fft(self, im)
+    }
+
+    /// Calls [ifft()] on `self` and `im`.
+    pub fn ifft(self, im: &<Self as Tensor>::NoTape) -> (Self, <Self as Tensor>::NoTape) {
+        ifft(self, im)
+    }
+
+    /// Calls [rfft()] on `self`.
+    pub fn rfft<R>(self) -> (R, R::NoTape)
+    where
+        Self: RfftTo<R>,
+        R: Tensor<Dtype = f32, Tape = <Self as Tensor>::Tape>,
+    {
+        rfft(self)
+    }
+
+    /// Calls [irfft()] on `self` and `im`.
+    pub fn irfft<T: RfftTo<Self, Tape = <Self as Tensor>::Tape>>(self, im: &<Self as Tensor>::NoTape) -> T {
+        irfft(self, im)
+    }
+
+    /// Calls [complex_abs()] on `self` and `im`.
+    pub fn complex_abs(self, im: &<Self as Tensor>::NoTape) -> Self {
+        complex_abs(self, im)
+    }
+}
+    };
+}
+
+tensor_impl!(Tensor1D, [M]);
+tensor_impl!(Tensor2D, [M, N]);
+tensor_impl!(Tensor3D, [M, N, O]);
+tensor_impl!(Tensor4D, [M, N, O, P]);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arrays::AllAxes;
+
+    #[test]
+    fn test_fft_backward() {
+        // sum(re_out) = N * re[0], sum(im_out) = N * im[0]
+        let re: Tensor1D<5> = tensor([1.0, 2.0, -1.0, 0.5, 3.0]);
+        let im: Tensor1D<5> = tensor([0.0, 1.0, 2.0, -1.0, 0.0]);
+        let (re_out, im_out) = fft(re.trace(), &im);
+        assert_tensor_close!(re_out.data()[0], 5.5);
+        assert_tensor_close!(im_out.data()[0], 2.0);
+        let (re_out, tape) = re_out.split_tape();
+        let r = add(im_out.put_tape(tape), &re_out);
+        let g = r.sum::<_, AllAxes>().backward();
+        assert_tensor_close!(
+            g.ref_gradient(&re),
+            [5.0, 0.0, 0.0, 0.0, 0.0],
+            rtol = 0.0,
+            atol = 1e-5
+        );
+        assert_tensor_close!(
+            g.ref_gradient(&im),
+            [5.0, 0.0, 0.0, 0.0, 0.0],
+            rtol = 0.0,
+            atol = 1e-5
+        );
+    }
+
+    #[test]
+    fn test_ifft_backward_unused_im() {
+        // re_out[0] = mean(re)
+        let re: Tensor2D<2, 4> = tensor([[1.0, 2.0, 3.0, 4.0], [0.0, -1.0, 0.0, 1.0]]);
+        let (re_out, _) = ifft(re.trace(), &Tensor2D::zeros());
+        assert_tensor_close!(re_out.data()[0][0], 2.5);
+        let mask = tensor([[1.0, 0.0, 0.0, 0.0], [0.0; 4]]);
+        let g = mul(re_out, &mask).sum::<_, AllAxes>().backward();
+        assert_tensor_close!(
+            g.ref_gradient(&re),
+            [[0.25; 4], [0.0; 4]],
+            rtol = 0.0,
+            atol = 1e-6
+        );
+    }
+
+    #[test]
+    fn test_rfft_irfft_backward() {
+        let x: Tensor2D<2, 5> = tensor([[1.0, -2.0, 3.0, 0.5, 4.0], [0.0, 1.0, 0.0, -1.0, 2.0]]);
+        let (re, im): (Tensor2D<2, 3, OwnedTape>, _) = x.trace().rfft();
+        let y: Tensor2D<2, 5, OwnedTape> = irfft(re, &im);
+        assert_tensor_close!(y, x.clone());
+        let w = tensor([[1.0, 2.0, 3.0, 4.0, 5.0], [-1.0, 0.0, 1.0, 0.0, 2.0]]);
+        let g = mul(y, &w).sum::<_, AllAxes>().backward();
+        assert_tensor_close!(g.ref_gradient(&x), *w.data());
+    }
+
+    #[test]
+    fn test_rfft_grad_matches_numerical() {
+        let x: Tensor1D<6> = tensor([0.3, -1.0, 2.0, 0.5, 0.0, 1.5]);
+        let loss = |x: Tensor1D<6, OwnedTape>| {
+            let (re, im): (Tensor1D<4, OwnedTape>, _) = x.rfft();
+            complex_abs(re, &im).sum::<_, AllAxes>()
+        };
+        let g = loss(x.trace()).backward();
+        for i in 0..6 {
+            let mut xp = x.clone();
+            xp.mut_data()[i] += 1e-2;
+            let mut xm = x.clone();
+            xm.mut_data()[i] -= 1e-2;
+            let fd = (loss(xp.traced()).data() - loss(xm.traced()).data()) / 2e-2;
+            assert!((g.ref_gradient(&x)[i] - fd).abs() < 1e-2);
+        }
+    }
+
+    #[test]
+    fn test_irfft_ignores_imaginary_dc_and_nyquist() {
+        let re = tensor([4.0, 0.0, 0.0]);
+        let im = tensor([1.0, 0.0, 1.0]);
+        let t: Tensor1D<4, OwnedTape> = irfft(re.trace(), &im);
+        assert_tensor_close!(t, tensor([1.0; 4]));
+        let g = t.sum::<_, AllAxes>().backward();
+        assert_tensor_close!(
+            g.ref_gradient(&re),
+            [1.0, 0.0, 0.0],
+            rtol = 0.0,
+            atol = 1e-6
+        );
+        assert_tensor_close!(g.ref_gradient(&im), [0.0; 3], rtol = 0.0, atol = 1e-6);
+    }
+
+    #[test]
+    fn test_stft_backward() {
+        let signal: Tensor1D<6> = TensorCreator::arange();
+        let window: Tensor1D<4> = TensorCreator::ones();
+        let (re, _): (Tensor2D<2, 3, OwnedTape>, _) = stft(signal.trace(), &window, 2);
+        // the first frequency of each frame is the sum of the frame
+        assert_tensor_close!(re.data().map(|f| f[0]), [6.0, 14.0]);
+        let g = re.sum::<_, AllAxes>().backward();
+        // the real parts of a frame sum to 3 * f[0] + f[2], and the frames overlap at x[2]
+        assert_tensor_close!(
+            g.ref_gradient(&signal),
+            [3.0, 0.0, 4.0, 0.0, 1.0, 0.0],
+            rtol = 0.0,
+            atol = 1e-5
+        );
+    }
+
+    #[test]
+    fn test_stft_drops_trailing_samples() {
+        let signal: Tensor1D<7> = TensorCreator::arange();
+        let window: Tensor1D<4> = TensorCreator::ones();
+        let (re, _): (Tensor2D<2, 3, OwnedTape>, _) = stft(signal.trace(), &window, 2);
+        assert_tensor_close!(re.data().map(|f| f[0]), [6.0, 14.0]);
+        let g = re.sum::<_, AllAxes>().backward();
+        assert_eq!(g.ref_gradient(&signal)[6], 0.0);
+    }
+
+    #[test]
+    #[should_panic = "A real signal of length 4 has 3 frequencies, but found 4"]
+    fn test_rfft_wrong_num_frequencies() {
+        let _: (Tensor1D<4>, _) = Tensor1D::<4>::zeros().rfft();
+    }
+
+    #[test]
+    #[should_panic = "Can't split a signal of length 10 into 3 frames of length 4 with a hop of 2"]
+    fn test_stft_wrong_num_frames() {
+        let _: (Tensor2D<3, 3>, _) = stft(Tensor1D::<10>::zeros(), &hann_window::<4>(), 2);
+    }
+}
@@ -0,0 +1,145 @@
+use crate::gradients::Tape;
+use crate::prelude::*;
+
+/// Registers `hook` to be called with the gradient of `t` during backward, and replaces
+/// the gradient of `t` with what `hook` returns. Returns `t` unchanged, so the rest of the
+/// forward pass should use the returned tensor.
+///
+/// The hook is called once the gradient of `t` is fully computed (i.e. after backprop through
+/// everything that used `t`), and before it is backpropagated to whatever `t` was computed
+/// from. Normal tensor operations can be used on the gradient, since it is a tensor without a tape.
+///
+/// Does nothing for tensors without [crate::gradients::OwnedTape].
+///
+/// **Pytorch equivalent**: `t.register_hook(hook)`
+///
+/// Examples:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// // gradient reversal
+/// let x = tensor([1.0, 2.0, 3.0]);
+/// let y = x.trace().square().register_hook(|g| negate(g)).sum();
+/// let gradients = y.backward();
+/// assert_eq!(gradients.ref_gradient(&x), &[-2.0, -4.0, -6.0]);
+/// ```
+///
+/// Logging gradients:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// # use std::{cell::RefCell, rc::Rc};
+/// let norms = Rc::new(RefCell::new(Vec::new()));
+/// let log = norms.clone();
+/// let x = tensor([3.0, 4.0]);
+/// let y = x.trace().register_hook(move |g| {
+///     log.borrow_mut().push(*g.clone().square().sum().sqrt().data());
+///     g
+/// });
+/// let _ = (y * 2.0).sum().backward();
+/// assert_eq!(norms.borrow().as_slice(), &[2.0 * 2.0f32.sqrt()]);
+/// ```
+pub fn register_hook<T, F>(t: T, hook: F) -> T
+where
+    T: Tensor<Dtype = f32>,
+    F: 'static + FnOnce(T::NoTape) -> T::NoTape,
+{
+    let (t, mut tape) = t.split_tape();
+    let _t = t.phantom();
+    tape.add_backward_op(move |grads| {
+        let gradient = hook(T::NoTape::new_boxed(grads.clone_gradient(&_t)));
+        grads.mut_gradient(&_t).clone_from(gradient.data());
+    });
+    t.put_tape(tape)
+}
+
+macro_rules! tensor_impl {
+    ($typename:ident, [$($Vs:tt),*]) => {
+impl<$(const $Vs: usize, )* H: Tape> $typename<$($Vs, )* H> {
+    /// Calls [register_hook()] on `self`.
+    pub fn register_hook<F>(self, hook: F) -> Self
+    where
+        F: 'static + FnOnce($typename<$($Vs, )* NoneTape>) -> $typename<$($Vs, )* NoneTape>,
+    {
+        register_hook(self, hook)
+    }
+}
+    };
+}
+
+tensor_impl!(Tensor0D, []);
+tensor_impl!(Tensor1D, [M]);
+tensor_impl!(Tensor2D, [M, N]);
+tensor_impl!(Tensor3D, [M, N, O]);
+tensor_impl!(Tensor4D, [M, N, O, P]);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{cell::RefCell, rc::Rc};
+
+    #[test]
+    fn test_hook_sees_full_gradient() {
+        let seen = Rc::new(RefCell::new(None));
+        let log = seen.clone();
+        let x = tensor([1.0, 2.0]);
+        let h = (x.trace() * 3.0).register_hook(move |g| {
+            *log.borrow_mut() = Some(*g.data());
+            g
+        });
+        let h_ = h.duplicate();
+        // h is used twice, so its gradient is the sum of both uses
+        let y = add(h.square(), &h_).sum();
+        let gradients = y.backward();
+        assert_eq!(seen.borrow().unwrap(), [7.0, 13.0]);
+        assert_eq!(gradients.ref_gradient(&x), &[21.0, 39.0]);
+    }
+
+    #[test]
+    fn test_hook_replaces_gradient() {
+        let x: Tensor2D<2, 2> = tensor([[1.0, -2.0], [3.0, -4.0]]);
+        let y = x.trace().square().register_hook(|g| g.clamp(-1.0, 1.0)) * 10.0;
+        let y = y.sum::<_, AllAxes>();
+        let gradients = y.backward();
+        assert_eq!(gradients.ref_gradient(&x), &[[2.0, -4.0], [6.0, -8.0]]);
+    }
+
+    #[test]
+    fn test_hooks_run_in_backward_order() {
+        let order = Rc::new(RefCell::new(Vec::new()));
+        let (first, second) = (order.clone(), order.clone());
+        let x = tensor(2.0);
+        let y = x
+            .trace()
+            .register_hook(move |g| {
+                first.borrow_mut().push("x");
+                g
+            })
+            .exp()
+            .register_hook(move |g| {
+                second.borrow_mut().push("exp");
+                g
+            });
+        let _ = y.backward();
+        assert_eq!(order.borrow().as_slice(), &["exp", "x"]);
+    }
+
+    #[test]
+    fn test_hook_on_unused_tensor() {
+        let x = tensor([1.0, 2.0]);
+        let (_, tape) = x
+            .trace()
+            .register_hook(|g| {
+                assert_eq!(g.data(), &[0.0, 0.0]);
+                g
+            })
+            .split_tape();
+        let gradients = tensor(1.0).put_tape(tape).backward();
+        assert_eq!(gradients.ref_gradient(&x), &[0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_hook_without_tape_is_never_called() {
+        let x = tensor([1.0, 2.0]);
+        let y = x.register_hook(|_| panic!("hook called"));
+        assert_eq!(y.data(), &[1.0, 2.0]);
+    }
+}
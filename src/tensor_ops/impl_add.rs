@@ -1,5 +1,5 @@
-use super::utils::binary_map;
-use crate::gradients::{NoneTape, Tape};
+use super::utils::broadcast_binary_map;
+use crate::gradients::Tape;
 use crate::prelude::*;
 
 /// Element wise addition.
@@ -12,16 +12,31 @@ use crate::prelude::*;
 /// let r = add(a, &b); // or `a + &b`
 /// assert_eq!(r.data(), &[[2.0, 3.0, 4.0], [0.0, -1.0, -2.0]]);
 /// ```
-pub fn add<T: Tensor<Dtype = f32>>(lhs: T, rhs: &T::NoTape) -> T {
-    binary_map(lhs, rhs, |x, y| x + y, |_, _| 1.0, |_, _| 1.0)
+pub fn add<L, R>(lhs: L, rhs: &R) -> L::Output
+where
+    L: BroadcastBinary<R>,
+    R: 'static + Tensor<Dtype = f32>,
+{
+    broadcast_binary_map(
+        lhs,
+        rhs,
+        |x, y| x + y,
+        |_, _| 1.0,
+        |_, _| 1.0,
+        |_, _| [0.0; 3],
+    )
 }
 
 macro_rules! binary_ops_impl {
     ($typename:ident, [$($Vs:tt),*]) => {
-impl<$(const $Vs: usize, )* H: Tape> std::ops::Add<&$typename<$($Vs, )* NoneTape>> for $typename<$($Vs, )* H> {
-    type Output = $typename<$($Vs, )* H>;
-    /// Calls [add()] - implements `T<H> + &T<NoneTape>`
-    fn add(self, rhs: &$typename<$($Vs, )* NoneTape>) -> Self::Output {
+impl<$(const $Vs: usize, )* H: Tape, Rhs> std::ops::Add<&Rhs> for $typename<$($Vs, )* H>
+where
+    Self: BroadcastBinary<Rhs>,
+    Rhs: 'static + Tensor<Dtype = f32>,
+{
+    type Output = <Self as BroadcastBinary<Rhs>>::Output;
+    /// Calls [add()] - implements `T<H> + &Rhs<NoneTape>`
+    fn add(self, rhs: &Rhs) -> Self::Output {
         add(self, rhs)
     }
 }
@@ -0,0 +1,69 @@
+use super::utils::binary_map;
+use crate::gradients::Tape;
+use crate::prelude::*;
+
+/// Element wise [four quadrant arctangent](https://en.wikipedia.org/wiki/Atan2) of `lhs / rhs`,
+/// i.e. the angle of the point `(rhs, lhs)`.
+///
+/// **Pytorch equivalent**: `torch.atan2(lhs, rhs)`
+///
+/// The derivatives are `rhs / (lhs^2 + rhs^2)` for `lhs` and `-lhs / (lhs^2 + rhs^2)` for `rhs`.
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// # use std::f32::consts::{FRAC_PI_4, PI};
+/// let y = tensor([1.0, -1.0, 0.0]);
+/// let x = tensor([1.0, 1.0, -1.0]);
+/// let r = y.atan2(&x);
+/// assert_eq!(r.data(), &[FRAC_PI_4, -FRAC_PI_4, PI]);
+/// ```
+pub fn atan2<T: Tensor<Dtype = f32>>(lhs: T, rhs: &T::NoTape) -> T {
+    fn f(y: &f32, x: &f32) -> f32 {
+        y.atan2(*x)
+    }
+    fn dfdy(y: &f32, x: &f32) -> f32 {
+        x / (y.powi(2) + x.powi(2))
+    }
+    fn dfdx(y: &f32, x: &f32) -> f32 {
+        -y / (y.powi(2) + x.powi(2))
+    }
+    binary_map(lhs, rhs, f, dfdy, dfdx)
+}
+
+macro_rules! tensor_impl {
+    ($typename:ident, [$($Vs:tt),*]) => {
+impl<$(const $Vs: usize, )* H: Tape> $typename<$($Vs, )* H> {
+    /// Calls [atan2()] on `self`.
+    pub fn atan2(self, other: &<Self as Tensor>::NoTape) -> Self {
+        atan2(self, other)
+    }
+}
+    };
+}
+
+tensor_impl!(Tensor0D, []);
+tensor_impl!(Tensor1D, [M]);
+tensor_impl!(Tensor2D, [M, N]);
+tensor_impl!(Tensor3D, [M, N, O]);
+tensor_impl!(Tensor4D, [M, N, O, P]);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::assert_close;
+    use std::f32::consts::{FRAC_PI_4, PI};
+
+    #[test]
+    fn test_atan2() {
+        let y = tensor([1.0, -1.0, 0.0, 2.0]);
+        let x = tensor([1.0, 1.0, -1.0, -2.0]);
+
+        let r = atan2(y.trace(), &x);
+        assert_close(r.data(), &[FRAC_PI_4, -FRAC_PI_4, PI, 3.0 * FRAC_PI_4]);
+
+        let g = backward(r.sum());
+        assert_close(g.ref_gradient(&y), &[0.5, 0.5, -1.0, -0.25]);
+        assert_close(g.ref_gradient(&x), &[-0.5, 0.5, 0.0, -0.25]);
+    }
+}
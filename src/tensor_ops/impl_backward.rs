@@ -1,5 +1,5 @@
 use crate::devices::{Cpu, FillElements};
-use crate::gradients::{Gradients, Tape};
+use crate::gradients::{GradientGraph, Gradients, Tape};
 use crate::prelude::*;
 
 /// Runs backprop algorithm with all operations contained in the tape that `t` has.
@@ -15,8 +15,157 @@ pub fn backward(t: Tensor0D<OwnedTape>) -> Gradients {
     tape.0.execute()
 }
 
+/// Same as [backward()], but the gradients can themselves be differentiated, which
+/// enables higher order gradients. See [GradientGraph] for how to use the result, and
+/// which operations are supported.
+///
+/// Example of a gradient penalty:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let x = tensor([0.5, -1.0]);
+/// let w = tensor([2.0, 3.0]);
+/// let y = (x.trace() * &w).tanh().sum();
+/// let mut graph = y.backward_create_graph();
+/// let dx: Tensor1D<2> = graph.gradient(&x);
+/// let penalty = dx.put_tape(graph.into_tape()).square().sum();
+/// let gradients = penalty.backward();
+/// let _: &[f32; 2] = gradients.ref_gradient(&w);
+/// ```
+pub fn backward_create_graph(t: Tensor0D<OwnedTape>) -> GradientGraph {
+    let (t, tape) = t.split_tape();
+    let mut graph: GradientGraph = Default::default();
+    let slot = graph.slot(&t);
+    Cpu::fill(graph.gradients().mut_gradient(&slot), &mut |v| *v = 1.0);
+    tape.0.execute_create_graph(graph)
+}
+
 impl Tensor0D<OwnedTape> {
     pub fn backward(self) -> Gradients {
         backward(self)
     }
+
+    /// Calls [backward_create_graph()].
+    pub fn backward_create_graph(self) -> GradientGraph {
+        backward_create_graph(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assert_tensor_close;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn test_create_graph_matches_backward() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut model: (Linear<3, 4>, Tanh, Linear<4, 2>, Sigmoid) = Default::default();
+        model.reset_params(&mut rng);
+        let x: Tensor2D<5, 3> = TensorCreator::randn(&mut rng);
+
+        let y = model.forward(x.trace()).square().mean::<_, AllAxes>();
+        let expected = y.backward();
+
+        let y = model.forward(x.trace()).square().mean::<_, AllAxes>();
+        let mut graph = y.backward_create_graph();
+        assert_tensor_close!(graph.gradient(&x), expected.ref_gradient(&x));
+        let w: Tensor2D<4, 3> = graph.gradient(&model.0.weight);
+        assert_tensor_close!(w, expected.ref_gradient(&model.0.weight));
+        let b: Tensor1D<2> = graph.gradient(&model.2.bias);
+        assert_tensor_close!(b, expected.ref_gradient(&model.2.bias));
+    }
+
+    #[test]
+    fn test_second_derivatives_of_maps() {
+        let x = tensor([0.5, -1.0, 2.0]);
+        let (a, tape) = x.trace().tanh().split_tape();
+        let y = x.duplicate().put_tape(tape).sin().exp() + &a;
+        let mut graph = y.sum().backward_create_graph();
+        let dx: Tensor1D<3> = graph.gradient(&x);
+        let g = dx.put_tape(graph.into_tape()).sum().backward();
+
+        let expected = x.data().map(|x| {
+            let tanh = -2.0 * x.tanh() * (1.0 - x.tanh().powi(2));
+            let sin_exp = (x.cos().powi(2) - x.sin()) * x.sin().exp();
+            tanh + sin_exp
+        });
+        assert_tensor_close!(g.ref_gradient(&x), expected);
+    }
+
+    #[test]
+    fn test_hessian_vector_product() {
+        // f(x, y) = sum(x^2 * y / 2), so the hessian wrt x is diag(y)
+        let x = tensor([1.0, -2.0]);
+        let y = tensor([3.0, 0.5]);
+        let f = (x.trace().square() * &y / 2.0).sum();
+        let mut graph = f.backward_create_graph();
+        let dx: Tensor1D<2> = graph.gradient(&x);
+        let v = tensor([1.0, 2.0]);
+        let g = (dx.put_tape(graph.into_tape()) * &v).sum().backward();
+        assert_eq!(g.ref_gradient(&x), &[3.0, 1.0]);
+        // d/dy of x * y . v = x * v
+        assert_eq!(g.ref_gradient(&y), &[1.0, -4.0]);
+    }
+
+    #[test]
+    fn test_gradient_penalty_gradcheck() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut model: (Linear<3, 4>, Tanh, Linear<4, 1>) = Default::default();
+        model.reset_params(&mut rng);
+        let x: Tensor1D<3> = TensorCreator::randn(&mut rng);
+        let cfg = GradcheckConfig {
+            eps: 1e-2,
+            ..Default::default()
+        };
+
+        let penalty = |m: &(Linear<3, 4>, Tanh, Linear<4, 1>), x: Tensor1D<3, OwnedTape>| {
+            let x_ = x.duplicate();
+            let mut graph = m.forward(x).sum().backward_create_graph();
+            let dx: Tensor1D<3> = graph.gradient(&x_);
+            (dx.put_tape(graph.into_tape()).square().sum() - 1.0).square()
+        };
+        gradcheck(|x| penalty(&model, x), &x, cfg).unwrap();
+        gradcheck_module(&model, |m| penalty(m, x.trace()), cfg).unwrap();
+    }
+
+    #[test]
+    fn test_create_graph_batched() {
+        let mut rng = StdRng::seed_from_u64(2);
+        let mut model: (Linear<3, 2>, Sigmoid) = Default::default();
+        model.reset_params(&mut rng);
+        let x: Tensor2D<4, 3> = TensorCreator::randn(&mut rng);
+        let cfg = GradcheckConfig {
+            eps: 1e-2,
+            ..Default::default()
+        };
+        gradcheck(
+            |x| {
+                let x_ = x.duplicate();
+                let scale: Tensor2D<4, 2> = tensor([2.0, 3.0]).broadcast();
+                let y = model.forward(x) / &scale;
+                let mut graph = y.sum::<_, AllAxes>().backward_create_graph();
+                let dx: Tensor2D<4, 3> = graph.gradient(&x_);
+                (dx.put_tape(graph.into_tape()) * &x_).mean()
+            },
+            &x,
+            cfg,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_gradient_of_unused_tensor_is_zeros() {
+        let x = tensor([1.0, 2.0]);
+        let unused: Tensor1D<3> = TensorCreator::ones();
+        let mut graph = x.trace().sum().backward_create_graph();
+        assert_eq!(graph.gradient(&unused).data(), &[0.0; 3]);
+        assert_eq!(graph.gradient(&x).data(), &[1.0; 2]);
+    }
+
+    #[test]
+    #[should_panic = "doesn't support higher order gradients"]
+    fn test_create_graph_unsupported_op() {
+        let x = tensor([1.0, 2.0]);
+        let _ = x.trace().max().backward_create_graph();
+    }
 }
@@ -0,0 +1,170 @@
+use crate::arrays::{AllAxes, Axes2, Axes3, Axis, HasArrayType};
+use crate::devices::{AddAccum, CopyAccum, Cpu, Device, DeviceReduce};
+use crate::gradients::{NoneTape, Tape};
+use crate::prelude::*;
+
+/// Enables implicit broadcasting in binary operations like [add()], [mul()], and [maximum()].
+///
+/// Shapes are aligned on their last axes (like numpy), so a lower rank tensor is broadcasted
+/// along the leading axes of the higher rank tensor. For example a `Tensor1D<N>` bias can be
+/// added to a `Tensor2D<B, N>` batch, and a `Tensor0D` can be combined with anything.
+/// Either side can be the lower rank tensor, and the output always has the tape of `Self`.
+///
+/// Gradients are summed over the broadcasted axes, so they have the same shape as the operands.
+///
+/// NOTE: Unlike numpy, axes of size 1 are not broadcast implicitly, since with const generics
+/// there's no way to tell `Tensor2D<1, N>` apart from `Tensor2D<M, N>` when `M = 1`. Use
+/// [BroadcastTo] for those cases.
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let x: Tensor2D<2, 3> = tensor([[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]);
+/// let bias: Tensor1D<3> = tensor([1.0, 0.0, -1.0]);
+/// let r = x + &bias;
+/// assert_eq!(r.data(), &[[2.0, 2.0, 2.0], [5.0, 5.0, 5.0]]);
+/// ```
+pub trait BroadcastBinary<Rhs: HasArrayType>: Tensor<Dtype = f32> {
+    /// The broadcasted shape, with the tape of `Self`.
+    type Output: Tensor<Dtype = f32, Tape = Self::Tape>;
+
+    /// Copies `lhs` into `out`, broadcasting if necessary.
+    fn broadcast_lhs(lhs: &Self::Array, out: &mut <Self::Output as HasArrayType>::Array);
+
+    /// Copies `rhs` into `out`, broadcasting if necessary.
+    fn broadcast_rhs(rhs: &Rhs::Array, out: &mut <Self::Output as HasArrayType>::Array);
+
+    /// Adds `out_grad` into `lhs_grad`, summing over broadcasted axes.
+    fn reduce_lhs(out_grad: &<Self::Output as HasArrayType>::Array, lhs_grad: &mut Self::Array);
+
+    /// Adds `out_grad` into `rhs_grad`, summing over broadcasted axes.
+    fn reduce_rhs(out_grad: &<Self::Output as HasArrayType>::Array, rhs_grad: &mut Rhs::Array);
+}
+
+impl<T: Tensor<Dtype = f32>> BroadcastBinary<T::NoTape> for T {
+    type Output = T;
+    fn broadcast_lhs(lhs: &T::Array, out: &mut T::Array) {
+        out.clone_from(lhs);
+    }
+    fn broadcast_rhs(rhs: &T::Array, out: &mut T::Array) {
+        out.clone_from(rhs);
+    }
+    fn reduce_lhs(out_grad: &T::Array, lhs_grad: &mut T::Array) {
+        T::Device::add(lhs_grad, out_grad);
+    }
+    fn reduce_rhs(out_grad: &T::Array, rhs_grad: &mut T::Array) {
+        T::Device::add(rhs_grad, out_grad);
+    }
+}
+
+macro_rules! impl_broadcast_binary {
+    ($Small:ident, [$($SmallDims:tt),*], $AxesTy:ty, $Big:ident, [$($Dims:tt),*]) => {
+// rhs is broadcasted
+impl<$(const $Dims: usize, )* H: Tape> BroadcastBinary<$Small<$($SmallDims, )* NoneTape>>
+    for $Big<$($Dims, )* H>
+{
+    type Output = Self;
+    fn broadcast_lhs(lhs: &Self::Array, out: &mut Self::Array) {
+        out.clone_from(lhs);
+    }
+    fn broadcast_rhs(rhs: &<$Small<$($SmallDims, )* NoneTape> as HasArrayType>::Array, out: &mut Self::Array) {
+        <Cpu as DeviceReduce<_, $AxesTy>>::broadcast_into::<CopyAccum>(out, rhs);
+    }
+    fn reduce_lhs(out_grad: &Self::Array, lhs_grad: &mut Self::Array) {
+        Cpu::add(lhs_grad, out_grad);
+    }
+    fn reduce_rhs(out_grad: &Self::Array, rhs_grad: &mut <$Small<$($SmallDims, )* NoneTape> as HasArrayType>::Array) {
+        <Cpu as DeviceReduce<_, $AxesTy>>::reduce_into_no_reset::<AddAccum>(rhs_grad, out_grad);
+    }
+}
+
+// lhs is broadcasted
+impl<$(const $Dims: usize, )* H: Tape> BroadcastBinary<$Big<$($Dims, )* NoneTape>>
+    for $Small<$($SmallDims, )* H>
+{
+    type Output = $Big<$($Dims, )* H>;
+    fn broadcast_lhs(lhs: &Self::Array, out: &mut <Self::Output as HasArrayType>::Array) {
+        <Cpu as DeviceReduce<_, $AxesTy>>::broadcast_into::<CopyAccum>(out, lhs);
+    }
+    fn broadcast_rhs(rhs: &<Self::Output as HasArrayType>::Array, out: &mut <Self::Output as HasArrayType>::Array) {
+        out.clone_from(rhs);
+    }
+    fn reduce_lhs(out_grad: &<Self::Output as HasArrayType>::Array, lhs_grad: &mut Self::Array) {
+        <Cpu as DeviceReduce<_, $AxesTy>>::reduce_into_no_reset::<AddAccum>(lhs_grad, out_grad);
+    }
+    fn reduce_rhs(out_grad: &<Self::Output as HasArrayType>::Array, rhs_grad: &mut <Self::Output as HasArrayType>::Array) {
+        Cpu::add(rhs_grad, out_grad);
+    }
+}
+    };
+}
+
+impl_broadcast_binary!(Tensor0D, [], AllAxes, Tensor1D, [M]);
+impl_broadcast_binary!(Tensor0D, [], AllAxes, Tensor2D, [M, N]);
+impl_broadcast_binary!(Tensor0D, [], AllAxes, Tensor3D, [M, N, O]);
+impl_broadcast_binary!(Tensor0D, [], AllAxes, Tensor4D, [M, N, O, P]);
+impl_broadcast_binary!(Tensor1D, [N], Axis<0>, Tensor2D, [M, N]);
+impl_broadcast_binary!(Tensor1D, [O], Axes2<0, 1>, Tensor3D, [M, N, O]);
+impl_broadcast_binary!(Tensor1D, [P], Axes3<0, 1, 2>, Tensor4D, [M, N, O, P]);
+impl_broadcast_binary!(Tensor2D, [N, O], Axis<0>, Tensor3D, [M, N, O]);
+impl_broadcast_binary!(Tensor2D, [O, P], Axes2<0, 1>, Tensor4D, [M, N, O, P]);
+impl_broadcast_binary!(Tensor3D, [N, O, P], Axis<0>, Tensor4D, [M, N, O, P]);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::assert_close;
+
+    #[test]
+    fn test_broadcast_rhs_add() {
+        let a: Tensor2D<2, 3> = tensor([[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]);
+        let b: Tensor1D<3> = tensor([1.0, 0.0, -1.0]);
+        let r = add(a.trace(), &b);
+        assert_eq!(r.data(), &[[2.0, 2.0, 2.0], [5.0, 5.0, 5.0]]);
+        let g = backward(r.exp().mean());
+        assert_close(
+            g.ref_gradient(&a),
+            &[
+                [1.2315094, 1.2315094, 1.2315094],
+                [24.735527, 24.735527, 24.735527],
+            ],
+        );
+        assert_close(g.ref_gradient(&b), &[25.967037, 25.967037, 25.967037]);
+    }
+
+    #[test]
+    fn test_broadcast_lhs_mul() {
+        let a: Tensor1D<2> = tensor([2.0, -1.0]);
+        let b: Tensor3D<2, 2, 2> = tensor([[[1.0, 2.0], [3.0, 4.0]], [[5.0, 6.0], [7.0, 8.0]]]);
+        let r: Tensor3D<2, 2, 2, OwnedTape> = mul(a.trace(), &b);
+        assert_eq!(
+            r.data(),
+            &[[[2.0, -2.0], [6.0, -4.0]], [[10.0, -6.0], [14.0, -8.0]]]
+        );
+        let g = backward(r.sum::<_, AllAxes>());
+        assert_eq!(g.ref_gradient(&a), &[16.0, 20.0]);
+        assert_eq!(g.ref_gradient(&b), &[[[2.0, -1.0]; 2]; 2]);
+    }
+
+    #[test]
+    fn test_broadcast_scalar_sub() {
+        let a: Tensor2D<2, 2> = tensor([[1.0, 2.0], [3.0, 4.0]]);
+        let b: Tensor0D = tensor(1.0);
+        let r = b.trace() - &a;
+        assert_eq!(r.data(), &[[0.0, -1.0], [-2.0, -3.0]]);
+        let g = backward(r.mean());
+        assert_eq!(g.ref_gradient(&b), &1.0);
+        assert_eq!(g.ref_gradient(&a), &[[-0.25; 2]; 2]);
+    }
+
+    #[test]
+    fn test_broadcast_maximum() {
+        let a: Tensor3D<2, 1, 2> = tensor([[[0.0, 3.0]], [[2.0, -1.0]]]);
+        let b: Tensor2D<1, 2> = tensor([[1.0, 1.0]]);
+        let r = a.trace().maximum(&b);
+        assert_eq!(r.data(), &[[[1.0, 3.0]], [[2.0, 1.0]]]);
+        let g = backward(r.sum::<_, AllAxes>());
+        assert_eq!(g.ref_gradient(&a), &[[[0.0, 1.0]], [[1.0, 0.0]]]);
+        assert_eq!(g.ref_gradient(&b), &[[1.0, 1.0]]);
+    }
+}
@@ -1,4 +1,4 @@
-use super::utils::move_tape_and_add_backward_op;
+use super::utils::move_tape_and_add_backward_op_with_graph;
 use crate::arrays::{AllAxes, Axes2, Axes3, Axis, HasArrayType};
 use crate::devices::{AddAccum, CopyAccum, Cpu, DeviceReduce};
 use crate::gradients::Tape;
@@ -53,10 +53,28 @@ impl<$(const $Dims: usize, )* H: Tape> BroadcastTo<$DstTy, $AxesTy> for $SrcTy {
     fn broadcast(self) -> $DstTy {
         let mut result = <$DstTy as Tensor>::NoTape::zeros();
         <Cpu as DeviceReduce<_, $AxesTy>>::broadcast_into::<CopyAccum>(result.mut_data(), self.data());
-        move_tape_and_add_backward_op(self, result, move |t, result, grads| {
-            let (t_grad, result_grad) = grads.mut_and_ref(&t, &result);
-            <Cpu as DeviceReduce<_, $AxesTy>>::reduce_into_no_reset::<AddAccum>(t_grad, result_grad);
-        })
+        move_tape_and_add_backward_op_with_graph(
+            self,
+            result,
+            move |t, result, grads| {
+                let (t_grad, result_grad) = grads.mut_and_ref_or_zeros(&t, &result);
+                <Cpu as DeviceReduce<_, $AxesTy>>::reduce_into_no_reset::<AddAccum>(t_grad, result_grad);
+            },
+            move |t, result, graph| {
+                let t_slot = graph.slot(&t);
+                let result_slot = graph.slot(&result);
+                let (t_grad, result_grad) = graph.gradients().mut_and_ref_or_zeros(&t_slot, &result_slot);
+                <Cpu as DeviceReduce<_, $AxesTy>>::reduce_into_no_reset::<AddAccum>(t_grad, result_grad);
+                graph.add_backward_op(move |grads| {
+                    let t_slot_grad = grads.clone_gradient(&t_slot);
+                    let result_slot_grad = grads.mut_gradient(&result_slot);
+                    <Cpu as DeviceReduce<_, $AxesTy>>::broadcast_into_no_reset::<AddAccum>(
+                        result_slot_grad,
+                        t_slot_grad.as_ref(),
+                    );
+                });
+            },
+        )
     }
 }
     };
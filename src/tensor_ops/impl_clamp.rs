@@ -15,6 +15,7 @@ pub fn clamp<T: Tensor<Dtype = f32>>(t: T, min: T::Dtype, max: T::Dtype) -> T {
         t,
         move |x| x.clamp(min, max),
         move |x| if (min..=max).contains(x) { 1.0 } else { 0.0 },
+        |_| 0.0,
     )
 }
 
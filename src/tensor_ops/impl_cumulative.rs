@@ -0,0 +1,249 @@
+use super::utils::move_tape_and_add_backward_op;
+use crate::arrays::Axis;
+use crate::devices::{Device, DeviceScan};
+use crate::gradients::Tape;
+use crate::prelude::*;
+
+/// A [Tensor] that can be scanned along `Axes`. Enables functions like [cumsum()],
+/// [cumprod()], [flip()] and [roll()].
+///
+/// This trait can't be used directly as it doesn't contain any methods. Instead
+/// it is used by methods to specify the input type must be able to be scanned along `Axes`.
+pub trait Scan<Axes>: Tensor<Dtype = f32> {
+    type DeviceS: DeviceScan<Self::Array, Axes>;
+}
+
+macro_rules! impl_scan {
+    ($TensorTy:ty, $AxisTy:ty, {$($Dims:tt),*}) => {
+impl<$(const $Dims: usize, )* H: Tape> Scan<$AxisTy> for $TensorTy {
+    type DeviceS = <Self as HasDevice>::Device;
+}
+    };
+}
+
+impl_scan!(Tensor1D<M, H>, Axis<0>, { M });
+impl_scan!(Tensor2D<M, N, H>, Axis<0>, {M, N});
+impl_scan!(Tensor2D<M, N, H>, Axis<1>, {M, N});
+impl_scan!(Tensor3D<M, N, O, H>, Axis<0>, {M, N, O});
+impl_scan!(Tensor3D<M, N, O, H>, Axis<1>, {M, N, O});
+impl_scan!(Tensor3D<M, N, O, H>, Axis<2>, {M, N, O});
+impl_scan!(Tensor4D<M, N, O, P, H>, Axis<0>, {M, N, O, P});
+impl_scan!(Tensor4D<M, N, O, P, H>, Axis<1>, {M, N, O, P});
+impl_scan!(Tensor4D<M, N, O, P, H>, Axis<2>, {M, N, O, P});
+impl_scan!(Tensor4D<M, N, O, P, H>, Axis<3>, {M, N, O, P});
+
+/// Cumulative sum of values along `Axes` of `T`.
+///
+/// **Pytorch equivalent**: `t.cumsum(Axes)`
+///
+/// **Related functions**: [cumsum_reversed()], [discounted_cumsum()], [cumprod()]
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let t = tensor([[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]);
+/// let r = t.cumsum::<Axis<1>>();
+/// assert_eq!(r.data(), &[[1.0, 3.0, 6.0], [4.0, 9.0, 15.0]]);
+/// ```
+pub fn cumsum<T: Scan<Axes>, Axes>(t: T) -> T {
+    discounted_cumsum(t, 1.0, false)
+}
+
+/// Cumulative sum of values along `Axes` of `T`, starting from the end of the axis.
+///
+/// **Pytorch equivalent**: `t.flip(Axes).cumsum(Axes).flip(Axes)`
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let t = tensor([1.0, 2.0, 3.0]);
+/// let r = t.cumsum_reversed();
+/// assert_eq!(r.data(), &[6.0, 5.0, 3.0]);
+/// ```
+pub fn cumsum_reversed<T: Scan<Axes>, Axes>(t: T) -> T {
+    discounted_cumsum(t, 1.0, true)
+}
+
+/// Cumulative sum along `Axes` of `T` where the running sum is multiplied by `discount`
+/// at every step: `r[i] = t[i] + discount * r[i - 1]`.
+///
+/// If `reverse` is true the sum starts at the end of the axis: `r[i] = t[i] + discount * r[i + 1]`.
+/// This computes discounted returns from a sequence of rewards, and
+/// generalized advantage estimates from a sequence of td errors (with `discount = gamma * lambda`).
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let rewards = tensor([1.0, 1.0, 1.0]);
+/// let returns = rewards.discounted_cumsum(0.5, true);
+/// assert_eq!(returns.data(), &[1.75, 1.5, 1.0]);
+/// ```
+pub fn discounted_cumsum<T: Scan<Axes>, Axes>(t: T, discount: f32, reverse: bool) -> T {
+    let mut result = T::NoTape::zeros();
+    T::DeviceS::cumsum(result.mut_data(), t.data(), discount, reverse);
+    move_tape_and_add_backward_op(t, result, move |mut t, result, grads| {
+        let (t_grad, result_grad) = grads.mut_and_ref(&t, &result);
+        T::DeviceS::cumsum(t.mut_data(), result_grad, discount, !reverse);
+        T::Device::add(t_grad, t.data());
+    })
+}
+
+/// Cumulative product of values along `Axes` of `T`.
+///
+/// **Pytorch equivalent**: `t.cumprod(Axes)`
+///
+/// The gradient is computed without dividing by `t`, so it is correct
+/// even when `t` contains zeros.
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let t = tensor([[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]);
+/// let r = t.cumprod::<Axis<1>>();
+/// assert_eq!(r.data(), &[[1.0, 2.0, 6.0], [4.0, 20.0, 120.0]]);
+/// ```
+pub fn cumprod<T: Scan<Axes>, Axes>(t: T) -> T {
+    scan_prod(t, false)
+}
+
+/// Cumulative product of values along `Axes` of `T`, starting from the end of the axis.
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let t = tensor([1.0, 2.0, 3.0]);
+/// let r = t.cumprod_reversed();
+/// assert_eq!(r.data(), &[6.0, 6.0, 3.0]);
+/// ```
+pub fn cumprod_reversed<T: Scan<Axes>, Axes>(t: T) -> T {
+    scan_prod(t, true)
+}
+
+fn scan_prod<T: Scan<Axes>, Axes>(t: T, reverse: bool) -> T {
+    let mut result = T::NoTape::zeros();
+    T::DeviceS::cumprod(result.mut_data(), t.data(), reverse);
+    move_tape_and_add_backward_op(t, result, move |t, result, grads| {
+        let (t_grad, result_grad) = grads.mut_and_ref(&t, &result);
+        T::DeviceS::cumprod_backward(t_grad, t.data(), result_grad, reverse);
+    })
+}
+
+macro_rules! tensor_impl {
+    ($typename:ident, [$($Vs:tt),*]) => {
+impl<$(const $Vs: usize, )* H: Tape> $typename<$($Vs, )* H> {
+    /// Calls [cumsum()] on `self` with `Axes`.
+    pub fn cumsum<Axes>(self) -> Self where Self: Scan<Axes> {
+        cumsum(self)
+    }
+    /// Calls [cumsum_reversed()] on `self` with `Axes`.
+    pub fn cumsum_reversed<Axes>(self) -> Self where Self: Scan<Axes> {
+        cumsum_reversed(self)
+    }
+    /// Calls [discounted_cumsum()] on `self` with `Axes`.
+    pub fn discounted_cumsum<Axes>(self, discount: f32, reverse: bool) -> Self where Self: Scan<Axes> {
+        discounted_cumsum(self, discount, reverse)
+    }
+    /// Calls [cumprod()] on `self` with `Axes`.
+    pub fn cumprod<Axes>(self) -> Self where Self: Scan<Axes> {
+        cumprod(self)
+    }
+    /// Calls [cumprod_reversed()] on `self` with `Axes`.
+    pub fn cumprod_reversed<Axes>(self) -> Self where Self: Scan<Axes> {
+        cumprod_reversed(self)
+    }
+}
+    };
+}
+
+tensor_impl!(Tensor1D, [M]);
+tensor_impl!(Tensor2D, [M, N]);
+tensor_impl!(Tensor3D, [M, N, O]);
+tensor_impl!(Tensor4D, [M, N, O, P]);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{assert_close, AssertClose};
+    use rand::thread_rng;
+
+    #[test]
+    fn test_cumsum_1d() {
+        let t = tensor([1.0, 2.0, 3.0]);
+        let r = t.trace().cumsum();
+        assert_eq!(r.data(), &[1.0, 3.0, 6.0]);
+        // NOTE: .exp() to make sure its using result grad properly
+        let g = r.exp().sum().backward();
+        g.ref_gradient(&t)
+            .assert_close(&[426.2326, 423.51434, 403.4288], 1e-3);
+    }
+
+    #[test]
+    fn test_cumsum_2d_axis_0() {
+        let t = tensor([[1.0, 2.0], [3.0, 4.0], [5.0, 6.0]]);
+        let r = t.trace().cumsum::<Axis<0>>();
+        assert_eq!(r.data(), &[[1.0, 2.0], [4.0, 6.0], [9.0, 12.0]]);
+        let g = r.sum::<_, AllAxes>().backward();
+        assert_eq!(g.ref_gradient(&t), &[[3.0; 2], [2.0; 2], [1.0; 2]]);
+    }
+
+    #[test]
+    fn test_cumsum_reversed_2d_axis_1() {
+        let t = tensor([[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]);
+        let r = t.trace().cumsum_reversed::<Axis<1>>();
+        assert_eq!(r.data(), &[[6.0, 5.0, 3.0], [15.0, 11.0, 6.0]]);
+        let g = r.sum::<_, AllAxes>().backward();
+        assert_eq!(g.ref_gradient(&t), &[[1.0, 2.0, 3.0]; 2]);
+    }
+
+    #[test]
+    fn test_discounted_cumsum() {
+        let t = tensor([1.0, 2.0, 4.0]);
+        let r = t.trace().discounted_cumsum(0.5, true);
+        assert_eq!(r.data(), &[3.0, 4.0, 4.0]);
+        let g = r.sum().backward();
+        assert_eq!(g.ref_gradient(&t), &[1.0, 1.5, 1.75]);
+
+        let r = t.trace().discounted_cumsum(0.5, false);
+        assert_eq!(r.data(), &[1.0, 2.5, 5.25]);
+        let g = r.sum().backward();
+        assert_eq!(g.ref_gradient(&t), &[1.75, 1.5, 1.0]);
+    }
+
+    #[test]
+    fn test_cumsum_4d_matches_sum() {
+        let mut rng = thread_rng();
+        let t: Tensor4D<2, 3, 4, 5> = TensorCreator::randn(&mut rng);
+        let r = t.clone().cumsum::<Axis<2>>();
+        let s: Tensor3D<2, 3, 5> = t.sum();
+        let last: Tensor4D<2, 3, 1, 5> = r.select(&[[[3; 1]; 3]; 2]);
+        let last: Tensor3D<2, 3, 5> = last.sum();
+        assert_close(last.data(), s.data());
+    }
+
+    #[test]
+    fn test_cumprod_1d() {
+        let t = tensor([1.0, 2.0, 3.0]);
+        let r = t.trace().cumprod();
+        assert_eq!(r.data(), &[1.0, 2.0, 6.0]);
+        let g = r.sum().backward();
+        assert_eq!(g.ref_gradient(&t), &[9.0, 4.0, 2.0]);
+    }
+
+    #[test]
+    fn test_cumprod_with_zeros() {
+        let t = tensor([[2.0, 0.0, 3.0], [0.0, 0.0, 1.0]]);
+        let r = t.trace().cumprod::<Axis<1>>();
+        assert_eq!(r.data(), &[[2.0, 0.0, 0.0], [0.0, 0.0, 0.0]]);
+        let g = r.sum::<_, AllAxes>().backward();
+        assert_eq!(g.ref_gradient(&t), &[[1.0, 8.0, 0.0], [1.0, 0.0, 0.0]]);
+    }
+
+    #[test]
+    fn test_cumprod_reversed_2d_axis_0() {
+        let t = tensor([[1.0, 2.0], [3.0, 4.0]]);
+        let r = t.trace().cumprod_reversed::<Axis<0>>();
+        assert_eq!(r.data(), &[[3.0, 8.0], [3.0, 4.0]]);
+        let g = r.sum::<_, AllAxes>().backward();
+        assert_eq!(g.ref_gradient(&t), &[[3.0, 4.0], [2.0, 3.0]]);
+    }
+}
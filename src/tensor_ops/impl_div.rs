@@ -1,4 +1,4 @@
-use super::utils::binary_map;
+use super::utils::broadcast_binary_map;
 use crate::gradients::Tape;
 use crate::prelude::*;
 
@@ -12,19 +12,30 @@ use crate::prelude::*;
 /// let r = div(a, &b); // or `a / &b`
 /// assert_eq!(r.data(), &[[1.0, 4.0, 3.0], [-2.0, -2.0, -1.0]]);
 /// ```
-pub fn div<T: Tensor<Dtype = f32>>(lhs: T, rhs: &T::NoTape) -> T {
+pub fn div<L, R>(lhs: L, rhs: &R) -> L::Output
+where
+    L: BroadcastBinary<R>,
+    R: 'static + Tensor<Dtype = f32>,
+{
     fn dfdy(x: &f32, y: &f32) -> f32 {
         (-x) * y.powi(2).recip()
     }
-    binary_map(lhs, rhs, |x, y| x * y.recip(), |_, y| y.recip(), dfdy)
+    fn d2f(x: &f32, y: &f32) -> [f32; 3] {
+        [0.0, -y.powi(2).recip(), 2.0 * x * y.powi(3).recip()]
+    }
+    broadcast_binary_map(lhs, rhs, |x, y| x * y.recip(), |_, y| y.recip(), dfdy, d2f)
 }
 
 macro_rules! binary_ops_impl {
     ($typename:ident, [$($Vs:tt),*]) => {
-impl<$(const $Vs: usize, )* H: Tape> std::ops::Div<&$typename<$($Vs, )* NoneTape>> for $typename<$($Vs, )* H> {
-    type Output = $typename<$($Vs, )* H>;
-    /// Calls [div()] - implements `T<H> / &T<NoneTape>`
-    fn div(self, rhs: &$typename<$($Vs, )* NoneTape>) -> Self::Output {
+impl<$(const $Vs: usize, )* H: Tape, Rhs> std::ops::Div<&Rhs> for $typename<$($Vs, )* H>
+where
+    Self: BroadcastBinary<Rhs>,
+    Rhs: 'static + Tensor<Dtype = f32>,
+{
+    type Output = <Self as BroadcastBinary<Rhs>>::Output;
+    /// Calls [div()] - implements `T<H> / &Rhs<NoneTape>`
+    fn div(self, rhs: &Rhs) -> Self::Output {
         div(self, rhs)
     }
 }
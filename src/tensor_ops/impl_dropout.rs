@@ -57,6 +57,7 @@ pub fn dropout<T: Tensor<Dtype = f32>, R: Rng>(t: T, p: f32, rng: &mut R) -> T {
                     1.0 / (1.0 - p)
                 }
             },
+            |_| 0.0,
         )
     }
 }
@@ -1,4 +1,4 @@
-use super::utils::binary_map;
+use super::utils::broadcast_binary_map;
 use crate::gradients::Tape;
 use crate::prelude::*;
 
@@ -13,7 +13,11 @@ use crate::prelude::*;
 /// let b = tensor([[1.0, 0.5, 1.0], [-2.0, 2.0, -3.5]]);
 /// let r = a.minimum(&b);
 /// assert_eq!(r.data(), &[[1.0, 0.5, 1.0], [-2.0, -2.0, -3.5]]);
-pub fn minimum<T: Tensor<Dtype = f32>>(lhs: T, rhs: &T::NoTape) -> T {
+pub fn minimum<L, R>(lhs: L, rhs: &R) -> L::Output
+where
+    L: BroadcastBinary<R>,
+    R: 'static + Tensor<Dtype = f32>,
+{
     fn f(x: &f32, y: &f32) -> f32 {
         x.min(*y)
     }
@@ -36,14 +40,18 @@ pub fn minimum<T: Tensor<Dtype = f32>>(lhs: T, rhs: &T::NoTape) -> T {
             0.5
         }
     }
-    binary_map(lhs, rhs, f, dfdx, dfdy)
+    broadcast_binary_map(lhs, rhs, f, dfdx, dfdy, |_, _| [0.0; 3])
 }
 
 macro_rules! tensor_impl {
     ($typename:ident, [$($Vs:tt),*]) => {
 impl<$(const $Vs: usize, )* H: Tape> $typename<$($Vs, )* H> {
     /// Calls [minimum()] on `self`.
-    pub fn minimum(self, other: &<Self as Tensor>::NoTape) -> Self {
+    pub fn minimum<Rhs>(self, other: &Rhs) -> <Self as BroadcastBinary<Rhs>>::Output
+    where
+        Self: BroadcastBinary<Rhs>,
+        Rhs: 'static + Tensor<Dtype = f32>,
+    {
         minimum(self, other)
     }
 }
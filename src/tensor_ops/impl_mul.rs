@@ -1,4 +1,4 @@
-use super::utils::binary_map;
+use super::utils::broadcast_binary_map;
 use crate::gradients::Tape;
 use crate::prelude::*;
 
@@ -12,16 +12,31 @@ use crate::prelude::*;
 /// let r = mul(a, &b); // or `a * &b`
 /// assert_eq!(r.data(), &[[1.0, 2.0, 3.0], [-1.0, -2.0, -3.0]]);
 /// ```
-pub fn mul<T: Tensor<Dtype = f32>>(lhs: T, rhs: &T::NoTape) -> T {
-    binary_map(lhs, rhs, |x, y| x * y, |_, y| *y, |x, _| *x)
+pub fn mul<L, R>(lhs: L, rhs: &R) -> L::Output
+where
+    L: BroadcastBinary<R>,
+    R: 'static + Tensor<Dtype = f32>,
+{
+    broadcast_binary_map(
+        lhs,
+        rhs,
+        |x, y| x * y,
+        |_, y| *y,
+        |x, _| *x,
+        |_, _| [0.0, 1.0, 0.0],
+    )
 }
 
 macro_rules! binary_ops_impl {
     ($typename:ident, [$($Vs:tt),*]) => {
-impl<$(const $Vs: usize, )* H: Tape> std::ops::Mul<&$typename<$($Vs, )* NoneTape>> for $typename<$($Vs, )* H> {
-    type Output = $typename<$($Vs, )* H>;
-    /// Calls [mul()] - implements `T<H> * &T<NoneTape>`
-    fn mul(self, rhs: &$typename<$($Vs, )* NoneTape>) -> Self::Output {
+impl<$(const $Vs: usize, )* H: Tape, Rhs> std::ops::Mul<&Rhs> for $typename<$($Vs, )* H>
+where
+    Self: BroadcastBinary<Rhs>,
+    Rhs: 'static + Tensor<Dtype = f32>,
+{
+    type Output = <Self as BroadcastBinary<Rhs>>::Output;
+    /// Calls [mul()] - implements `T<H> * &Rhs<NoneTape>`
+    fn mul(self, rhs: &Rhs) -> Self::Output {
         mul(self, rhs)
     }
 }
@@ -17,6 +17,7 @@ pub fn nans_to<T: Tensor<Dtype = f32>>(t: T, value: T::Dtype) -> T {
         t,
         move |x| if x.is_nan() { value } else { *x },
         move |x| if x.is_nan() { 0.0 } else { 1.0 },
+        |_| 0.0,
     )
 }
 
@@ -0,0 +1,217 @@
+use super::utils::move_tape_and_add_backward_op;
+use crate::devices::{AddAccum, CopyAccum, Device, DeviceReduce, ForEachElement, MulAccum};
+use crate::gradients::Tape;
+use crate::prelude::*;
+
+/// Reduces `Axes` of `T` by computing the p-norm `(sum |t|^p)^(1/p)` of all values in those axes.
+/// `p = f32::INFINITY` computes the max norm `max |t|`.
+///
+/// **Pytorch equivalent**: `t.norm(p, Axes)`
+///
+/// **Related functions**: [l2_normalize()], [cosine_similarity()], [abs()], [sum()]
+///
+/// The gradient is `0.0` where the norm is `0.0`, instead of `NaN`.
+///
+/// Panics if `p` is not positive.
+///
+/// Examples:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let t = tensor([[3.0, -4.0], [1.0, 1.0]]);
+/// let r = t.clone().norm::<_, Axis<1>>(1.0);
+/// assert_eq!(r.data(), &[7.0, 2.0]);
+/// let r = t.clone().norm::<_, Axis<1>>(2.0);
+/// assert_eq!(r.data(), &[5.0, 2.0f32.sqrt()]);
+/// let r: Tensor0D = t.norm(f32::INFINITY);
+/// assert_eq!(r.data(), &4.0);
+/// ```
+pub fn norm<T: Reduce<Axes>, Axes>(mut t: T, p: f32) -> T::Reduced {
+    assert!(p > 0.0, "p must be positive, found {}", p);
+    if p == f32::INFINITY {
+        return max(abs(t));
+    }
+    if p == 1.0 {
+        return sum(abs(t));
+    }
+
+    let mut result = <T::Reduced as Tensor>::NoTape::zeros();
+    let mut buf = T::Device::map(t.data(), move |x| x.abs().powf(p));
+    T::DeviceR::reduce_into::<AddAccum>(result.mut_data(), buf.as_ref());
+    <T::Reduced as HasDevice>::Device::foreach_m(result.mut_data(), &mut |r| *r = r.powf(1.0 / p));
+
+    // store derivative in t: sign(t) * (|t| / norm)^(p - 1)
+    T::DeviceR::broadcast_into::<CopyAccum>(buf.as_mut(), result.data());
+    T::Device::foreach_mr(t.mut_data(), buf.as_ref(), &mut |x, n| {
+        *x = if *x == 0.0 || *n == 0.0 {
+            0.0
+        } else {
+            x.signum() * (x.abs() / n).powf(p - 1.0)
+        };
+    });
+
+    move_tape_and_add_backward_op(t, result, move |mut t, result, grads| {
+        let (t_grad, result_grad) = grads.mut_and_ref(&t, &result);
+        T::DeviceR::broadcast_into_no_reset::<MulAccum>(t.mut_data(), result_grad);
+        T::Device::add(t_grad, t.data());
+    })
+}
+
+/// Normalizes `t` to have an L2 norm of `1.0` along `Axes` of `T`. Computes `t / max(||t||, epsilon)`,
+/// so vectors with a norm smaller than `epsilon` are not blown up.
+///
+/// **Pytorch equivalent**: `torch.nn.functional.normalize(t, p=2, dim=Axes, eps=epsilon)`
+///
+/// **Related functions**: [norm()], [cosine_similarity()], [normalize()]
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let t = tensor([[3.0, 4.0], [0.0, 0.0]]);
+/// let r = t.l2_normalize::<Axis<1>>(1e-12);
+/// assert_eq!(r.data(), &[[0.6, 0.8], [0.0, 0.0]]);
+/// ```
+pub fn l2_normalize<T: Reduce<Axes>, Axes>(t: T, epsilon: f32) -> T {
+    let (t, tape) = t.split_tape();
+    let (n, tape) = clamp(
+        norm(t.duplicate().put_tape(tape), 2.0),
+        epsilon,
+        f32::INFINITY,
+    )
+    .broadcast()
+    .split_tape();
+    div(t.put_tape(tape), &n)
+}
+
+/// Reduces `Axes` of `lhs` and `rhs` by computing the cosine similarity between them.
+/// Both sides are normalized with [l2_normalize()] using `epsilon` first, so this computes
+/// `sum(lhs * rhs) / (max(||lhs||, epsilon) * max(||rhs||, epsilon))`.
+///
+/// **Pytorch equivalent**: `torch.nn.functional.cosine_similarity(lhs, rhs, dim=Axes, eps=epsilon)`
+///
+/// **Related functions**: [norm()], [l2_normalize()]
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let a = tensor([[1.0, 0.0], [0.0, 1.0]]);
+/// let b = tensor([[0.0, 2.0], [0.0, -3.0]]);
+/// let r = a.cosine_similarity::<_, Axis<1>>(&b, 1e-8);
+/// assert_eq!(r.data(), &[0.0, -1.0]);
+/// ```
+pub fn cosine_similarity<T: Reduce<Axes>, Axes>(
+    lhs: T,
+    rhs: &T::NoTape,
+    epsilon: f32,
+) -> T::Reduced {
+    let (lhs, tape) = l2_normalize(lhs, epsilon).split_tape();
+    let rhs = l2_normalize(rhs.duplicate().put_tape(tape), epsilon);
+    sum(mul(rhs, &lhs))
+}
+
+macro_rules! tensor_impl {
+    ($typename:ident, [$($Vs:tt),*]) => {
+impl<$(const $Vs: usize, )* H: Tape> $typename<$($Vs, )* H> {
+    /// Calls [norm()]
+    pub fn norm<T, Axes>(self, p: f32) -> T where Self: ReduceTo<T, Axes> {
+        norm(self, p)
+    }
+    /// Calls [l2_normalize()]
+    pub fn l2_normalize<Axes>(self, epsilon: f32) -> Self where Self: Reduce<Axes> {
+        l2_normalize(self, epsilon)
+    }
+    /// Calls [cosine_similarity()]
+    pub fn cosine_similarity<T, Axes>(self, rhs: &<Self as Tensor>::NoTape, epsilon: f32) -> T
+    where
+        Self: ReduceTo<T, Axes>,
+    {
+        cosine_similarity(self, rhs, epsilon)
+    }
+}
+    };
+}
+
+tensor_impl!(Tensor1D, [M]);
+tensor_impl!(Tensor2D, [M, N]);
+tensor_impl!(Tensor3D, [M, N, O]);
+tensor_impl!(Tensor4D, [M, N, O, P]);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arrays::AllAxes;
+    use crate::tests::assert_close;
+
+    #[test]
+    fn test_l2_norm_1d() {
+        let t = tensor([3.0, 0.0, -4.0]);
+        let r = t.trace().norm(2.0);
+        assert_eq!(r.data(), &5.0);
+        let g = r.backward();
+        assert_eq!(g.ref_gradient(&t), &[0.6, 0.0, -0.8]);
+    }
+
+    #[test]
+    fn test_p_norm_2d() {
+        let t = tensor([[1.0, -2.0], [0.0, 3.0]]);
+        let r: Tensor1D<2, OwnedTape> = t.trace().norm::<_, Axis<1>>(3.0);
+        assert_close(r.data(), &[9.0f32.cbrt(), 3.0]);
+        let g = r.sum().backward();
+        let n = 9.0f32.cbrt();
+        assert_close(
+            g.ref_gradient(&t),
+            &[[(1.0 / n) * (1.0 / n), -(2.0 / n) * (2.0 / n)], [0.0, 1.0]],
+        );
+    }
+
+    #[test]
+    fn test_l1_and_inf_norm() {
+        let t = tensor([[1.0, -2.0], [0.0, 3.0]]);
+        let r: Tensor1D<2, OwnedTape> = t.trace().norm::<_, Axis<1>>(1.0);
+        assert_eq!(r.data(), &[3.0, 3.0]);
+        let g = r.sum().backward();
+        assert_eq!(g.ref_gradient(&t), &[[1.0, -1.0], [0.0, 1.0]]);
+
+        let r: Tensor0D<OwnedTape> = t.trace().norm(f32::INFINITY);
+        assert_eq!(r.data(), &3.0);
+        let g = r.backward();
+        assert_eq!(g.ref_gradient(&t), &[[0.0, 0.0], [0.0, 1.0]]);
+    }
+
+    #[test]
+    fn test_norm_of_zeros_has_zero_grad() {
+        let t: Tensor2D<2, 3> = TensorCreator::zeros();
+        let r: Tensor0D<OwnedTape> = t.trace().norm(2.0);
+        assert_eq!(r.data(), &0.0);
+        let g = r.backward();
+        assert_eq!(g.ref_gradient(&t), &[[0.0; 3]; 2]);
+    }
+
+    #[test]
+    #[should_panic = "p must be positive, found 0"]
+    fn test_norm_zero_p() {
+        let _: Tensor0D = tensor([1.0, 2.0]).norm(0.0);
+    }
+
+    #[test]
+    fn test_l2_normalize() {
+        let t = tensor([[3.0, 4.0], [0.0, 0.0]]);
+        let r = t.trace().l2_normalize::<Axis<1>>(1e-12);
+        assert_eq!(r.data(), &[[0.6, 0.8], [0.0, 0.0]]);
+        let g = mul(r, &tensor([[1.0, 0.0], [1.0, 0.0]]))
+            .sum::<_, AllAxes>()
+            .backward();
+        // d(x / ||x||) = (I - u u^T) / ||x||
+        assert_close(g.ref_gradient(&t), &[[0.128, -0.096], [1e12, 0.0]]);
+    }
+
+    #[test]
+    fn test_cosine_similarity() {
+        let a = tensor([[1.0, 0.0], [1.0, 1.0]]);
+        let b = tensor([[0.0, 2.0], [2.0, 2.0]]);
+        let r: Tensor1D<2, OwnedTape> = a.trace().cosine_similarity::<_, Axis<1>>(&b, 1e-8);
+        assert_close(r.data(), &[0.0, 1.0]);
+        let g = r.sum().backward();
+        assert_close(g.ref_gradient(&a), &[[0.0, 1.0], [0.0, 0.0]]);
+        assert_close(g.ref_gradient(&b), &[[0.5, 0.0], [0.0, 0.0]]);
+    }
+}
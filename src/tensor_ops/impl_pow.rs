@@ -15,7 +15,12 @@ use crate::prelude::*;
 /// let r = t.powf(-3.2);
 /// ```
 pub fn powf<T: Tensor<Dtype = f32>>(t: T, i: T::Dtype) -> T {
-    map(t, move |x| x.powf(i), move |x| i * x.powf(i - 1.0))
+    map(
+        t,
+        move |x| x.powf(i),
+        move |x| i * x.powf(i - 1.0),
+        move |x| i * (i - 1.0) * x.powf(i - 2.0),
+    )
 }
 
 /// Raises to an integer power. `t^i`.
@@ -31,7 +36,12 @@ pub fn powf<T: Tensor<Dtype = f32>>(t: T, i: T::Dtype) -> T {
 /// let r = t.powi(3);
 /// ```
 pub fn powi<T: Tensor<Dtype = f32>>(t: T, i: i32) -> T {
-    map(t, move |x| x.powi(i), move |x| i as f32 * x.powi(i - 1))
+    map(
+        t,
+        move |x| x.powi(i),
+        move |x| i as f32 * x.powi(i - 1),
+        move |x| (i * (i - 1)) as f32 * x.powi(i - 2),
+    )
 }
 
 macro_rules! tensor_impl {
@@ -0,0 +1,156 @@
+use super::utils::move_tape_and_add_backward_op;
+use crate::devices::{
+    AddAccum, AllocateZeros, CopyAccum, Device, DeviceReduce, ForEachElement, MulAccum,
+};
+use crate::gradients::Tape;
+use crate::prelude::*;
+
+/// Multiplies values along axes `Axes` of `T`.
+///
+/// **Pytorch equivalent**: `t.prod(Axes)`
+///
+/// The gradient is the product of all the other values along `Axes`. When a reduced
+/// slice has no zeros this is computed as `prod / t`; when it has exactly one zero,
+/// that element's gradient is the product of the non zero values and the others are
+/// `0.0`; with more zeros every gradient is `0.0`.
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let t = tensor([[1.0, 2.0, 3.0], [-1.0, -2.0, -3.0]]);
+/// let r: Tensor1D<2> = t.prod();
+/// assert_eq!(r.data(), &[6.0, -6.0]);
+/// ```
+///
+/// Reducing 2 axes:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// # let t = tensor([[1.0, 2.0, 3.0], [-1.0, -2.0, -3.0]]);
+/// let r: Tensor0D = t.prod();
+/// assert_eq!(r.data(), &-36.0);
+/// ```
+pub fn prod<T: Reduce<Axes>, Axes>(mut t: T) -> T::Reduced {
+    let mut result = <T::Reduced as Tensor>::NoTape::zeros();
+    T::DeviceR::reduce_into::<MulAccum>(result.mut_data(), t.data());
+
+    // product of the non zero values, and the number of zeros, for each reduced value
+    let nonzero: Box<T::Array> = T::Device::map(t.data(), |x| if x == &0.0 { 1.0 } else { *x });
+    let nonzero_prod = T::DeviceR::reduce::<MulAccum>(nonzero.as_ref());
+    let is_zero: Box<T::Array> = T::Device::map(t.data(), |x| if x == &0.0 { 1.0 } else { 0.0 });
+    let num_zeros = T::DeviceR::reduce::<AddAccum>(is_zero.as_ref());
+
+    let mut prods: Box<T::Array> = T::Device::zeros();
+    T::DeviceR::broadcast_into::<CopyAccum>(prods.as_mut(), nonzero_prod.as_ref());
+    let mut zeros: Box<T::Array> = T::Device::zeros();
+    T::DeviceR::broadcast_into::<CopyAccum>(zeros.as_mut(), num_zeros.as_ref());
+
+    // store derivative in t
+    T::Device::foreach_mrr(
+        t.mut_data(),
+        prods.as_ref(),
+        zeros.as_ref(),
+        &mut |x, p, z| {
+            *x = if z == &0.0 {
+                p / *x
+            } else if z == &1.0 && x == &0.0 {
+                *p
+            } else {
+                0.0
+            };
+        },
+    );
+
+    move_tape_and_add_backward_op(t, result, move |mut t, result, grads| {
+        let (t_grad, result_grad) = grads.mut_and_ref(&t, &result);
+        T::DeviceR::broadcast_into_no_reset::<MulAccum>(t.mut_data(), result_grad);
+        T::Device::add(t_grad, t.data());
+    })
+}
+
+macro_rules! prod_axis_impl {
+    ($typename:ident, [$($Vs:tt),*]) => {
+impl<$(const $Vs: usize, )* H: Tape> $typename<$($Vs, )* H> {
+    /// Calls [prod()].
+    pub fn prod<T, Axes>(self) -> T where Self: ReduceTo<T, Axes> {
+        prod(self)
+    }
+}
+    };
+}
+
+prod_axis_impl!(Tensor0D, []);
+prod_axis_impl!(Tensor1D, [M]);
+prod_axis_impl!(Tensor2D, [M, N]);
+prod_axis_impl!(Tensor3D, [M, N, O]);
+prod_axis_impl!(Tensor4D, [M, N, O, P]);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::AssertClose;
+    use rand::thread_rng;
+
+    #[test]
+    fn test_valids_prod_axis() {
+        let _: Tensor0D = Tensor1D::<5>::zeros().prod();
+
+        let _: Tensor1D<3> = Tensor2D::<5, 3>::zeros().prod();
+        let _: Tensor1D<5> = Tensor2D::<5, 3>::zeros().prod();
+
+        let _: Tensor2D<5, 3> = Tensor3D::<7, 5, 3>::zeros().prod();
+        let _: Tensor2D<7, 3> = Tensor3D::<7, 5, 3>::zeros().prod();
+        let _: Tensor2D<7, 5> = Tensor3D::<7, 5, 3>::zeros().prod();
+
+        let _: Tensor3D<9, 7, 5> = Tensor4D::<9, 7, 5, 3>::zeros().prod();
+        let _: Tensor0D = Tensor4D::<9, 7, 5, 3>::zeros().prod();
+    }
+
+    #[test]
+    fn test_prod_1d() {
+        let t = tensor([1.0, 2.0, 3.0]);
+        let r: Tensor0D<OwnedTape> = t.trace().prod();
+        assert_eq!(r.data(), &6.0);
+        let g = r.backward();
+        assert_eq!(g.ref_gradient(&t), &[6.0, 3.0, 2.0]);
+    }
+
+    #[test]
+    fn test_prod_axis_1_2d() {
+        let t = tensor([[1.0, 2.0, 3.0], [-1.0, 0.5, 4.0]]);
+        let r = t.trace().prod::<_, Axis<1>>();
+        assert_eq!(r.data(), &[6.0, -2.0]);
+        // NOTE: .exp() to make sure its using result grad properly
+        let g = r.exp().sum().backward();
+        g.ref_gradient(&t).assert_close(
+            &[
+                [2420.5737, 1210.2869, 806.8579],
+                [0.270_670_56, -0.541_341_1, -0.067_667_64],
+            ],
+            1e-3,
+        );
+    }
+
+    #[test]
+    fn test_prod_with_zeros() {
+        let t = tensor([[1.0, 0.0, 3.0], [0.0, 2.0, 0.0], [1.0, 2.0, 3.0]]);
+        let r = t.trace().prod::<_, Axis<1>>();
+        assert_eq!(r.data(), &[0.0, 0.0, 6.0]);
+        let g = r.sum().backward();
+        assert_eq!(
+            g.ref_gradient(&t),
+            &[[0.0, 3.0, 0.0], [0.0, 0.0, 0.0], [6.0, 3.0, 2.0]]
+        );
+    }
+
+    #[test]
+    fn test_prod_axes_3d_to_1d() {
+        let mut rng = thread_rng();
+        let t: Tensor3D<2, 3, 4> = TensorCreator::randn(&mut rng);
+        let r: Tensor1D<3, _> = t.trace().prod::<_, Axes2<0, 2>>();
+        let r2: Tensor1D<3, _> = t.trace().prod::<_, Axis<0>>().prod::<_, Axis<1>>();
+        r.data().assert_close(r2.data(), 1e-5);
+        let g = r.sum().backward();
+        let g2 = r2.sum().backward();
+        g.ref_gradient(&t).assert_close(g2.ref_gradient(&t), 1e-5);
+    }
+}
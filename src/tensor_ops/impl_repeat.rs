@@ -0,0 +1,254 @@
+use super::utils::move_tape_and_add_backward_op;
+use crate::arrays::{Axis, HasArrayType};
+use crate::devices::DeviceScan;
+use crate::gradients::Tape;
+use crate::prelude::*;
+
+/// A [Tensor] that can be repeated along `Axes` to produce `T`, where `T` has the same
+/// shape as `Self` except along `Axes`. Enables [tile()] and [repeat()].
+///
+/// The size of `Axes` in `T` must be a multiple of the size of `Axes` in `Self`,
+/// which is checked at runtime.
+///
+/// This trait can't be used directly as it doesn't contain any methods. Instead
+/// it is used by methods to specify the input type must be able to be repeated into `T`.
+pub trait RepeatTo<T: HasArrayType<Dtype = f32>, Axes>: Tensor<Dtype = f32> {
+    type DeviceR: DeviceScan<Self::Array, Axes> + DeviceScan<T::Array, Axes>;
+}
+
+macro_rules! impl_repeat {
+    ($SrcTy:ty, $DstTy:ty, $AxisTy:ty, {$($Dims:tt),*}) => {
+impl<$(const $Dims: usize, )* H: Tape> RepeatTo<$DstTy, $AxisTy> for $SrcTy {
+    type DeviceR = <Self as HasDevice>::Device;
+}
+    };
+}
+
+impl_repeat!(Tensor1D<M, H>, Tensor1D<K, H>, Axis<0>, {M, K});
+impl_repeat!(Tensor2D<M, N, H>, Tensor2D<K, N, H>, Axis<0>, {M, N, K});
+impl_repeat!(Tensor2D<M, N, H>, Tensor2D<M, K, H>, Axis<1>, {M, N, K});
+impl_repeat!(Tensor3D<M, N, O, H>, Tensor3D<K, N, O, H>, Axis<0>, {M, N, O, K});
+impl_repeat!(Tensor3D<M, N, O, H>, Tensor3D<M, K, O, H>, Axis<1>, {M, N, O, K});
+impl_repeat!(Tensor3D<M, N, O, H>, Tensor3D<M, N, K, H>, Axis<2>, {M, N, O, K});
+impl_repeat!(Tensor4D<M, N, O, P, H>, Tensor4D<K, N, O, P, H>, Axis<0>, {M, N, O, P, K});
+impl_repeat!(Tensor4D<M, N, O, P, H>, Tensor4D<M, K, O, P, H>, Axis<1>, {M, N, O, P, K});
+impl_repeat!(Tensor4D<M, N, O, P, H>, Tensor4D<M, N, K, P, H>, Axis<2>, {M, N, O, P, K});
+impl_repeat!(Tensor4D<M, N, O, P, H>, Tensor4D<M, N, O, K, H>, Axis<3>, {M, N, O, P, K});
+
+/// Copies all of `T` multiple times along `Axes`, concatenating the copies into `R`.
+///
+/// **Pytorch equivalent**: `t.repeat(...)`, **numpy equivalent**: `np.tile(t, ...)`
+///
+/// **Related functions**: [repeat()], [BroadcastTo]
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let t = tensor([[1.0, 2.0], [3.0, 4.0]]);
+/// let r: Tensor2D<2, 4> = t.tile::<_, Axis<1>>();
+/// assert_eq!(r.data(), &[[1.0, 2.0, 1.0, 2.0], [3.0, 4.0, 3.0, 4.0]]);
+/// ```
+pub fn tile<T, R, Axes>(t: T) -> R
+where
+    T: RepeatTo<R, Axes>,
+    R: Tensor<Dtype = f32, Tape = T::Tape>,
+{
+    let size = repeat_sizes::<T, R, Axes>().0;
+    gather::<T::DeviceR, T, R, Axes, _>(t, move |j| j % size)
+}
+
+/// Copies each element of `T` multiple times along `Axes`, so copies of the same
+/// element are next to each other in `R`.
+///
+/// **Pytorch equivalent**: `t.repeat_interleave(n, dim)`, **numpy equivalent**: `np.repeat(t, n, axis)`
+///
+/// **Related functions**: [tile()], [BroadcastTo]
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let t = tensor([[1.0, 2.0], [3.0, 4.0]]);
+/// let r: Tensor2D<2, 4> = t.repeat::<_, Axis<1>>();
+/// assert_eq!(r.data(), &[[1.0, 1.0, 2.0, 2.0], [3.0, 3.0, 4.0, 4.0]]);
+/// ```
+pub fn repeat<T, R, Axes>(t: T) -> R
+where
+    T: RepeatTo<R, Axes>,
+    R: Tensor<Dtype = f32, Tape = T::Tape>,
+{
+    let (size, out_size) = repeat_sizes::<T, R, Axes>();
+    let n = out_size / size;
+    gather::<T::DeviceR, T, R, Axes, _>(t, move |j| j / n)
+}
+
+/// Reverses the order of elements along `Axes` of `T`.
+///
+/// **Pytorch equivalent**: `t.flip(Axes)`
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let t = tensor([[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]);
+/// let r = t.flip::<Axis<1>>();
+/// assert_eq!(r.data(), &[[3.0, 2.0, 1.0], [6.0, 5.0, 4.0]]);
+/// ```
+pub fn flip<T: Scan<Axes>, Axes>(t: T) -> T {
+    let size = <T::DeviceS as DeviceScan<T::Array, Axes>>::SIZE;
+    gather::<T::DeviceS, T, T, Axes, _>(t, move |j| size - 1 - j)
+}
+
+/// Circularly shifts the elements along `Axes` of `T` by `shift`, so element `i`
+/// moves to `i + shift`. Elements shifted past the end wrap around to the start.
+/// Negative `shift` moves elements towards the start.
+///
+/// **Pytorch equivalent**: `t.roll(shift, Axes)`
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let t = tensor([1.0, 2.0, 3.0, 4.0]);
+/// assert_eq!(t.clone().roll(1).data(), &[4.0, 1.0, 2.0, 3.0]);
+/// assert_eq!(t.roll(-1).data(), &[2.0, 3.0, 4.0, 1.0]);
+/// ```
+pub fn roll<T: Scan<Axes>, Axes>(t: T, shift: isize) -> T {
+    let size = <T::DeviceS as DeviceScan<T::Array, Axes>>::SIZE;
+    let shift = shift.rem_euclid(size.max(1) as isize) as usize;
+    gather::<T::DeviceS, T, T, Axes, _>(t, move |j| (j + size - shift) % size)
+}
+
+/// The sizes of `Axes` in `T` and `R`. Panics if the size in `R` is not a multiple of the size in `T`.
+fn repeat_sizes<T: RepeatTo<R, Axes>, R: HasArrayType<Dtype = f32>, Axes>() -> (usize, usize) {
+    let size = <T::DeviceR as DeviceScan<T::Array, Axes>>::SIZE;
+    let out_size = <T::DeviceR as DeviceScan<R::Array, Axes>>::SIZE;
+    assert!(
+        size > 0 && out_size % size == 0,
+        "Can't repeat an axis of size {} into an axis of size {}",
+        size,
+        out_size
+    );
+    (size, out_size)
+}
+
+/// Builds `R` from `T` by gathering along `Axes`: `r[.., j, ..] = t[.., idx(j), ..]`.
+fn gather<D, T, R, Axes, F>(t: T, idx: F) -> R
+where
+    D: DeviceScan<T::Array, Axes> + DeviceScan<R::Array, Axes>,
+    T: Tensor<Dtype = f32>,
+    R: Tensor<Dtype = f32, Tape = T::Tape>,
+    F: 'static + Copy + Fn(usize) -> usize,
+{
+    let mut result = R::NoTape::zeros();
+    <D as DeviceScan<T::Array, Axes>>::gather_add(result.mut_data(), t.data(), idx);
+    move_tape_and_add_backward_op(t, result, move |t, result, grads| {
+        let (t_grad, result_grad) = grads.mut_and_ref(&t, &result);
+        <D as DeviceScan<T::Array, Axes>>::gather_backward(t_grad, result_grad, idx);
+    })
+}
+
+macro_rules! tensor_impl {
+    ($typename:ident, [$($Vs:tt),*]) => {
+impl<$(const $Vs: usize, )* H: Tape> $typename<$($Vs, )* H> {
+    /// Calls [tile()] on `self` with `Axes`.
+    pub fn tile<R, Axes>(self) -> R
+    where
+        Self: RepeatTo<R, Axes>,
+        R: Tensor<Dtype = f32, Tape = <Self as Tensor>::Tape>,
+    {
+        tile(self)
+    }
+    /// Calls [repeat()] on `self` with `Axes`.
+    pub fn repeat<R, Axes>(self) -> R
+    where
+        Self: RepeatTo<R, Axes>,
+        R: Tensor<Dtype = f32, Tape = <Self as Tensor>::Tape>,
+    {
+        repeat(self)
+    }
+    /// Calls [flip()] on `self` with `Axes`.
+    pub fn flip<Axes>(self) -> Self where Self: Scan<Axes> {
+        flip(self)
+    }
+    /// Calls [roll()] on `self` with `Axes`.
+    pub fn roll<Axes>(self, shift: isize) -> Self where Self: Scan<Axes> {
+        roll(self, shift)
+    }
+}
+    };
+}
+
+tensor_impl!(Tensor1D, [M]);
+tensor_impl!(Tensor2D, [M, N]);
+tensor_impl!(Tensor3D, [M, N, O]);
+tensor_impl!(Tensor4D, [M, N, O, P]);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arrays::AllAxes;
+
+    #[test]
+    fn test_tile_1d() {
+        let t = tensor([1.0, 2.0]);
+        let r: Tensor1D<6, OwnedTape> = t.trace().tile();
+        assert_eq!(r.data(), &[1.0, 2.0, 1.0, 2.0, 1.0, 2.0]);
+        let g = mul(r, &tensor([1.0, 2.0, 3.0, 4.0, 5.0, 6.0]))
+            .sum()
+            .backward();
+        assert_eq!(g.ref_gradient(&t), &[9.0, 12.0]);
+    }
+
+    #[test]
+    fn test_repeat_3d_axis_1() {
+        let t = tensor([[[1.0, 2.0]], [[3.0, 4.0]]]);
+        let r: Tensor3D<2, 3, 2, OwnedTape> = t.trace().repeat::<_, Axis<1>>();
+        assert_eq!(r.data(), &[[[1.0, 2.0]; 3], [[3.0, 4.0]; 3]]);
+        let g = r.sum::<_, AllAxes>().backward();
+        assert_eq!(g.ref_gradient(&t), &[[[3.0; 2]], [[3.0; 2]]]);
+    }
+
+    #[test]
+    fn test_repeat_interleaves() {
+        let t = tensor([[1.0, 2.0], [3.0, 4.0]]);
+        let r: Tensor2D<4, 2, OwnedTape> = t.trace().repeat::<_, Axis<0>>();
+        assert_eq!(r.data(), &[[1.0, 2.0], [1.0, 2.0], [3.0, 4.0], [3.0, 4.0]]);
+        let w = tensor([[1.0, 1.0], [2.0, 2.0], [3.0, 3.0], [4.0, 4.0]]);
+        let g = mul(r, &w).sum::<_, AllAxes>().backward();
+        assert_eq!(g.ref_gradient(&t), &[[3.0, 3.0], [7.0, 7.0]]);
+    }
+
+    #[test]
+    #[should_panic = "Can't repeat an axis of size 2 into an axis of size 3"]
+    fn test_tile_not_multiple() {
+        let _: Tensor1D<3> = tensor([1.0, 2.0]).tile();
+    }
+
+    #[test]
+    fn test_flip_2d() {
+        let t = tensor([[1.0, 2.0], [3.0, 4.0], [5.0, 6.0]]);
+        let r = t.trace().flip::<Axis<0>>();
+        assert_eq!(r.data(), &[[5.0, 6.0], [3.0, 4.0], [1.0, 2.0]]);
+        let w = tensor([[1.0, 2.0], [3.0, 4.0], [5.0, 6.0]]);
+        let g = mul(r, &w).sum::<_, AllAxes>().backward();
+        assert_eq!(g.ref_gradient(&t), &[[5.0, 6.0], [3.0, 4.0], [1.0, 2.0]]);
+    }
+
+    #[test]
+    fn test_flip_4d_twice_is_identity() {
+        let t: Tensor4D<2, 3, 4, 5> = TensorCreator::randn(&mut rand::thread_rng());
+        let r = t.clone().flip::<Axis<2>>().flip::<Axis<2>>();
+        assert_eq!(r.data(), t.data());
+    }
+
+    #[test]
+    fn test_roll_2d() {
+        let t = tensor([[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]);
+        let r = t.trace().roll::<Axis<1>>(4);
+        assert_eq!(r.data(), &[[3.0, 1.0, 2.0], [6.0, 4.0, 5.0]]);
+        let w = tensor([[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]);
+        let g = mul(r, &w).sum::<_, AllAxes>().backward();
+        assert_eq!(g.ref_gradient(&t), &[[2.0, 3.0, 1.0], [5.0, 6.0, 4.0]]);
+
+        let r = t.roll::<Axis<0>>(-3);
+        assert_eq!(r.data(), &[[4.0, 5.0, 6.0], [1.0, 2.0, 3.0]]);
+    }
+}
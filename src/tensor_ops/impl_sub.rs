@@ -1,4 +1,4 @@
-use super::utils::binary_map;
+use super::utils::broadcast_binary_map;
 use crate::gradients::Tape;
 use crate::prelude::*;
 
@@ -12,16 +12,31 @@ use crate::prelude::*;
 /// let r = sub(a, &b); // or `a - &b`
 /// assert_eq!(r.data(), &[[0.0, 1.0, 2.0], [-2.0, -3.0, -4.0]]);
 /// ```
-pub fn sub<T: Tensor<Dtype = f32>>(lhs: T, rhs: &T::NoTape) -> T {
-    binary_map(lhs, rhs, |x, y| x - y, |_, _| 1.0, |_, _| -1.0)
+pub fn sub<L, R>(lhs: L, rhs: &R) -> L::Output
+where
+    L: BroadcastBinary<R>,
+    R: 'static + Tensor<Dtype = f32>,
+{
+    broadcast_binary_map(
+        lhs,
+        rhs,
+        |x, y| x - y,
+        |_, _| 1.0,
+        |_, _| -1.0,
+        |_, _| [0.0; 3],
+    )
 }
 
 macro_rules! binary_ops_impl {
     ($typename:ident, [$($Vs:tt),*]) => {
-impl<$(const $Vs: usize, )* H: Tape> std::ops::Sub<&$typename<$($Vs, )* NoneTape>> for $typename<$($Vs, )* H> {
-    type Output = $typename<$($Vs, )* H>;
-    /// Calls [sub()] - implements `T<H> - &T<NoneTape>`
-    fn sub(self, rhs: &$typename<$($Vs, )* NoneTape>) -> Self::Output {
+impl<$(const $Vs: usize, )* H: Tape, Rhs> std::ops::Sub<&Rhs> for $typename<$($Vs, )* H>
+where
+    Self: BroadcastBinary<Rhs>,
+    Rhs: 'static + Tensor<Dtype = f32>,
+{
+    type Output = <Self as BroadcastBinary<Rhs>>::Output;
+    /// Calls [sub()] - implements `T<H> - &Rhs<NoneTape>`
+    fn sub(self, rhs: &Rhs) -> Self::Output {
         sub(self, rhs)
     }
 }
@@ -1,4 +1,4 @@
-use super::utils::move_tape_and_add_backward_op;
+use super::utils::move_tape_and_add_backward_op_with_graph;
 use crate::devices::{AddAccum, DeviceReduce};
 use crate::gradients::Tape;
 use crate::prelude::*;
@@ -30,10 +30,30 @@ use crate::prelude::*;
 pub fn sum<T: Reduce<Axes>, Axes>(t: T) -> T::Reduced {
     let mut result = <T::Reduced as Tensor>::NoTape::zeros();
     T::DeviceR::reduce_into_no_reset::<AddAccum>(result.mut_data(), t.data());
-    move_tape_and_add_backward_op(t, result, move |t, result, grads| {
-        let (t_grad, result_grad) = grads.mut_and_ref(&t, &result);
-        T::DeviceR::broadcast_into_no_reset::<AddAccum>(t_grad, result_grad);
-    })
+    move_tape_and_add_backward_op_with_graph(
+        t,
+        result,
+        move |t, result, grads| {
+            let (t_grad, result_grad) = grads.mut_and_ref_or_zeros(&t, &result);
+            T::DeviceR::broadcast_into_no_reset::<AddAccum>(t_grad, result_grad);
+        },
+        move |t, result, graph| {
+            let t_slot = graph.slot(&t);
+            let result_slot = graph.slot(&result);
+            let (t_grad, result_grad) = graph
+                .gradients()
+                .mut_and_ref_or_zeros(&t_slot, &result_slot);
+            T::DeviceR::broadcast_into_no_reset::<AddAccum>(t_grad, result_grad);
+            graph.add_backward_op(move |grads| {
+                let t_slot_grad = grads.clone_gradient(&t_slot);
+                let result_slot_grad = grads.mut_gradient(&result_slot);
+                T::DeviceR::reduce_into_no_reset::<AddAccum>(
+                    result_slot_grad,
+                    t_slot_grad.as_ref(),
+                );
+            });
+        },
+    )
 }
 
 macro_rules! sum_axis_impl {
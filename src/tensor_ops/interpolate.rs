@@ -0,0 +1,224 @@
+use super::utils::move_tape_and_add_backward_op;
+use crate::arrays::HasArrayType;
+use crate::devices::{
+    adaptive_avg_taps, cubic_taps, image_size, linear_taps, nearest_taps, Cpu, DeviceResize2D, Taps,
+};
+use crate::gradients::Tape;
+use crate::prelude::*;
+
+/// How [interpolate()] computes the values between pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InterpolateMode {
+    /// Copies the nearest pixel. `align_corners` is ignored.
+    Nearest,
+    /// Linear interpolation between the 2x2 nearest pixels.
+    Bilinear,
+    /// Cubic convolution with the 4x4 nearest pixels (with `a = -0.75`). The output can overshoot
+    /// the range of the input.
+    Bicubic,
+}
+
+/// Resizes the last two axes (height & width) of an image ([Tensor3D<C, H, W>]) or a batch of
+/// images ([Tensor4D<B, C, H, W>]) into `R`, which is `Self` with a different height & width.
+///
+/// This trait can't be used directly as it doesn't contain any methods. Instead
+/// it is used by methods to specify the input type must be able to be resized into `R`.
+pub trait Resize2DTo<R: HasArrayType<Dtype = f32>>: Tensor<Dtype = f32> {}
+
+#[rustfmt::skip]
+impl<const C: usize, const H: usize, const W: usize, const OH: usize, const OW: usize, T: Tape>
+    Resize2DTo<Tensor3D<C, OH, OW, T>> for Tensor3D<C, H, W, T> {}
+
+#[rustfmt::skip]
+impl<const B: usize, const C: usize, const H: usize, const W: usize, const OH: usize, const OW: usize, T: Tape>
+    Resize2DTo<Tensor4D<B, C, OH, OW, T>> for Tensor4D<B, C, H, W, T> {}
+
+/// Resizes the height & width of images to the height & width of `R` with `mode`.
+///
+/// If `align_corners` is true, the corner pixels of the input and output are aligned, and
+/// the values at the corners are preserved. Otherwise the input and output pixels are treated
+/// as squares that cover the same area.
+///
+/// **Pytorch equivalent**: `torch.nn.functional.interpolate(t, size=(OH, OW), mode=mode, align_corners=align_corners)`
+///
+/// **Related functions**: [adaptive_avg_pool2d()]
+///
+/// Examples:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let t: Tensor3D<1, 2, 2> = tensor([[[1.0, 2.0], [3.0, 4.0]]]);
+/// let r: Tensor3D<1, 3, 3> = t.clone().interpolate(InterpolateMode::Bilinear, true);
+/// assert_eq!(r.data(), &[[[1.0, 1.5, 2.0], [2.0, 2.5, 3.0], [3.0, 3.5, 4.0]]]);
+///
+/// let r: Tensor3D<1, 2, 4> = t.interpolate(InterpolateMode::Nearest, false);
+/// assert_eq!(r.data(), &[[[1.0, 1.0, 2.0, 2.0], [3.0, 3.0, 4.0, 4.0]]]);
+/// ```
+pub fn interpolate<T, R>(t: T, mode: InterpolateMode, align_corners: bool) -> R
+where
+    T: Resize2DTo<R>,
+    R: Tensor<Dtype = f32, Tape = T::Tape>,
+{
+    let (h, w) = image_size::<T::Array>();
+    let (oh, ow) = image_size::<R::Array>();
+    let (rows, cols) = match mode {
+        InterpolateMode::Nearest => (nearest_taps(h, oh), nearest_taps(w, ow)),
+        InterpolateMode::Bilinear => (
+            linear_taps(h, oh, align_corners),
+            linear_taps(w, ow, align_corners),
+        ),
+        InterpolateMode::Bicubic => (
+            cubic_taps(h, oh, align_corners),
+            cubic_taps(w, ow, align_corners),
+        ),
+    };
+    resize2d(t, rows, cols)
+}
+
+/// Average pools the height & width of images into the height & width of `R`. Output
+/// pixel `(i, j)` is the average of the input pixels in
+/// `[floor(i * H / OH), ceil((i + 1) * H / OH))` x `[floor(j * W / OW), ceil((j + 1) * W / OW))`,
+/// so the windows can overlap when the sizes aren't divisible.
+///
+/// **Pytorch equivalent**: `torch.nn.functional.adaptive_avg_pool2d(t, (OH, OW))`
+///
+/// **Related functions**: [interpolate()]
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let t: Tensor3D<1, 2, 3> = tensor([[[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]]);
+/// let r: Tensor3D<1, 1, 2> = t.adaptive_avg_pool2d();
+/// assert_eq!(r.data(), &[[[3.0, 4.0]]]);
+/// ```
+pub fn adaptive_avg_pool2d<T, R>(t: T) -> R
+where
+    T: Resize2DTo<R>,
+    R: Tensor<Dtype = f32, Tape = T::Tape>,
+{
+    let (h, w) = image_size::<T::Array>();
+    let (oh, ow) = image_size::<R::Array>();
+    resize2d(t, adaptive_avg_taps(h, oh), adaptive_avg_taps(w, ow))
+}
+
+fn resize2d<T, R>(t: T, rows: Taps, cols: Taps) -> R
+where
+    T: Resize2DTo<R>,
+    R: Tensor<Dtype = f32, Tape = T::Tape>,
+{
+    let mut result = R::NoTape::zeros();
+    <Cpu as DeviceResize2D<T::Array, R::Array>>::resize2d(
+        t.data(),
+        result.mut_data(),
+        &rows,
+        &cols,
+    );
+    move_tape_and_add_backward_op::<_, R, _>(t, result, move |t, result, grads| {
+        let (t_grad, result_grad) = grads.mut_and_ref(&t, &result);
+        Cpu::resize2d_backward(result_grad, t_grad, &rows, &cols);
+    })
+}
+
+macro_rules! tensor_impl {
+    ($typename:ident, [$($Vs:tt),*]) => {
+impl<$(const $Vs: usize, )* H: Tape> $typename<$($Vs, )* H> {
+    /// Calls [interpolate()] on `self`.
+    pub fn interpolate<R>(self, mode: InterpolateMode, align_corners: bool) -> R
+    where
+        Self: Resize2DTo<R>,
+        R: Tensor<Dtype = f32, Tape = <Self as Tensor>::Tape>,
+    {
+        interpolate(self, mode, align_corners)
+    }
+
+    /// Calls [adaptive_avg_pool2d()] on `self`.
+    pub fn adaptive_avg_pool2d<R>(self) -> R
+    where
+        Self: Resize2DTo<R>,
+        R: Tensor<Dtype = f32, Tape = <Self as Tensor>::Tape>,
+    {
+        adaptive_avg_pool2d(self)
+    }
+}
+    };
+}
+
+tensor_impl!(Tensor3D, [M, N, O]);
+tensor_impl!(Tensor4D, [M, N, O, P]);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arrays::AllAxes;
+
+    #[test]
+    fn test_bilinear_no_align_corners() {
+        let t: Tensor3D<1, 2, 2> = tensor([[[1.0, 2.0], [3.0, 4.0]]]);
+        let r: Tensor3D<1, 4, 4, OwnedTape> =
+            t.trace().interpolate(InterpolateMode::Bilinear, false);
+        assert_tensor_close!(
+            r,
+            tensor([[
+                [1.0, 1.25, 1.75, 2.0],
+                [1.5, 1.75, 2.25, 2.5],
+                [2.5, 2.75, 3.25, 3.5],
+                [3.0, 3.25, 3.75, 4.0],
+            ]])
+        );
+        let g = r.sum::<_, AllAxes>().backward();
+        assert_tensor_close!(g.ref_gradient(&t), [[[4.0; 2]; 2]]);
+    }
+
+    #[test]
+    fn test_bicubic() {
+        let t: Tensor3D<1, 1, 4> = tensor([[[0.0, 1.0, 2.0, 3.0]]]);
+        // cubic convolution reproduces linear functions away from the borders
+        let r: Tensor3D<1, 1, 7> = t.clone().interpolate(InterpolateMode::Bicubic, true);
+        assert_tensor_close!(r.data()[0][0][2], 1.0);
+        assert_tensor_close!(r.data()[0][0][3], 1.5);
+        assert_tensor_close!(r.data()[0][0][4], 2.0);
+
+        // the first output samples x = 1 / 6 with the clamped pixels [0, 0, 1, 2]
+        let r: Tensor3D<1, 1, 3, OwnedTape> =
+            t.trace().interpolate(InterpolateMode::Bicubic, false);
+        assert_tensor_close!(r, tensor([[[0.1261574, 1.5, 2.8738426]]]));
+        let g = r.sum::<_, AllAxes>().backward();
+        let g_sum: f32 = g.ref_gradient(&t)[0][0].iter().sum();
+        assert_tensor_close!(g_sum, 3.0);
+    }
+
+    #[test]
+    fn test_nearest_downsample_4d() {
+        let t: Tensor4D<2, 1, 2, 4> = TensorCreator::arange();
+        let r: Tensor4D<2, 1, 1, 2, OwnedTape> =
+            t.trace().interpolate(InterpolateMode::Nearest, false);
+        assert_eq!(r.data(), &[[[[0.0, 2.0]]], [[[8.0, 10.0]]]]);
+        let g = r.sum::<_, AllAxes>().backward();
+        assert_eq!(
+            g.ref_gradient(&t),
+            &[
+                [[[1.0, 0.0, 1.0, 0.0], [0.0; 4]]],
+                [[[1.0, 0.0, 1.0, 0.0], [0.0; 4]]]
+            ]
+        );
+    }
+
+    #[test]
+    fn test_adaptive_avg_pool2d_overlapping() {
+        let t: Tensor3D<1, 3, 3> = TensorCreator::arange();
+        let r: Tensor3D<1, 2, 2, OwnedTape> = t.trace().adaptive_avg_pool2d();
+        assert_eq!(r.data(), &[[[2.0, 3.0], [5.0, 6.0]]]);
+        let g = r.sum::<_, AllAxes>().backward();
+        assert_eq!(
+            g.ref_gradient(&t),
+            &[[[0.25, 0.5, 0.25], [0.5, 1.0, 0.5], [0.25, 0.5, 0.25]]]
+        );
+    }
+
+    #[test]
+    fn test_adaptive_avg_pool2d_to_1x1_is_mean() {
+        let t: Tensor4D<2, 3, 4, 5> = TensorCreator::arange();
+        let r: Tensor4D<2, 3, 1, 1> = t.clone().adaptive_avg_pool2d();
+        let m: Tensor2D<2, 3> = t.mean();
+        assert_tensor_close!(r.data().map(|b| b.map(|c| c[0][0])), *m.data());
+    }
+}
@@ -0,0 +1,166 @@
+use crate::gradients::{Gradients, OwnedTape};
+use crate::prelude::*;
+
+/// The full Jacobian of `f` at `x`, where row `i` is the gradient of output `i` with
+/// respect to `x`.
+///
+/// This calls `f` and [backward()] once per output, so prefer [jvp()] or [backward()]
+/// directly if you only need a product with the Jacobian.
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let x = tensor([1.0, 2.0, 3.0]);
+/// let j: Tensor2D<2, 3> = jacobian(|x| mul(x.square(), &tensor([1.0, 0.0, 2.0])).sum::<_, AllAxes>().broadcast(), &x);
+/// assert_eq!(j.data(), &[[2.0, 0.0, 12.0], [2.0, 0.0, 12.0]]);
+/// ```
+pub fn jacobian<const M: usize, const N: usize, F>(mut f: F, x: &Tensor1D<N>) -> Tensor2D<M, N>
+where
+    F: FnMut(Tensor1D<N, OwnedTape>) -> Tensor1D<M, OwnedTape>,
+{
+    let mut j: Tensor2D<M, N> = TensorCreator::zeros();
+    for i in 0..M {
+        let mut onehot = [0.0; M];
+        onehot[i] = 1.0;
+        let gradients = mul(f(x.trace()), &Tensor1D::new(onehot)).sum().backward();
+        j.mut_data()[i] = *gradient_of(gradients, x).data();
+    }
+    j
+}
+
+/// The Hessian of the scalar function `f` at `x`. Row `i` is computed as the Hessian-vector
+/// product [hvp()] with the `i`th unit vector, so `f` is called once per element of `x`.
+///
+/// This uses [backward_create_graph()], so `f` can only use the operations that support
+/// higher order gradients. See [crate::gradients::GradientGraph].
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let x = tensor([1.0, 2.0]);
+/// let h: Tensor2D<2, 2> = hessian(|x| mul(x.square(), &tensor([1.0, 3.0])).sum(), &x);
+/// assert_eq!(h.data(), &[[2.0, 0.0], [0.0, 6.0]]);
+/// ```
+pub fn hessian<const N: usize, F>(mut f: F, x: &Tensor1D<N>) -> Tensor2D<N, N>
+where
+    F: FnMut(Tensor1D<N, OwnedTape>) -> Tensor0D<OwnedTape>,
+{
+    let mut h: Tensor2D<N, N> = TensorCreator::zeros();
+    for i in 0..N {
+        let mut onehot = [0.0; N];
+        onehot[i] = 1.0;
+        h.mut_data()[i] = *hvp(&mut f, x, &Tensor1D::new(onehot)).data();
+    }
+    h
+}
+
+/// The Hessian-vector product `H(x) * v` of the scalar function `f`, computed by
+/// differentiating the dot product of the gradient of `f` with `v`. This is much cheaper
+/// than [hessian()], and doesn't store the full Hessian.
+///
+/// This uses [backward_create_graph()], so `f` can only use the operations that support
+/// higher order gradients. See [crate::gradients::GradientGraph].
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let x = tensor([1.0, 2.0, 3.0]);
+/// let hv = hvp(|x| x.powi(3).sum(), &x, &tensor([1.0, 1.0, -1.0]));
+/// assert_eq!(hv.data(), &[6.0, 12.0, -18.0]);
+/// ```
+pub fn hvp<const N: usize, F>(f: F, x: &Tensor1D<N>, v: &Tensor1D<N>) -> Tensor1D<N>
+where
+    F: FnOnce(Tensor1D<N, OwnedTape>) -> Tensor0D<OwnedTape>,
+{
+    let mut graph = f(x.trace()).backward_create_graph();
+    let dx: Tensor1D<N> = graph.gradient(x);
+    let gradients = mul(dx.put_tape(graph.into_tape()), v).sum().backward();
+    gradient_of(gradients, x)
+}
+
+/// `x` isn't in `gradients` if nothing depended on it.
+fn gradient_of<const N: usize>(mut gradients: Gradients, x: &Tensor1D<N>) -> Tensor1D<N> {
+    match gradients.remove(x) {
+        Some(g) => Tensor1D::new_boxed(g),
+        None => TensorCreator::zeros(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::assert_close;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn test_jacobian_of_linear() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut model: Linear<3, 2> = Default::default();
+        model.reset_params(&mut rng);
+        let x: Tensor1D<3> = TensorCreator::randn(&mut rng);
+        let j = jacobian(|x| model.forward(x), &x);
+        assert_eq!(j.data(), model.weight.data());
+    }
+
+    #[test]
+    fn test_jacobian_matches_jvp() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let w: Tensor2D<4, 3> = TensorCreator::randn(&mut rng);
+        let x: Tensor1D<3> = TensorCreator::randn(&mut rng);
+        let v: Tensor1D<3> = TensorCreator::randn(&mut rng);
+        let j = jacobian(|x| vecmat_mul_transpose(x.sin(), &w).tanh(), &x);
+        let (_, dy) = jvp(|x| vecmat_mul_transpose(x.sin(), &w).tanh(), &x, &v);
+        let jv = vecmat_mul_transpose(v, &j);
+        assert_close(jv.data(), dy.data());
+    }
+
+    #[test]
+    fn test_jacobian_unused_input() {
+        let x = tensor([1.0, 2.0]);
+        let j: Tensor2D<3, 2> = jacobian(
+            |x| {
+                let (_, tape) = x.split_tape();
+                Tensor1D::new([1.0; 3]).put_tape(tape)
+            },
+            &x,
+        );
+        assert_eq!(j.data(), &[[0.0; 2]; 3]);
+    }
+
+    #[test]
+    fn test_hessian_of_quadratic_form() {
+        let a = tensor([[1.0, 2.0, 0.0], [0.5, -1.0, 3.0], [0.0, 1.0, 2.0]]);
+        let x = tensor([0.3, -0.2, 1.5]);
+        let h = hessian(
+            |x| {
+                let x_ = x.duplicate();
+                mul(vecmat_mul(x, &a), &x_).sum()
+            },
+            &x,
+        );
+        // the hessian of x^T A x is A + A^T
+        assert_close(
+            h.data(),
+            &[[2.0, 2.5, 0.0], [2.5, -2.0, 4.0], [0.0, 4.0, 4.0]],
+        );
+    }
+
+    #[test]
+    fn test_hessian_of_linear_is_zeros() {
+        let x = tensor([1.0, 2.0, 3.0]);
+        let h = hessian(|x| mul_scalar(x, 2.0).sum(), &x);
+        assert_eq!(h.data(), &[[0.0; 3]; 3]);
+    }
+
+    #[test]
+    fn test_hvp_matches_hessian() {
+        let mut rng = StdRng::seed_from_u64(2);
+        let x: Tensor1D<4> = TensorCreator::randn(&mut rng);
+        let v: Tensor1D<4> = TensorCreator::randn(&mut rng);
+        let w: Tensor2D<4, 4> = TensorCreator::randn(&mut rng);
+        let f = |x: Tensor1D<4, OwnedTape>| vecmat_mul(x, &w).tanh().square().mean();
+        let h = hessian(f, &x);
+        let hv = hvp(f, &x, &v);
+        assert_close(hv.data(), vecmat_mul(v, &h).data());
+    }
+}
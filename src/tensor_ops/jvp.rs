@@ -0,0 +1,130 @@
+use crate::gradients::{ForwardTape, NoneTape};
+use crate::prelude::*;
+
+/// Computes `f(x)` and the Jacobian-vector product `J(x) * tangent` in a single forward
+/// pass, by calling `f` with a copy of `x` that has a [ForwardTape].
+///
+/// This is the directional derivative of every output of `f` in the direction of `tangent`,
+/// so it is much cheaper than [crate::tensor_ops::backward()] when there are many outputs.
+///
+/// See [ForwardTape] for which operations are supported.
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let x = tensor([1.0, 2.0]);
+/// let w = tensor([[1.0, 2.0], [3.0, 4.0], [5.0, 6.0]]);
+/// let (y, dy): (Tensor1D<3>, Tensor1D<3>) = jvp(|x| vecmat_mul_transpose(x, &w).square(), &x, &tensor([1.0, 0.0]));
+/// assert_eq!(y.data(), &[25.0, 121.0, 289.0]);
+/// assert_eq!(dy.data(), &[10.0, 66.0, 170.0]);
+/// ```
+pub fn jvp<X, Y, F>(f: F, x: &X, tangent: &X) -> (Y::NoTape, Y::NoTape)
+where
+    X: Tensor<Dtype = f32, Tape = NoneTape, NoTape = X> + PutTape<ForwardTape>,
+    Y: Tensor<Dtype = f32, Tape = ForwardTape>,
+    F: FnOnce(X::Output) -> Y,
+{
+    let mut tape = ForwardTape::default();
+    tape.0.mut_gradient(x).clone_from(tangent.data());
+    let (y, tape) = f(x.duplicate().put_tape(tape)).split_tape();
+    let y_tangent = tape.tangent(&y);
+    (y, y_tangent)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::assert_close;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn test_dual_maps() {
+        let x = tensor([0.5, -1.0, 2.0]);
+        let y = x.dual(&tensor([1.0, 2.0, -1.0])).sin().exp();
+        let dy = y.tangent();
+        let expected = [
+            0.5f32.cos() * 0.5f32.sin().exp(),
+            2.0 * (-1.0f32).cos() * (-1.0f32).sin().exp(),
+            -2.0f32.cos() * 2.0f32.sin().exp(),
+        ];
+        assert_close(dy.data(), &expected);
+    }
+
+    #[test]
+    fn test_dual_same_tensor_twice() {
+        let x = tensor([1.0, 2.0, 3.0]);
+        let y = mul(x.dual(&tensor([1.0, 1.0, 2.0])), &x);
+        assert_eq!(y.tangent().data(), &[2.0, 4.0, 12.0]);
+    }
+
+    #[test]
+    fn test_tangent_of_constant_is_zeros() {
+        let x = tensor([1.0, 2.0]);
+        let (y, tape) = x.dual(&tensor([1.0, 1.0])).split_tape();
+        let c = tensor([3.0, 4.0]);
+        assert_eq!(tape.tangent(&c).data(), &[0.0, 0.0]);
+        let z = add(c.put_tape(tape), &y);
+        assert_eq!(z.tangent().data(), &[1.0, 1.0]);
+    }
+
+    #[test]
+    fn test_jvp_matches_backward() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let x: Tensor2D<2, 3> = TensorCreator::randn(&mut rng);
+        let v: Tensor2D<2, 3> = TensorCreator::randn(&mut rng);
+        let w: Tensor2D<3, 4> = TensorCreator::randn(&mut rng);
+
+        let (y, dy) = jvp(|x| matmul(x, &w).tanh().mean::<_, AllAxes>(), &x, &v);
+        let r = matmul(x.trace(), &w).tanh().mean::<_, AllAxes>();
+        assert_eq!(y.data(), r.data());
+        let g = r.backward();
+        let expected: f32 = g
+            .ref_gradient(&x)
+            .iter()
+            .flatten()
+            .zip(v.data().iter().flatten())
+            .map(|(a, b)| a * b)
+            .sum();
+        assert!((dy.data() - expected).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_jvp_through_model() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut model: (Linear<3, 5>, Tanh, Linear<5, 2>) = Default::default();
+        model.reset_params(&mut rng);
+        let x: Tensor1D<3> = TensorCreator::randn(&mut rng);
+        let v: Tensor1D<3> = TensorCreator::randn(&mut rng);
+        let (_, dy) = jvp(|x| model.forward(x), &x, &v);
+
+        // central differences
+        let eps = 1e-2;
+        let plus = model.forward(add(x.clone(), &mul_scalar(v.clone(), eps)));
+        let minus = model.forward(sub(x.clone(), &mul_scalar(v.clone(), eps)));
+        let numerical = div_scalar(sub(plus, &minus), 2.0 * eps);
+        assert!(allclose(&dy, &numerical, 1e-3, 1e-3));
+    }
+
+    #[test]
+    fn test_jvp_broadcast_and_sum() {
+        let x = tensor([1.0, 2.0, 3.0]);
+        let (y, dy): (Tensor2D<2, 3>, Tensor2D<2, 3>) = jvp(
+            |x| {
+                let b: Tensor2D<2, 3, _> = x.broadcast();
+                let s: Tensor2D<2, 3, _> = add_scalar(b.sum::<_, AllAxes>(), 1.0).broadcast();
+                s
+            },
+            &x,
+            &tensor([1.0, -1.0, 0.5]),
+        );
+        assert_eq!(y.data(), &[[13.0; 3]; 2]);
+        assert_eq!(dy.data(), &[[1.0; 3]; 2]);
+    }
+
+    #[test]
+    #[should_panic = "doesn't support forward mode"]
+    fn test_jvp_unsupported_op() {
+        let x = tensor([1.0, 2.0]);
+        let _ = jvp(|x| x.max::<_, AllAxes>(), &x, &tensor([1.0, 0.0]));
+    }
+}
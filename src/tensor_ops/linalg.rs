@@ -0,0 +1,545 @@
+//! Linear algebra on square matrices ([Tensor2D<N, N>]) and batches of square
+//! matrices ([Tensor3D<B, N, N>]).
+
+use super::utils::{move_tape_and_add_backward_binop, move_tape_and_add_backward_op};
+use crate::arrays::HasArrayType;
+use crate::devices::{AllocateZeros, Cpu, Device, DeviceLinalg};
+use crate::gradients::Tape;
+use crate::prelude::*;
+
+/// A square matrix ([Tensor2D<N, N>]) or a batch of square matrices ([Tensor3D<B, N, N>]).
+/// Enables functions like [inverse()], [det()], and [svd()].
+pub trait SquareMatrices: Tensor<Dtype = f32> {
+    /// One value per matrix: [Tensor0D] or [Tensor1D<B>].
+    type Scalars: Tensor<Dtype = f32, Tape = Self::Tape>;
+
+    /// One vector of length `N` per matrix: [Tensor1D<N>] or [Tensor2D<B, N>].
+    type Vectors: Tensor<Dtype = f32, Tape = Self::Tape>;
+
+    type DeviceL: DeviceLinalg<Self::Array>;
+}
+
+impl<const N: usize, H: Tape> SquareMatrices for Tensor2D<N, N, H> {
+    type Scalars = Tensor0D<H>;
+    type Vectors = Tensor1D<N, H>;
+    type DeviceL = Cpu;
+}
+
+impl<const B: usize, const N: usize, H: Tape> SquareMatrices for Tensor3D<B, N, N, H> {
+    type Scalars = Tensor1D<B, H>;
+    type Vectors = Tensor2D<B, N, H>;
+    type DeviceL = Cpu;
+}
+
+/// Enables concrete output types for [solve()] and [triangular_solve()], where the right hand
+/// side is either a matrix ([Tensor2D<N, K>] or [Tensor3D<B, N, K>]) or a vector ([Tensor1D<N>]).
+pub trait SolveTyping<Rhs: HasArrayType>: SquareMatrices {
+    type Output: Tensor<Dtype = f32, Tape = Self::Tape, Array = Rhs::Array>;
+}
+
+impl<const N: usize, const K: usize, H: Tape> SolveTyping<Tensor2D<N, K>> for Tensor2D<N, N, H> {
+    type Output = Tensor2D<N, K, H>;
+}
+
+impl<const N: usize, H: Tape> SolveTyping<Tensor1D<N>> for Tensor2D<N, N, H> {
+    type Output = Tensor1D<N, H>;
+}
+
+impl<const B: usize, const N: usize, const K: usize, H: Tape> SolveTyping<Tensor3D<B, N, K>>
+    for Tensor3D<B, N, N, H>
+{
+    type Output = Tensor3D<B, N, K, H>;
+}
+
+/// Inverse of each matrix in `t`.
+///
+/// **Pytorch equivalent**: `torch.linalg.inv(t)`
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let t = tensor([[2.0, 0.0], [0.0, 4.0]]);
+/// let r = t.inverse();
+/// assert_eq!(r.data(), &[[0.5, 0.0], [0.0, 0.25]]);
+/// ```
+pub fn inverse<T: SquareMatrices>(t: T) -> T {
+    let mut result = T::NoTape::zeros();
+    T::DeviceL::inverse(t.data(), result.mut_data());
+    let inv = result.clone();
+    move_tape_and_add_backward_op(t, result, move |t, result, grads| {
+        let (t_grad, result_grad) = grads.mut_and_ref(&t, &result);
+        T::DeviceL::inverse_backward(inv.data(), result_grad, t_grad);
+    })
+}
+
+/// Determinant of each matrix in `t`.
+///
+/// **Pytorch equivalent**: `torch.linalg.det(t)`
+///
+/// **Related functions**: [logdet()]
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let t: Tensor3D<2, 2, 2> = tensor([[[2.0, 1.0], [4.0, 3.0]], [[0.0, 2.0], [3.0, 0.0]]]);
+/// let r: Tensor1D<2> = t.det();
+/// assert_eq!(r.data(), &[2.0, -6.0]);
+/// ```
+pub fn det<T: SquareMatrices>(t: T) -> T::Scalars {
+    let mut result = <T::Scalars as Tensor>::NoTape::zeros();
+    T::DeviceL::det(t.data(), result.mut_data());
+    let det = result.clone();
+    move_tape_and_add_backward_op(t, result, move |t, result, grads| {
+        let (t_grad, result_grad) = grads.mut_and_ref(&t, &result);
+        T::DeviceL::det_backward(t.data(), det.data(), result_grad, t_grad);
+    })
+}
+
+/// Natural log of the absolute value of the determinant of each matrix in `t`.
+/// This is more numerically stable than `det(t).abs().ln()`.
+///
+/// **Pytorch equivalent**: `torch.linalg.slogdet(t).logabsdet`
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let t = tensor([[-2.0, 0.0], [0.0, 4.0]]);
+/// let r = t.logdet();
+/// assert_eq!(r.data(), &8.0f32.ln());
+/// ```
+pub fn logdet<T: SquareMatrices>(t: T) -> T::Scalars {
+    let mut result = <T::Scalars as Tensor>::NoTape::zeros();
+    T::DeviceL::logdet(t.data(), result.mut_data());
+    move_tape_and_add_backward_op(t, result, move |t, result, grads| {
+        let (t_grad, result_grad) = grads.mut_and_ref(&t, &result);
+        T::DeviceL::logdet_backward(t.data(), result_grad, t_grad);
+    })
+}
+
+/// Cholesky decomposition of each matrix in `t`, which must be symmetric positive definite.
+/// Returns lower triangular `l` such that `t = l * l^T`. Only the lower triangle of `t` is used.
+///
+/// The gradient is symmetric, since `t` is assumed to be symmetric.
+///
+/// **Pytorch equivalent**: `torch.linalg.cholesky(t)`
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let t = tensor([[4.0, 2.0], [2.0, 5.0]]);
+/// let l = t.cholesky();
+/// assert_eq!(l.data(), &[[2.0, 0.0], [1.0, 2.0]]);
+/// ```
+pub fn cholesky<T: SquareMatrices>(t: T) -> T {
+    let mut result = T::NoTape::zeros();
+    T::DeviceL::cholesky(t.data(), result.mut_data());
+    let l = result.clone();
+    move_tape_and_add_backward_op(t, result, move |t, result, grads| {
+        let (t_grad, result_grad) = grads.mut_and_ref(&t, &result);
+        T::DeviceL::cholesky_backward(l.data(), result_grad, t_grad);
+    })
+}
+
+/// Solves `a * x = b` for `x`, where each matrix in `a` is lower triangular if `lower`
+/// is true, and upper triangular otherwise. Only that triangle of `a` is used.
+///
+/// **Pytorch equivalent**: `torch.linalg.solve_triangular(a, b, upper=not lower)`
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let a = tensor([[2.0, 0.0], [1.0, 1.0]]);
+/// let b = tensor([2.0, 3.0]);
+/// let x = a.triangular_solve(&b, true);
+/// assert_eq!(x.data(), &[1.0, 2.0]);
+/// ```
+pub fn triangular_solve<A, B>(a: A, b: &B, lower: bool) -> A::Output
+where
+    A: SolveTyping<B>,
+    B: 'static + Tensor<Dtype = f32>,
+{
+    let mut x = <A::Output as Tensor>::NoTape::zeros();
+    A::DeviceL::triangular_solve(a.data(), b.data(), x.mut_data(), lower);
+    let x_ = x.clone();
+    move_tape_and_add_backward_binop(a, b, x, move |a, b, x, grads| {
+        let mut b_grad: Box<B::Array> = B::Device::zeros();
+        let (a_grad, x_grad) = grads.mut_and_ref(&a, &x);
+        A::DeviceL::triangular_solve_backward(
+            a.data(),
+            x_.data(),
+            x_grad,
+            lower,
+            a_grad,
+            b_grad.as_mut(),
+        );
+        B::Device::add(grads.mut_gradient(&b), b_grad.as_ref());
+    })
+}
+
+/// Solves `a * x = b` for `x`, where each matrix in `a` is invertible.
+///
+/// **Pytorch equivalent**: `torch.linalg.solve(a, b)`
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let a = tensor([[2.0, 1.0], [4.0, 3.0]]);
+/// let b = tensor([[4.0], [10.0]]);
+/// let x = a.solve(&b);
+/// assert_eq!(x.data(), &[[1.0], [2.0]]);
+/// ```
+pub fn solve<A, B>(a: A, b: &B) -> A::Output
+where
+    A: SolveTyping<B>,
+    B: 'static + Tensor<Dtype = f32>,
+{
+    let mut x = <A::Output as Tensor>::NoTape::zeros();
+    A::DeviceL::solve(a.data(), b.data(), x.mut_data());
+    let x_ = x.clone();
+    move_tape_and_add_backward_binop(a, b, x, move |a, b, x, grads| {
+        let mut b_grad: Box<B::Array> = B::Device::zeros();
+        let (a_grad, x_grad) = grads.mut_and_ref(&a, &x);
+        A::DeviceL::solve_backward(a.data(), x_.data(), x_grad, a_grad, b_grad.as_mut());
+        B::Device::add(grads.mut_gradient(&b), b_grad.as_ref());
+    })
+}
+
+/// QR decomposition of each matrix in `t`. Returns orthogonal `q` and upper triangular `r`
+/// such that `t = q * r`. The diagonal of `r` is non negative, which makes the
+/// decomposition unique for invertible matrices.
+///
+/// Only one tensor can own the tape, so it is moved into `q`, and `r` is returned without
+/// a tape. Just like the complex outputs of [fft()], gradients still flow back through both
+/// of them, as long as they are used in operations that are recorded on the tape.
+///
+/// **Pytorch equivalent**: `torch.linalg.qr(t)`
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let t = tensor([[0.0, 2.0], [3.0, 1.0]]);
+/// let (q, r) = t.qr();
+/// assert_eq!(q.data(), &[[0.0, 1.0], [1.0, 0.0]]);
+/// assert_eq!(r.data(), &[[3.0, 1.0], [0.0, 2.0]]);
+/// ```
+///
+/// Move the tape over when you need to use `r` by value:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let t = tensor([[0.0, 2.0], [3.0, 1.0]]);
+/// let (q, r) = t.trace().qr();
+/// let (_, tape) = q.split_tape();
+/// let gradients = r.put_tape(tape).sum::<_, AllAxes>().backward();
+/// ```
+pub fn qr<T: SquareMatrices>(t: T) -> (T, T::NoTape) {
+    let mut q = T::NoTape::zeros();
+    let mut r = T::NoTape::zeros();
+    T::DeviceL::qr(t.data(), q.mut_data(), r.mut_data());
+    let q_ = q.clone();
+    let r_ = r.clone();
+    let phantom_r = r.phantom();
+    let q = move_tape_and_add_backward_op(t, q, move |t, q, grads| {
+        let q_grad = grads.clone_gradient(&q);
+        let r_grad = grads.clone_gradient(&phantom_r);
+        let t_grad = grads.mut_gradient(&t);
+        T::DeviceL::qr_backward(q_.data(), r_.data(), &q_grad, &r_grad, t_grad);
+    });
+    (q, r)
+}
+
+/// Singular value decomposition of each matrix in `t`. Returns orthogonal `u` & `v`, and
+/// singular values `s` in descending order, such that `t = u * diag(s) * v^T`.
+///
+/// The tape is moved into `s`, and `u` & `v` are returned without a tape. Gradients flow
+/// through all three of them, like the outputs of [qr()]. The gradient is only well defined
+/// when the singular values are distinct, and the loss doesn't depend on the signs of the
+/// columns of `u` & `v`.
+///
+/// **Pytorch equivalent**: `torch.linalg.svd(t)`, though pytorch returns `v^T` instead of `v`.
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let t = tensor([[0.0, 2.0], [-3.0, 0.0]]);
+/// let (u, s, v) = t.svd();
+/// assert_eq!(s.data(), &[3.0, 2.0]);
+/// ```
+pub fn svd<T: SquareMatrices>(t: T) -> (T::NoTape, T::Vectors, T::NoTape) {
+    let mut u = T::NoTape::zeros();
+    let mut s = <T::Vectors as Tensor>::NoTape::zeros();
+    let mut v = T::NoTape::zeros();
+    T::DeviceL::svd(t.data(), u.mut_data(), s.mut_data(), v.mut_data());
+    let u_ = u.clone();
+    let s_ = s.clone();
+    let v_ = v.clone();
+    let phantom_u = u.phantom();
+    let phantom_v = v.phantom();
+    let s = move_tape_and_add_backward_op(t, s, move |t, s, grads| {
+        let u_grad = grads.clone_gradient(&phantom_u);
+        let s_grad = grads.clone_gradient(&s);
+        let v_grad = grads.clone_gradient(&phantom_v);
+        T::DeviceL::svd_backward(
+            u_.data(),
+            s_.data(),
+            v_.data(),
+            &u_grad,
+            &s_grad,
+            &v_grad,
+            grads.mut_gradient(&t),
+        );
+    });
+    (u, s, v)
+}
+
+macro_rules! tensor_impl {
+    ([$($Vs:tt),*], $TensorTy:ty, $Square:ty) => {
+impl<$(const $Vs: usize, )* H: Tape> $TensorTy {
+    /// Calls [inverse()] on `self`.
+    pub fn inverse(self) -> Self {
+        inverse(self)
+    }
+    /// Calls [det()] on `self`.
+    pub fn det(self) -> <Self as SquareMatrices>::Scalars {
+        det(self)
+    }
+    /// Calls [logdet()] on `self`.
+    pub fn logdet(self) -> <Self as SquareMatrices>::Scalars {
+        logdet(self)
+    }
+    /// Calls [cholesky()] on `self`.
+    pub fn cholesky(self) -> Self {
+        cholesky(self)
+    }
+    /// Calls [triangular_solve()] on `self`.
+    pub fn triangular_solve<Rhs>(self, b: &Rhs, lower: bool) -> <Self as SolveTyping<Rhs>>::Output
+    where
+        Self: SolveTyping<Rhs>,
+        Rhs: 'static + Tensor<Dtype = f32>,
+    {
+        triangular_solve(self, b, lower)
+    }
+    /// Calls [solve()] on `self`.
+    pub fn solve<Rhs>(self, b: &Rhs) -> <Self as SolveTyping<Rhs>>::Output
+    where
+        Self: SolveTyping<Rhs>,
+        Rhs: 'static + Tensor<Dtype = f32>,
+    {
+        solve(self, b)
+    }
+    /// Calls [qr()] on `self`.
+    pub fn qr(self) -> (Self, $Square) {
+        qr(self)
+    }
+    /// Calls [svd()] on `self`.
+    pub fn svd(self) -> ($Square, <Self as SquareMatrices>::Vectors, $Square) {
+        svd(self)
+    }
+}
+    };
+}
+
+tensor_impl!([N], Tensor2D<N, N, H>, Tensor2D<N, N>);
+tensor_impl!([B, N], Tensor3D<B, N, N, H>, Tensor3D<B, N, N>);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gradients::OwnedTape;
+    use crate::tests::AssertClose;
+
+    const A: [[f32; 3]; 3] = [[4.0, -2.0, 1.0], [3.0, 6.0, -4.0], [2.0, 1.0, 8.0]];
+    const SPD: [[f32; 3]; 3] = [[4.0, 2.0, 0.4], [2.0, 5.0, 1.0], [0.4, 1.0, 3.0]];
+    const W: [[f32; 3]; 3] = [[0.5, -1.0, 2.0], [1.5, 0.25, -0.5], [-2.0, 1.0, 0.75]];
+
+    /// Compares the gradient of `f` at `a` with central differences. If `symmetric`,
+    /// `a[i][j]` and `a[j][i]` are perturbed together.
+    fn assert_grad_close<F>(a: [[f32; 3]; 3], symmetric: bool, f: F)
+    where
+        F: Fn(Tensor2D<3, 3, OwnedTape>) -> Tensor0D<OwnedTape>,
+    {
+        let t = tensor(a);
+        let g = f(t.trace()).backward();
+        let g = g.ref_gradient(&t);
+        let eps = 1e-2;
+        for i in 0..3 {
+            for j in 0..3 {
+                if symmetric && j > i {
+                    continue;
+                }
+                let mut p = a;
+                let mut m = a;
+                p[i][j] += eps;
+                m[i][j] -= eps;
+                let mut expected = g[i][j];
+                if symmetric && i != j {
+                    p[j][i] += eps;
+                    m[j][i] -= eps;
+                    expected += g[j][i];
+                }
+                let fp = *f(tensor(p).trace()).data();
+                let fm = *f(tensor(m).trace()).data();
+                let numerical = (fp - fm) / (2.0 * eps);
+                assert!(
+                    (numerical - expected).abs() < 1e-2 * (1.0 + numerical.abs()),
+                    "[{i}][{j}] {numerical} != {expected}"
+                );
+            }
+        }
+    }
+
+    fn weighted_sum(t: Tensor2D<3, 3, OwnedTape>) -> Tensor0D<OwnedTape> {
+        mul(t, &tensor(W)).sum::<_, AllAxes>()
+    }
+
+    #[test]
+    fn test_inverse() {
+        let r = tensor(A).inverse();
+        matmul(r, &tensor(A))
+            .data()
+            .assert_close(&[[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]], 1e-6);
+        assert_grad_close(A, false, |t| weighted_sum(t.inverse()));
+    }
+
+    #[test]
+    fn test_det_and_logdet() {
+        assert!((tensor(A).det().data() - 263.0).abs() < 1e-3);
+        assert!((tensor(A).logdet().data() - 263.0f32.ln()).abs() < 1e-5);
+        assert_grad_close(A, false, |t| t.det() / 100.0);
+        assert_grad_close(A, false, |t| t.logdet());
+        assert_grad_close(A, false, |t| (t * -1.0).logdet());
+    }
+
+    #[test]
+    fn test_singular_det_gradients() {
+        let t = tensor([[1.0, 2.0], [2.0, 4.0]]);
+        let r = t.trace().det();
+        assert_eq!(r.data(), &0.0);
+        let g = r.backward();
+        g.ref_gradient(&t)
+            .assert_close(&[[4.0, -2.0], [-2.0, 1.0]], 1e-5);
+    }
+
+    #[test]
+    fn test_batched_det() {
+        let a = tensor(A);
+        let t: Tensor3D<2, 3, 3> = tensor([A, SPD]);
+        let r = t.trace().det();
+        let dets = [*a.clone().det().data(), *tensor(SPD).det().data()];
+        r.data().assert_close(&dets, 1e-4);
+        let g = r.sum().backward();
+        let g0 = a.trace().det().backward();
+        g.ref_gradient(&t)[0].assert_close(g0.ref_gradient(&a), 1e-4);
+    }
+
+    #[test]
+    fn test_cholesky() {
+        let l = tensor(SPD).cholesky();
+        let llt = matmul_transpose(l.clone(), &l);
+        llt.data().assert_close(&SPD, 1e-5);
+        assert_grad_close(SPD, true, |t| weighted_sum(t.cholesky()));
+    }
+
+    #[test]
+    fn test_solve() {
+        let b = tensor([[1.0, 2.0], [3.0, 4.0], [5.0, 6.0]]);
+        let x = tensor(A).solve(&b);
+        matmul(tensor(A), &x).data().assert_close(b.data(), 1e-5);
+        assert_grad_close(A, false, |t| {
+            let w: Tensor2D<3, 2> = tensor([[1.0, -2.0], [0.5, 3.0], [-1.0, 1.0]]);
+            mul(t.solve(&b), &w).sum::<_, AllAxes>()
+        });
+
+        // gradient wrt b is the same as inverse(a) * b
+        let w = tensor([[1.0, -2.0], [0.5, 3.0], [-1.0, 1.0]]);
+        let g = mul(tensor(A).trace().solve(&b), &w)
+            .sum::<_, AllAxes>()
+            .backward();
+        let g2 = mul(matmul(tensor(A).trace().inverse(), &b), &w)
+            .sum::<_, AllAxes>()
+            .backward();
+        g.ref_gradient(&b).assert_close(g2.ref_gradient(&b), 1e-5);
+    }
+
+    #[test]
+    fn test_solve_vector() {
+        let x = tensor(A).solve(&tensor([1.0, 2.0, 3.0]));
+        let x2 = tensor(A).solve(&tensor([[1.0], [2.0], [3.0]]));
+        x.data()
+            .assert_close(&[x2.data()[0][0], x2.data()[1][0], x2.data()[2][0]], 1e-6);
+    }
+
+    #[test]
+    fn test_triangular_solve() {
+        let l = tensor(SPD).cholesky();
+        let b = tensor([[1.0, 2.0], [3.0, 4.0], [5.0, 6.0]]);
+        let x = l.clone().triangular_solve(&b, true);
+        matmul(l.clone(), &x).data().assert_close(b.data(), 1e-5);
+
+        let u: Tensor2D<3, 3> = PermuteTo::<_, Axes2<1, 0>>::permute(l.clone());
+        let x = u.clone().triangular_solve(&b, false);
+        matmul(u, &x).data().assert_close(b.data(), 1e-5);
+
+        let f = |t: Tensor2D<3, 3, OwnedTape>| {
+            let w: Tensor2D<3, 2> = tensor([[1.0, -2.0], [0.5, 3.0], [-1.0, 1.0]]);
+            mul(t.triangular_solve(&b, true), &w).sum::<_, AllAxes>()
+        };
+        assert_grad_close(*l.data(), false, f);
+    }
+
+    #[test]
+    fn test_qr() {
+        let (q, r) = tensor(A).qr();
+        matmul(q, &r).data().assert_close(&A, 1e-5);
+        assert_grad_close(A, false, |t| weighted_sum(t.qr().0));
+    }
+
+    #[test]
+    fn test_qr_gradients_through_r() {
+        assert_grad_close(A, false, |t| {
+            let (q, r) = t.qr();
+            let (_, tape) = q.split_tape();
+            weighted_sum(r.put_tape(tape))
+        });
+        assert_grad_close(A, false, |t| {
+            let (q, r) = t.qr();
+            weighted_sum(mul(q, &r))
+        });
+    }
+
+    #[test]
+    fn test_svd() {
+        let (u, s, v) = tensor(A).svd();
+        let s: Tensor2D<3, 3> = BroadcastTo::<_, Axis<0>>::broadcast(s);
+        let us = mul(u, &s);
+        matmul_transpose(us, &v).data().assert_close(&A, 1e-4);
+        assert_grad_close(A, false, |t| {
+            mul(t.svd().1, &tensor([1.0, -0.5, 2.0])).sum()
+        });
+    }
+
+    #[test]
+    fn test_svd_gradients_through_u_and_v() {
+        // squared so the loss doesn't depend on the signs of the singular vectors
+        assert_grad_close(A, false, |t| {
+            let (u, s, _) = t.svd();
+            let (_, tape) = s.split_tape();
+            weighted_sum(u.put_tape(tape).square())
+        });
+        assert_grad_close(A, false, |t| {
+            let (_, s, v) = t.svd();
+            let (_, tape) = s.split_tape();
+            weighted_sum(v.put_tape(tape).square())
+        });
+    }
+
+    #[test]
+    fn test_batched_inverse() {
+        let spd = tensor(SPD);
+        let t: Tensor3D<2, 3, 3> = tensor([A, SPD]);
+        let r = t.trace().inverse();
+        r.data()[1].assert_close(spd.clone().inverse().data(), 1e-6);
+        let g = mul(r, &tensor([W, W])).sum::<_, AllAxes>().backward();
+        let g1 = weighted_sum(spd.trace().inverse()).backward();
+        g.ref_gradient(&t)[1].assert_close(g1.ref_gradient(&spd), 1e-5);
+    }
+}
@@ -13,7 +13,7 @@ use std::ops::Neg;
 /// assert_eq!(r.data(), &[2.0, 0.0, -5.0]);
 /// ```
 pub fn negate<T: Tensor<Dtype = f32>>(t: T) -> T {
-    map_df_uses_fx(t, |x| -x, |_| -1.0)
+    map_df_uses_fx(t, |x| -x, |_| -1.0, |_| 0.0)
 }
 
 /// [Rectified Linear Unit (ReLU)](https://en.wikipedia.org/wiki/Rectifier_(neural_networks)). `max(0, t)`
@@ -32,7 +32,12 @@ pub fn negate<T: Tensor<Dtype = f32>>(t: T) -> T {
 /// let r2 = t.relu();
 /// ```
 pub fn relu<T: Tensor<Dtype = f32>>(t: T) -> T {
-    map_df_uses_fx(t, |x| x.max(0.0), |fx| if fx > &0.0 { 1.0 } else { 0.0 })
+    map_df_uses_fx(
+        t,
+        |x| x.max(0.0),
+        |fx| if fx > &0.0 { 1.0 } else { 0.0 },
+        |_| 0.0,
+    )
 }
 
 /// `t^2`
@@ -51,7 +56,7 @@ pub fn relu<T: Tensor<Dtype = f32>>(t: T) -> T {
 /// let r2 = t.square();
 /// ```
 pub fn square<T: Tensor<Dtype = f32>>(t: T) -> T {
-    map(t, |x| x.powi(2), |x| 2.0 * x)
+    map(t, |x| x.powi(2), |x| 2.0 * x, |_| 2.0)
 }
 
 /// `√t` or `t^0.5`
@@ -70,7 +75,12 @@ pub fn square<T: Tensor<Dtype = f32>>(t: T) -> T {
 /// let r2 = t.sqrt();
 /// ```
 pub fn sqrt<T: Tensor<Dtype = f32>>(t: T) -> T {
-    map_df_uses_fx(t, |x| x.sqrt(), |fx| 0.5 * fx.recip())
+    map_df_uses_fx(
+        t,
+        |x| x.sqrt(),
+        |fx| 0.5 * fx.recip(),
+        |fx| -0.25 * fx.powi(3).recip(),
+    )
 }
 
 /// [Hyperbolic Tangent (Tanh)](https://en.wikipedia.org/wiki/Hyperbolic_functions).
@@ -89,7 +99,12 @@ pub fn sqrt<T: Tensor<Dtype = f32>>(t: T) -> T {
 /// let r2 = t.tanh();
 /// ```
 pub fn tanh<T: Tensor<Dtype = f32>>(t: T) -> T {
-    map_df_uses_fx(t, |x| x.tanh(), |fx| 1.0 - fx.powi(2))
+    map_df_uses_fx(
+        t,
+        |x| x.tanh(),
+        |fx| 1.0 - fx.powi(2),
+        |fx| -2.0 * fx * (1.0 - fx.powi(2)),
+    )
 }
 
 /// [Sigmoid](https://en.wikipedia.org/wiki/Sigmoid_function).
@@ -114,7 +129,12 @@ pub fn sigmoid<T: Tensor<Dtype = f32>>(t: T) -> T {
         (1.0 + x.neg().exp()).recip()
     }
 
-    map_df_uses_fx(t, f, |fx| fx * (1.0 - fx))
+    map_df_uses_fx(
+        t,
+        f,
+        |fx| fx * (1.0 - fx),
+        |fx| fx * (1.0 - fx) * (1.0 - 2.0 * fx),
+    )
 }
 
 /// [Sine function](https://en.wikipedia.org/wiki/Sine_and_cosine).
@@ -133,7 +153,7 @@ pub fn sigmoid<T: Tensor<Dtype = f32>>(t: T) -> T {
 /// let r2 = t.sin();
 /// ```
 pub fn sin<T: Tensor<Dtype = f32>>(t: T) -> T {
-    map(t, |x| x.sin(), |x| x.cos())
+    map(t, |x| x.sin(), |x| x.cos(), |x| x.sin().neg())
 }
 
 /// [Cosine function](https://en.wikipedia.org/wiki/Sine_and_cosine).
@@ -152,7 +172,7 @@ pub fn sin<T: Tensor<Dtype = f32>>(t: T) -> T {
 /// let r2 = t.cos();
 /// ```
 pub fn cos<T: Tensor<Dtype = f32>>(t: T) -> T {
-    map(t, |x| x.cos(), |x| x.sin().neg())
+    map(t, |x| x.cos(), |x| x.sin().neg(), |x| x.cos().neg())
 }
 
 /// [Natural Logarithm (ln)](https://en.wikipedia.org/wiki/Natural_logarithm). `log_e(t)`.
@@ -171,7 +191,7 @@ pub fn cos<T: Tensor<Dtype = f32>>(t: T) -> T {
 /// let r2 = t.ln();
 /// ```
 pub fn ln<T: Tensor<Dtype = f32>>(t: T) -> T {
-    map(t, |x| x.ln(), |x| x.recip())
+    map(t, |x| x.ln(), |x| x.recip(), |x| x.powi(2).recip().neg())
 }
 
 /// [Exponential function (exp)](https://en.wikipedia.org/wiki/Natural_logarithm). `e^t`
@@ -190,7 +210,7 @@ pub fn ln<T: Tensor<Dtype = f32>>(t: T) -> T {
 /// let r2 = t.exp();
 /// ```
 pub fn exp<T: Tensor<Dtype = f32>>(t: T) -> T {
-    map_df_uses_fx(t, |x| x.exp(), |fx| *fx)
+    map_df_uses_fx(t, |x| x.exp(), |fx| *fx, |fx| *fx)
 }
 
 /// [Absolute value (abs)](https://en.wikipedia.org/wiki/Absolute_value). `|t|`
@@ -209,7 +229,299 @@ pub fn exp<T: Tensor<Dtype = f32>>(t: T) -> T {
 /// let r2 = t.abs();
 /// ```
 pub fn abs<T: Tensor<Dtype = f32>>(t: T) -> T {
-    map(t, |x| x.abs(), |x| if x == &0.0 { 0.0 } else { x.signum() })
+    map(
+        t,
+        |x| x.abs(),
+        |x| if x == &0.0 { 0.0 } else { x.signum() },
+        |_| 0.0,
+    )
+}
+
+/// `ln(1 + t)`, which is more accurate than [ln()] for `t` close to 0.
+///
+/// The derivative is `1 / (1 + t)`.
+///
+/// Examples:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let t = tensor([-1.0, 0.0, 1.0, 2.0]);
+///
+/// // use function version
+/// let r = log1p(t.clone());
+///
+/// // or the tensor method!
+/// let r2 = t.log1p();
+/// ```
+pub fn log1p<T: Tensor<Dtype = f32>>(t: T) -> T {
+    map(
+        t,
+        |x| x.ln_1p(),
+        |x| (1.0 + x).recip(),
+        |x| (1.0 + x).powi(2).recip().neg(),
+    )
+}
+
+/// `e^t - 1`, which is more accurate than [exp()] for `t` close to 0.
+///
+/// The derivative is `e^t`.
+///
+/// Examples:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let t = tensor([-1.0, 0.0, 1.0, 2.0]);
+///
+/// // use function version
+/// let r = expm1(t.clone());
+///
+/// // or the tensor method!
+/// let r2 = t.expm1();
+/// ```
+pub fn expm1<T: Tensor<Dtype = f32>>(t: T) -> T {
+    map_df_uses_fx(t, |x| x.exp_m1(), |fx| fx + 1.0, |fx| fx + 1.0)
+}
+
+/// [Error function (erf)](https://en.wikipedia.org/wiki/Error_function).
+///
+/// The derivative is `2 / sqrt(pi) * exp(-t^2)`.
+///
+/// Examples:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let t = tensor([-1.0, 0.0, 1.0, 2.0]);
+///
+/// // use function version
+/// let r = erf(t.clone());
+///
+/// // or the tensor method!
+/// let r2 = t.erf();
+/// ```
+pub fn erf<T: Tensor<Dtype = f32>>(t: T) -> T {
+    fn df(x: &f32) -> f32 {
+        std::f32::consts::FRAC_2_SQRT_PI * x.powi(2).neg().exp()
+    }
+    map(t, erf_f32, df, |x| -2.0 * x * df(x))
+}
+
+/// Approximation of erf with a maximum absolute error of `1.2e-7`, from
+/// Numerical Recipes (`erfc` via Chebyshev fitting).
+fn erf_f32(x: &f32) -> f32 {
+    let x = *x as f64;
+    let z = x.abs();
+    let t = 1.0 / (1.0 + 0.5 * z);
+    let poly = -z * z - 1.26551223
+        + t * (1.00002368
+            + t * (0.37409196
+                + t * (0.09678418
+                    + t * (-0.18628806
+                        + t * (0.27886807
+                            + t * (-1.13520398
+                                + t * (1.48851587 + t * (-0.82215223 + t * 0.17087277))))))));
+    let erfc = t * poly.exp();
+    (if x >= 0.0 { 1.0 - erfc } else { erfc - 1.0 }) as f32
+}
+
+/// `ln(sigmoid(t))`, computed without overflow for large `|t|`.
+///
+/// The derivative is `1 - sigmoid(t)`.
+///
+/// Examples:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let t = tensor([-1.0, 0.0, 1.0, 2.0]);
+///
+/// // use function version
+/// let r = log_sigmoid(t.clone());
+///
+/// // or the tensor method!
+/// let r2 = t.log_sigmoid();
+/// ```
+pub fn log_sigmoid<T: Tensor<Dtype = f32>>(t: T) -> T {
+    fn f(x: &f32) -> f32 {
+        x.min(0.0) - x.abs().neg().exp().ln_1p()
+    }
+    fn df(x: &f32) -> f32 {
+        (1.0 + x.exp()).recip()
+    }
+    map(t, f, df, |x| -df(x) * (1.0 - df(x)))
+}
+
+/// [Softplus](https://en.wikipedia.org/wiki/Rectifier_(neural_networks)#Softplus). `ln(1 + e^t)`, computed without overflow for large `t`.
+///
+/// The derivative is `sigmoid(t)`.
+///
+/// Examples:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let t = tensor([-1.0, 0.0, 1.0, 2.0]);
+///
+/// // use function version
+/// let r = softplus(t.clone());
+///
+/// // or the tensor method!
+/// let r2 = t.softplus();
+/// ```
+pub fn softplus<T: Tensor<Dtype = f32>>(t: T) -> T {
+    fn f(x: &f32) -> f32 {
+        x.max(0.0) + x.abs().neg().exp().ln_1p()
+    }
+    fn df(x: &f32) -> f32 {
+        (1.0 + x.neg().exp()).recip()
+    }
+    map(t, f, df, |x| df(x) * (1.0 - df(x)))
+}
+
+/// `1 / t`
+///
+/// The derivative is `-1 / t^2`.
+///
+/// Examples:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let t = tensor([-2.0, -0.5, 0.5, 4.0]);
+///
+/// // use function version
+/// let r = reciprocal(t.clone());
+///
+/// // or the tensor method!
+/// let r2 = t.reciprocal();
+/// ```
+pub fn reciprocal<T: Tensor<Dtype = f32>>(t: T) -> T {
+    map_df_uses_fx(t, |x| x.recip(), |fx| -fx.powi(2), |fx| 2.0 * fx.powi(3))
+}
+
+/// The sign of each element: -1.0 for t < 0, 0 for t == 0, and 1.0 for t > 0.
+///
+/// The derivative is 0.
+///
+/// Examples:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let t = tensor([-1.0, 0.0, 1.0, 2.0]);
+///
+/// // use function version
+/// let r = sign(t.clone());
+///
+/// // or the tensor method!
+/// let r2 = t.sign();
+/// ```
+pub fn sign<T: Tensor<Dtype = f32>>(t: T) -> T {
+    map(
+        t,
+        |x| if x == &0.0 { 0.0 } else { x.signum() },
+        |_| 0.0,
+        |_| 0.0,
+    )
+}
+
+/// Rounds down to the nearest integer.
+///
+/// The derivative is 0. See [floor_straight_through()] to pass gradients through unchanged.
+///
+/// Examples:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let t = tensor([-1.5, -0.5, 0.5, 1.5]);
+///
+/// // use function version
+/// let r = floor(t.clone());
+///
+/// // or the tensor method!
+/// let r2 = t.floor();
+/// ```
+pub fn floor<T: Tensor<Dtype = f32>>(t: T) -> T {
+    map(t, |x| x.floor(), |_| 0.0, |_| 0.0)
+}
+
+/// Same as [floor()], but uses a [straight-through estimator](https://arxiv.org/abs/1308.3432)
+/// for the gradient: the derivative is 1.0, as if this was the identity function.
+///
+/// Examples:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let t = tensor([-1.5, -0.5, 0.5, 1.5]);
+///
+/// // use function version
+/// let r = floor_straight_through(t.clone());
+///
+/// // or the tensor method!
+/// let r2 = t.floor_straight_through();
+/// ```
+pub fn floor_straight_through<T: Tensor<Dtype = f32>>(t: T) -> T {
+    map(t, |x| x.floor(), |_| 1.0, |_| 0.0)
+}
+
+/// Rounds up to the nearest integer.
+///
+/// The derivative is 0. See [ceil_straight_through()] to pass gradients through unchanged.
+///
+/// Examples:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let t = tensor([-1.5, -0.5, 0.5, 1.5]);
+///
+/// // use function version
+/// let r = ceil(t.clone());
+///
+/// // or the tensor method!
+/// let r2 = t.ceil();
+/// ```
+pub fn ceil<T: Tensor<Dtype = f32>>(t: T) -> T {
+    map(t, |x| x.ceil(), |_| 0.0, |_| 0.0)
+}
+
+/// Same as [ceil()], but uses a [straight-through estimator](https://arxiv.org/abs/1308.3432)
+/// for the gradient: the derivative is 1.0, as if this was the identity function.
+///
+/// Examples:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let t = tensor([-1.5, -0.5, 0.5, 1.5]);
+///
+/// // use function version
+/// let r = ceil_straight_through(t.clone());
+///
+/// // or the tensor method!
+/// let r2 = t.ceil_straight_through();
+/// ```
+pub fn ceil_straight_through<T: Tensor<Dtype = f32>>(t: T) -> T {
+    map(t, |x| x.ceil(), |_| 1.0, |_| 0.0)
+}
+
+/// Rounds to the nearest integer, rounding half-way cases away from 0.
+///
+/// The derivative is 0. See [round_straight_through()] to pass gradients through unchanged.
+///
+/// Examples:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let t = tensor([-1.5, -0.5, 0.5, 1.5]);
+///
+/// // use function version
+/// let r = round(t.clone());
+///
+/// // or the tensor method!
+/// let r2 = t.round();
+/// ```
+pub fn round<T: Tensor<Dtype = f32>>(t: T) -> T {
+    map(t, |x| x.round(), |_| 0.0, |_| 0.0)
+}
+
+/// Same as [round()], but uses a [straight-through estimator](https://arxiv.org/abs/1308.3432)
+/// for the gradient: the derivative is 1.0, as if this was the identity function.
+///
+/// Examples:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let t = tensor([-1.5, -0.5, 0.5, 1.5]);
+///
+/// // use function version
+/// let r = round_straight_through(t.clone());
+///
+/// // or the tensor method!
+/// let r2 = t.round_straight_through();
+/// ```
+pub fn round_straight_through<T: Tensor<Dtype = f32>>(t: T) -> T {
+    map(t, |x| x.round(), |_| 1.0, |_| 0.0)
 }
 
 macro_rules! activation_impl {
@@ -235,6 +547,19 @@ impl<$(const $Vs: usize, )* H: Tape> $typename<$($Vs, )* H> {
     activation_impl!(square, #[doc="Calls [square()] on `self`."]);
     activation_impl!(sqrt, #[doc="Calls [sqrt()] on `self`."]);
     activation_impl!(abs, #[doc="Calls [abs()] on `self`."]);
+    activation_impl!(log1p, #[doc="Calls [log1p()] on `self`."]);
+    activation_impl!(expm1, #[doc="Calls [expm1()] on `self`."]);
+    activation_impl!(erf, #[doc="Calls [erf()] on `self`."]);
+    activation_impl!(log_sigmoid, #[doc="Calls [log_sigmoid()] on `self`."]);
+    activation_impl!(softplus, #[doc="Calls [softplus()] on `self`."]);
+    activation_impl!(reciprocal, #[doc="Calls [reciprocal()] on `self`."]);
+    activation_impl!(sign, #[doc="Calls [sign()] on `self`."]);
+    activation_impl!(floor, #[doc="Calls [floor()] on `self`."]);
+    activation_impl!(ceil, #[doc="Calls [ceil()] on `self`."]);
+    activation_impl!(round, #[doc="Calls [round()] on `self`."]);
+    activation_impl!(floor_straight_through, #[doc="Calls [floor_straight_through()] on `self`."]);
+    activation_impl!(ceil_straight_through, #[doc="Calls [ceil_straight_through()] on `self`."]);
+    activation_impl!(round_straight_through, #[doc="Calls [round_straight_through()] on `self`."]);
 }
 
 impl<$(const $Vs: usize, )* H: Tape> std::ops::Neg for $typename<$($Vs, )* H>
@@ -258,6 +583,7 @@ tensor_impl!(Tensor4D, [M, N, O, P]);
 mod tests {
     use super::*;
     use crate::tests::assert_close;
+    use std::f32::consts::LN_2;
 
     #[test]
     fn test_relu() {
@@ -393,6 +719,125 @@ mod tests {
         assert_eq!(gradients.ref_gradient(&x), &[-0.2, -0.2, 0.0, 0.2, 0.2]);
     }
 
+    #[test]
+    fn test_log1p() {
+        let x = tensor([-0.5, 0.0, 1.0, 2.0]);
+        let r = x.trace().log1p();
+        assert_close(r.data(), &[-LN_2, 0.0, LN_2, 1.0986123]);
+        let gradients = backward(r.mean());
+        assert_close(gradients.ref_gradient(&x), &[0.5, 0.25, 0.125, 0.083333336]);
+    }
+
+    #[test]
+    fn test_expm1() {
+        let x = tensor([-2.0, -1.0, 0.0, 1.0, 2.0]);
+        let r = x.trace().expm1();
+        assert_close(
+            r.data(),
+            &[-0.86466473, -0.63212055, 0.0, 1.7182817, 6.389056],
+        );
+        let gradients = backward(r.mean());
+        assert_close(
+            gradients.ref_gradient(&x),
+            &[0.027067056, 0.07357589, 0.2, 0.54365635, 1.4778112],
+        );
+    }
+
+    #[test]
+    fn test_erf() {
+        let x = tensor([-2.0, -1.0, 0.0, 1.0, 2.0]);
+        let r = x.trace().erf();
+        assert_close(
+            r.data(),
+            &[-0.9953223, -0.8427008, 0.0, 0.8427008, 0.9953223],
+        );
+        let gradients = backward(r.mean());
+        assert_close(
+            gradients.ref_gradient(&x),
+            &[0.004133397, 0.0830215, 0.22567584, 0.0830215, 0.004133397],
+        );
+    }
+
+    #[test]
+    fn test_log_sigmoid() {
+        let x = tensor([-2.0, -1.0, 0.0, 1.0, 2.0]);
+        let r = x.trace().log_sigmoid();
+        assert_close(
+            r.data(),
+            &[-2.126928, -1.3132616, -LN_2, -0.3132617, -0.126928],
+        );
+        let gradients = backward(r.mean());
+        assert_close(
+            gradients.ref_gradient(&x),
+            &[0.17615941, 0.14621171, 0.1, 0.053788286, 0.023840584],
+        );
+
+        let r = tensor([-100.0, 100.0]).log_sigmoid();
+        assert_close(r.data(), &[-100.0, 0.0]);
+    }
+
+    #[test]
+    fn test_softplus() {
+        let x = tensor([-2.0, -1.0, 0.0, 1.0, 2.0]);
+        let r = x.trace().softplus();
+        assert_close(r.data(), &[0.126928, 0.3132617, LN_2, 1.3132616, 2.126928]);
+        let gradients = backward(r.mean());
+        assert_close(
+            gradients.ref_gradient(&x),
+            &[0.023840584, 0.053788286, 0.1, 0.14621171, 0.17615941],
+        );
+
+        let r = tensor([-100.0, 100.0]).softplus();
+        assert_close(r.data(), &[0.0, 100.0]);
+    }
+
+    #[test]
+    fn test_reciprocal() {
+        let x = tensor([-2.0, -0.5, 0.5, 4.0]);
+        let r = x.trace().reciprocal();
+        assert_eq!(r.data(), &[-0.5, -2.0, 2.0, 0.25]);
+        let gradients = backward(r.mean());
+        assert_eq!(
+            gradients.ref_gradient(&x),
+            &[-0.0625, -1.0, -1.0, -0.015625]
+        );
+    }
+
+    #[test]
+    fn test_sign() {
+        let x = tensor([-2.0, -0.0, 0.0, 3.0]);
+        let r = x.trace().sign();
+        assert_eq!(r.data(), &[-1.0, 0.0, 0.0, 1.0]);
+        let gradients = backward(r.mean());
+        assert_eq!(gradients.ref_gradient(&x), &[0.0; 4]);
+    }
+
+    #[test]
+    fn test_floor_ceil_round() {
+        let x = tensor([-1.5, -0.5, 0.2, 0.5, 1.7]);
+        assert_eq!(x.clone().floor().data(), &[-2.0, -1.0, 0.0, 0.0, 1.0]);
+        assert_eq!(x.clone().ceil().data(), &[-1.0, -0.0, 1.0, 1.0, 2.0]);
+        assert_eq!(x.clone().round().data(), &[-2.0, -1.0, 0.0, 1.0, 2.0]);
+
+        let gradients = backward(x.trace().round().mean());
+        assert_eq!(gradients.ref_gradient(&x), &[0.0; 5]);
+    }
+
+    #[test]
+    fn test_round_straight_through() {
+        let x = tensor([-1.5, -0.5, 0.2, 0.5, 1.7]);
+        let r = x.trace().round_straight_through();
+        assert_eq!(r.data(), &[-2.0, -1.0, 0.0, 1.0, 2.0]);
+        // NOTE: .square() to make sure its using result grad properly
+        let gradients = backward(r.square().mean());
+        assert_eq!(gradients.ref_gradient(&x), &[-0.8, -0.4, 0.0, 0.4, 0.8]);
+
+        let gradients = backward(x.trace().floor_straight_through().mean());
+        assert_eq!(gradients.ref_gradient(&x), &[0.2; 5]);
+        let gradients = backward(x.trace().ceil_straight_through().mean());
+        assert_eq!(gradients.ref_gradient(&x), &[0.2; 5]);
+    }
+
     #[test]
     fn test_1d_neg() {
         let a: Tensor1D<3> = tensor([-2.0, 0.0, 5.0]);
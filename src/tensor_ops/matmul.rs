@@ -1,4 +1,4 @@
-use super::utils::move_tape_and_add_backward_binop;
+use super::utils::move_tape_and_add_backward_binop_with_graph;
 use crate::devices::{Cpu, MatMul, MatMulOp, Transpose};
 use crate::gradients::Tape;
 use crate::prelude::*;
@@ -44,14 +44,54 @@ where
     A::Device::mm(a.data(), b.data(), c.mut_data());
 
     let b_ = b.clone();
-
-    move_tape_and_add_backward_binop(a, b, c, move |a, b, c, grads| {
-        let (a_grad, c_grad) = grads.mut_and_ref(&a, &c);
-        A::Device::mm_bt(c_grad, b_.data(), a_grad);
-
-        let (b_grad, c_grad) = grads.mut_and_ref(&b, &c);
-        A::Device::mm_at(a.data(), c_grad, b_grad);
-    })
+    let graph_b = b.clone();
+
+    move_tape_and_add_backward_binop_with_graph(
+        a,
+        b,
+        c,
+        move |a, b, c, grads| {
+            let (a_grad, c_grad) = grads.mut_and_ref_or_zeros(&a, &c);
+            A::Device::mm_bt(c_grad, b_.data(), a_grad);
+
+            let (b_grad, c_grad) = grads.mut_and_ref_or_zeros(&b, &c);
+            A::Device::mm_at(a.data(), c_grad, b_grad);
+        },
+        move |a, b, c, graph| {
+            let a_slot = graph.slot(&a);
+            let b_slot = graph.slot(&b);
+            let c_slot = graph.slot(&c);
+            let c_grad: Box<C::Array> = graph.gradients().clone_gradient(&c_slot);
+            A::Device::mm_bt(
+                c_grad.as_ref(),
+                graph_b.data(),
+                graph.gradients().mut_gradient(&a_slot),
+            );
+            A::Device::mm_at(
+                a.data(),
+                c_grad.as_ref(),
+                graph.gradients().mut_gradient(&b_slot),
+            );
+
+            graph.add_backward_op(move |grads| {
+                let a_slot_grad = grads.clone_gradient(&a_slot);
+                let b_slot_grad = grads.clone_gradient(&b_slot);
+                let c_slot_grad = grads.mut_gradient(&c_slot);
+                A::Device::mm(a_slot_grad.as_ref(), graph_b.data(), c_slot_grad);
+                A::Device::mm(a.data(), b_slot_grad.as_ref(), c_slot_grad);
+                A::Device::mm_at(
+                    a_slot_grad.as_ref(),
+                    c_grad.as_ref(),
+                    grads.mut_gradient(&b),
+                );
+                A::Device::mm_bt(
+                    c_grad.as_ref(),
+                    b_slot_grad.as_ref(),
+                    grads.mut_gradient(&a),
+                );
+            });
+        },
+    )
 }
 
 /// Enables concrete output types for generic matmul functions. Without this
@@ -129,14 +169,54 @@ where
     A::Device::mm_bt(a.data(), b.data(), c.mut_data());
 
     let b_ = b.clone();
-
-    move_tape_and_add_backward_binop(a, b, c, move |a, b, c, grads| {
-        let (a_grad, c_grad) = grads.mut_and_ref(&a, &c);
-        A::Device::mm(c_grad, b_.data(), a_grad);
-
-        let (b_grad, c_grad) = grads.mut_and_ref(&b, &c);
-        A::Device::mm_atct(a.data(), c_grad, b_grad);
-    })
+    let graph_b = b.clone();
+
+    move_tape_and_add_backward_binop_with_graph(
+        a,
+        b,
+        c,
+        move |a, b, c, grads| {
+            let (a_grad, c_grad) = grads.mut_and_ref_or_zeros(&a, &c);
+            A::Device::mm(c_grad, b_.data(), a_grad);
+
+            let (b_grad, c_grad) = grads.mut_and_ref_or_zeros(&b, &c);
+            A::Device::mm_atct(a.data(), c_grad, b_grad);
+        },
+        move |a, b, c, graph| {
+            let a_slot = graph.slot(&a);
+            let b_slot = graph.slot(&b);
+            let c_slot = graph.slot(&c);
+            let c_grad: Box<C::Array> = graph.gradients().clone_gradient(&c_slot);
+            A::Device::mm(
+                c_grad.as_ref(),
+                graph_b.data(),
+                graph.gradients().mut_gradient(&a_slot),
+            );
+            A::Device::mm_atct(
+                a.data(),
+                c_grad.as_ref(),
+                graph.gradients().mut_gradient(&b_slot),
+            );
+
+            graph.add_backward_op(move |grads| {
+                let a_slot_grad = grads.clone_gradient(&a_slot);
+                let b_slot_grad = grads.clone_gradient(&b_slot);
+                let c_slot_grad = grads.mut_gradient(&c_slot);
+                A::Device::mm_bt(a_slot_grad.as_ref(), graph_b.data(), c_slot_grad);
+                A::Device::mm_bt(a.data(), b_slot_grad.as_ref(), c_slot_grad);
+                A::Device::mm_atct(
+                    a_slot_grad.as_ref(),
+                    c_grad.as_ref(),
+                    grads.mut_gradient(&b),
+                );
+                A::Device::mm(
+                    c_grad.as_ref(),
+                    b_slot_grad.as_ref(),
+                    grads.mut_gradient(&a),
+                );
+            });
+        },
+    )
 }
 
 /// Enables concrete output types for generic matmul functions. Without this
@@ -199,14 +279,59 @@ pub fn vecmat_mul<const K: usize, const N: usize, TAPE: Tape>(
     Cpu::vm(lhs.data(), rhs.data(), result.mut_data());
 
     let rhs_data = rhs.data.clone();
-
-    move_tape_and_add_backward_binop(lhs, rhs, result, move |lhs, rhs, result, grads| {
-        let (lhs_grad, result_grad) = grads.mut_and_ref(&lhs, &result);
-        Cpu::vm_bt(result_grad, rhs_data.as_ref(), lhs_grad);
-
-        let (rhs_t_grad, result_grad) = grads.mut_and_ref(&rhs, &result);
-        Cpu::vv(lhs.data(), result_grad, rhs_t_grad);
-    })
+    let graph_rhs_data = rhs.data.clone();
+
+    move_tape_and_add_backward_binop_with_graph(
+        lhs,
+        rhs,
+        result,
+        move |lhs, rhs, result, grads| {
+            let (lhs_grad, result_grad) = grads.mut_and_ref_or_zeros(&lhs, &result);
+            Cpu::vm_bt(result_grad, rhs_data.as_ref(), lhs_grad);
+
+            let (rhs_t_grad, result_grad) = grads.mut_and_ref_or_zeros(&rhs, &result);
+            Cpu::vv(lhs.data(), result_grad, rhs_t_grad);
+        },
+        move |lhs, rhs, result, graph| {
+            let lhs_slot = graph.slot(&lhs);
+            let rhs_slot = graph.slot(&rhs);
+            let result_slot = graph.slot(&result);
+            let result_grad: Box<[f32; N]> = graph.gradients().clone_gradient(&result_slot);
+            let rhs_data = graph_rhs_data.as_ref();
+            Cpu::vm_bt(
+                result_grad.as_ref(),
+                rhs_data,
+                graph.gradients().mut_gradient(&lhs_slot),
+            );
+            Cpu::vv(
+                lhs.data(),
+                result_grad.as_ref(),
+                graph.gradients().mut_gradient(&rhs_slot),
+            );
+
+            graph.add_backward_op(move |grads| {
+                let lhs_slot_grad = grads.clone_gradient(&lhs_slot);
+                let rhs_slot_grad = grads.clone_gradient(&rhs_slot);
+                let result_slot_grad = grads.mut_gradient(&result_slot);
+                Cpu::vm(
+                    lhs_slot_grad.as_ref(),
+                    graph_rhs_data.as_ref(),
+                    result_slot_grad,
+                );
+                Cpu::vm(lhs.data(), rhs_slot_grad.as_ref(), result_slot_grad);
+                Cpu::vv(
+                    lhs_slot_grad.as_ref(),
+                    result_grad.as_ref(),
+                    grads.mut_gradient(&rhs),
+                );
+                Cpu::vm_bt(
+                    result_grad.as_ref(),
+                    rhs_slot_grad.as_ref(),
+                    grads.mut_gradient(&lhs),
+                );
+            });
+        },
+    )
 }
 
 /// vector * matrix multiplication where `rhs` is transposed. `y * transpose(rhs)`
@@ -237,14 +362,59 @@ pub fn vecmat_mul_transpose<const K: usize, const N: usize, TAPE: Tape>(
     Cpu::vm_bt(lhs.data(), rhs_t.data(), result.mut_data());
 
     let rhs_t_data = rhs_t.data.clone();
-
-    move_tape_and_add_backward_binop(lhs, rhs_t, result, move |lhs, rhs, result, grads| {
-        let (lhs_grad, result_grad) = grads.mut_and_ref(&lhs, &result);
-        Cpu::vm(result_grad, rhs_t_data.as_ref(), lhs_grad);
-
-        let (rhs_t_grad, result_grad) = grads.mut_and_ref(&rhs, &result);
-        Cpu::vv(result_grad, lhs.data(), rhs_t_grad);
-    })
+    let graph_rhs_t_data = rhs_t.data.clone();
+
+    move_tape_and_add_backward_binop_with_graph(
+        lhs,
+        rhs_t,
+        result,
+        move |lhs, rhs, result, grads| {
+            let (lhs_grad, result_grad) = grads.mut_and_ref_or_zeros(&lhs, &result);
+            Cpu::vm(result_grad, rhs_t_data.as_ref(), lhs_grad);
+
+            let (rhs_t_grad, result_grad) = grads.mut_and_ref_or_zeros(&rhs, &result);
+            Cpu::vv(result_grad, lhs.data(), rhs_t_grad);
+        },
+        move |lhs, rhs, result, graph| {
+            let lhs_slot = graph.slot(&lhs);
+            let rhs_slot = graph.slot(&rhs);
+            let result_slot = graph.slot(&result);
+            let result_grad: Box<[f32; N]> = graph.gradients().clone_gradient(&result_slot);
+            let rhs_t_data = graph_rhs_t_data.as_ref();
+            Cpu::vm(
+                result_grad.as_ref(),
+                rhs_t_data,
+                graph.gradients().mut_gradient(&lhs_slot),
+            );
+            Cpu::vv(
+                result_grad.as_ref(),
+                lhs.data(),
+                graph.gradients().mut_gradient(&rhs_slot),
+            );
+
+            graph.add_backward_op(move |grads| {
+                let lhs_slot_grad = grads.clone_gradient(&lhs_slot);
+                let rhs_slot_grad = grads.clone_gradient(&rhs_slot);
+                let result_slot_grad = grads.mut_gradient(&result_slot);
+                Cpu::vm_bt(
+                    lhs_slot_grad.as_ref(),
+                    graph_rhs_t_data.as_ref(),
+                    result_slot_grad,
+                );
+                Cpu::vm_bt(lhs.data(), rhs_slot_grad.as_ref(), result_slot_grad);
+                Cpu::vv(
+                    result_grad.as_ref(),
+                    lhs_slot_grad.as_ref(),
+                    grads.mut_gradient(&rhs),
+                );
+                Cpu::vm(
+                    result_grad.as_ref(),
+                    rhs_slot_grad.as_ref(),
+                    grads.mut_gradient(&lhs),
+                );
+            });
+        },
+    )
 }
 
 #[cfg(test)]
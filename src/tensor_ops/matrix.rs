@@ -0,0 +1,362 @@
+use super::utils::move_tape_and_add_backward_op;
+use crate::arrays::HasArrayType;
+use crate::devices::{Device, DeviceEinsum, DeviceMatrix};
+use crate::gradients::Tape;
+use crate::prelude::*;
+
+/// Keeps the upper triangle of each matrix in `t` (the last two axes), and sets
+/// everything else to 0. Element `[i, j]` is kept if `j - i >= offset`.
+///
+/// `offset = 0` keeps the diagonal, `offset > 0` excludes diagonals, and `offset < 0`
+/// includes diagonals below the main diagonal.
+///
+/// **Pytorch equivalent**: `t.triu(offset)`
+///
+/// **Related functions**: [tril()]
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let t: Tensor2D<3, 3> = TensorCreator::ones();
+/// let r = t.triu(1);
+/// assert_eq!(r.data(), &[[0.0, 1.0, 1.0], [0.0, 0.0, 1.0], [0.0, 0.0, 0.0]]);
+/// ```
+pub fn triu<T: Tensor<Dtype = f32>>(t: T, offset: isize) -> T
+where
+    T::Device: DeviceMatrix<T::Array>,
+{
+    triangle(t, offset, true)
+}
+
+/// Keeps the lower triangle of each matrix in `t` (the last two axes), and sets
+/// everything else to 0. Element `[i, j]` is kept if `j - i <= offset`.
+///
+/// This is useful for creating causal attention masks.
+///
+/// **Pytorch equivalent**: `t.tril(offset)`
+///
+/// **Related functions**: [triu()]
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let t = tensor([[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]);
+/// let r = t.tril(0);
+/// assert_eq!(r.data(), &[[1.0, 0.0, 0.0], [4.0, 5.0, 0.0]]);
+/// ```
+pub fn tril<T: Tensor<Dtype = f32>>(t: T, offset: isize) -> T
+where
+    T::Device: DeviceMatrix<T::Array>,
+{
+    triangle(t, offset, false)
+}
+
+fn triangle<T: Tensor<Dtype = f32>>(t: T, offset: isize, upper: bool) -> T
+where
+    T::Device: DeviceMatrix<T::Array>,
+{
+    let mut result = T::NoTape::zeros();
+    result.mut_data().clone_from(t.data());
+    T::Device::triangle(result.mut_data(), offset, upper);
+    move_tape_and_add_backward_op(t, result, move |mut t, result, grads| {
+        let (t_grad, result_grad) = grads.mut_and_ref(&t, &result);
+        t.mut_data().clone_from(result_grad);
+        T::Device::triangle(t.mut_data(), offset, upper);
+        T::Device::add(t_grad, t.data());
+    })
+}
+
+/// The diagonal of each matrix in `t`.
+///
+/// **Pytorch equivalent**: `torch.diagonal(t, dim1=-2, dim2=-1)`
+///
+/// **Related functions**: [diag_embed()], [matrix_trace()]
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let t = tensor([[1.0, 2.0], [3.0, 4.0]]);
+/// let r = t.diag();
+/// assert_eq!(r.data(), &[1.0, 4.0]);
+/// ```
+pub fn diag<T: SquareMatrices>(t: T) -> T::Vectors
+where
+    T::Device: DeviceMatrix<T::Array>,
+{
+    let mut result = <T::Vectors as Tensor>::NoTape::zeros();
+    T::Device::diag_add(t.data(), result.mut_data());
+    move_tape_and_add_backward_op(t, result, move |t, result, grads| {
+        let (t_grad, result_grad) = grads.mut_and_ref(&t, &result);
+        T::Device::diag_embed_add(result_grad, t_grad);
+    })
+}
+
+/// Vectors that can be turned into diagonal matrices with [diag_embed()]:
+/// [Tensor1D<N>] into [Tensor2D<N, N>], and [Tensor2D<B, N>] into [Tensor3D<B, N, N>].
+pub trait DiagEmbed: Tensor<Dtype = f32> {
+    type Matrices: SquareMatrices<Tape = Self::Tape, Vectors = Self>;
+}
+
+impl<const N: usize, H: Tape> DiagEmbed for Tensor1D<N, H> {
+    type Matrices = Tensor2D<N, N, H>;
+}
+
+impl<const B: usize, const N: usize, H: Tape> DiagEmbed for Tensor2D<B, N, H> {
+    type Matrices = Tensor3D<B, N, N, H>;
+}
+
+/// Creates a diagonal matrix from each vector in `t`. The inverse of [diag()].
+///
+/// **Pytorch equivalent**: `torch.diag_embed(t)`
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let t = tensor([1.0, 2.0]);
+/// let r = t.diag_embed();
+/// assert_eq!(r.data(), &[[1.0, 0.0], [0.0, 2.0]]);
+/// ```
+pub fn diag_embed<T: DiagEmbed>(t: T) -> T::Matrices
+where
+    <T::Matrices as HasDevice>::Device: DeviceMatrix<<T::Matrices as HasArrayType>::Array>,
+{
+    let mut result = <T::Matrices as Tensor>::NoTape::zeros();
+    <T::Matrices as HasDevice>::Device::diag_embed_add(t.data(), result.mut_data());
+    move_tape_and_add_backward_op(t, result, move |t, result, grads| {
+        let (t_grad, result_grad) = grads.mut_and_ref(&t, &result);
+        <T::Matrices as HasDevice>::Device::diag_add(result_grad, t_grad);
+    })
+}
+
+/// Sum of the diagonal of each matrix in `t`.
+///
+/// This is called `matrix_trace` since `trace()` starts tracking gradients of a tensor.
+///
+/// **Pytorch equivalent**: `torch.diagonal(t, dim1=-2, dim2=-1).sum(-1)`
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let t = tensor([[1.0, 2.0], [3.0, 4.0]]);
+/// let r = t.matrix_trace();
+/// assert_eq!(r.data(), &5.0);
+/// ```
+pub fn matrix_trace<T: SquareMatrices>(t: T) -> T::Scalars
+where
+    T::Device: DeviceMatrix<T::Array>,
+{
+    let mut result = <T::Scalars as Tensor>::NoTape::zeros();
+    T::Device::trace_add(t.data(), result.mut_data());
+    move_tape_and_add_backward_op(t, result, move |t, result, grads| {
+        let (t_grad, result_grad) = grads.mut_and_ref(&t, &result);
+        T::Device::trace_backward(result_grad, t_grad);
+    })
+}
+
+/// Enables concrete output types for [outer()]: vectors ([Tensor1D]) and
+/// batches of vectors ([Tensor2D]).
+pub trait OuterTyping<Rhs> {
+    type Output;
+    type Equation: EinsumEquation;
+}
+
+/// `i,j->ij`
+pub struct OuterEquation;
+impl EinsumEquation for OuterEquation {
+    const EQUATION: Equation = Equation::parse("i,j->ij");
+}
+
+/// `bi,bj->bij`
+pub struct BatchedOuterEquation;
+impl EinsumEquation for BatchedOuterEquation {
+    const EQUATION: Equation = Equation::parse("bi,bj->bij");
+}
+
+impl<const M: usize, const N: usize, H: Tape> OuterTyping<Tensor1D<N>> for Tensor1D<M, H> {
+    type Output = Tensor2D<M, N, H>;
+    type Equation = OuterEquation;
+}
+
+impl<const B: usize, const M: usize, const N: usize, H: Tape> OuterTyping<Tensor2D<B, N>>
+    for Tensor2D<B, M, H>
+{
+    type Output = Tensor3D<B, M, N, H>;
+    type Equation = BatchedOuterEquation;
+}
+
+/// Outer product of vectors `lhs` and `rhs`: `r[i, j] = lhs[i] * rhs[j]`.
+/// Also supports batches of vectors.
+///
+/// **Pytorch equivalent**: `torch.outer(lhs, rhs)`
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let a = tensor([1.0, 2.0]);
+/// let b = tensor([1.0, -1.0, 0.5]);
+/// let r = a.outer(&b);
+/// assert_eq!(r.data(), &[[1.0, -1.0, 0.5], [2.0, -2.0, 1.0]]);
+/// ```
+pub fn outer<L, R, O>(lhs: L, rhs: &R) -> O
+where
+    L: Tensor<Dtype = f32> + OuterTyping<R, Output = O>,
+    R: 'static + Tensor<Dtype = f32> + Clone,
+    O: Tensor<Dtype = f32, Tape = L::Tape>,
+    L::Device: DeviceEinsum<L::Array, R::Array, O::Array>
+        + DeviceEinsum<O::Array, R::Array, L::Array>
+        + DeviceEinsum<L::Array, O::Array, R::Array>,
+{
+    einsum2::<L::Equation, L, R, O>(lhs, rhs)
+}
+
+macro_rules! triangle_impl {
+    ($typename:ident, [$($Vs:tt),*]) => {
+impl<$(const $Vs: usize, )* H: Tape> $typename<$($Vs, )* H> {
+    /// Calls [triu()] on `self`.
+    pub fn triu(self, offset: isize) -> Self {
+        triu(self, offset)
+    }
+    /// Calls [tril()] on `self`.
+    pub fn tril(self, offset: isize) -> Self {
+        tril(self, offset)
+    }
+}
+    };
+}
+
+triangle_impl!(Tensor2D, [M, N]);
+triangle_impl!(Tensor3D, [M, N, O]);
+triangle_impl!(Tensor4D, [M, N, O, P]);
+
+macro_rules! square_impl {
+    ([$($Vs:tt),*], $TensorTy:ty) => {
+impl<$(const $Vs: usize, )* H: Tape> $TensorTy {
+    /// Calls [diag()] on `self`.
+    pub fn diag(self) -> <Self as SquareMatrices>::Vectors {
+        diag(self)
+    }
+    /// Calls [matrix_trace()] on `self`.
+    pub fn matrix_trace(self) -> <Self as SquareMatrices>::Scalars {
+        matrix_trace(self)
+    }
+}
+    };
+}
+
+square_impl!([N], Tensor2D<N, N, H>);
+square_impl!([B, N], Tensor3D<B, N, N, H>);
+
+impl<const M: usize, H: Tape> Tensor1D<M, H> {
+    /// Calls [diag_embed()] on `self`.
+    pub fn diag_embed(self) -> Tensor2D<M, M, H> {
+        diag_embed(self)
+    }
+    /// Calls [outer()] on `self`.
+    pub fn outer<const N: usize>(self, rhs: &Tensor1D<N>) -> Tensor2D<M, N, H> {
+        outer(self, rhs)
+    }
+}
+
+impl<const B: usize, const M: usize, H: Tape> Tensor2D<B, M, H> {
+    /// Calls [diag_embed()] on `self`.
+    pub fn diag_embed(self) -> Tensor3D<B, M, M, H> {
+        diag_embed(self)
+    }
+    /// Calls [outer()] on `self`.
+    pub fn outer<const N: usize>(self, rhs: &Tensor2D<B, N>) -> Tensor3D<B, M, N, H> {
+        outer(self, rhs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::assert_close;
+
+    #[test]
+    fn test_triu_tril_backward() {
+        let t = tensor([[1.0, 2.0, 3.0], [4.0, 5.0, 6.0], [7.0, 8.0, 9.0]]);
+        let r = t.trace().triu(0);
+        assert_eq!(
+            r.data(),
+            &[[1.0, 2.0, 3.0], [0.0, 5.0, 6.0], [0.0, 0.0, 9.0]]
+        );
+        let g = r.exp().sum::<_, AllAxes>().backward();
+        assert_close(
+            g.ref_gradient(&t),
+            &[
+                [1.0f32.exp(), 2.0f32.exp(), 3.0f32.exp()],
+                [0.0, 5.0f32.exp(), 6.0f32.exp()],
+                [0.0, 0.0, 9.0f32.exp()],
+            ],
+        );
+
+        let r = t.trace().tril(-1);
+        assert_eq!(
+            r.data(),
+            &[[0.0, 0.0, 0.0], [4.0, 0.0, 0.0], [7.0, 8.0, 0.0]]
+        );
+        let g = r.sum::<_, AllAxes>().backward();
+        assert_eq!(
+            g.ref_gradient(&t),
+            &[[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [1.0, 1.0, 0.0]]
+        );
+    }
+
+    #[test]
+    fn test_causal_mask_4d() {
+        let t: Tensor4D<2, 1, 2, 2> = TensorCreator::ones();
+        let r = t.tril(0);
+        assert_eq!(r.data(), &[[[[1.0, 0.0], [1.0, 1.0]]]; 2]);
+    }
+
+    #[test]
+    fn test_diag_and_diag_embed() {
+        let t: Tensor3D<2, 2, 2> = tensor([[[1.0, 2.0], [3.0, 4.0]], [[5.0, 6.0], [7.0, 8.0]]]);
+        let r = t.trace().diag();
+        assert_eq!(r.data(), &[[1.0, 4.0], [5.0, 8.0]]);
+        let g = mul(r, &tensor([[1.0, 2.0], [3.0, 4.0]]))
+            .sum::<_, AllAxes>()
+            .backward();
+        assert_eq!(
+            g.ref_gradient(&t),
+            &[[[1.0, 0.0], [0.0, 2.0]], [[3.0, 0.0], [0.0, 4.0]]]
+        );
+
+        let v = tensor([1.0, 2.0]);
+        let r = v.trace().diag_embed();
+        assert_eq!(r.data(), &[[1.0, 0.0], [0.0, 2.0]]);
+        let g = mul(r, &tensor([[1.0, 2.0], [3.0, 4.0]]))
+            .sum::<_, AllAxes>()
+            .backward();
+        assert_eq!(g.ref_gradient(&v), &[1.0, 4.0]);
+    }
+
+    #[test]
+    fn test_matrix_trace() {
+        let t: Tensor3D<2, 2, 2> = tensor([[[1.0, 2.0], [3.0, 4.0]], [[5.0, 6.0], [7.0, 8.0]]]);
+        let r = t.trace().matrix_trace();
+        assert_eq!(r.data(), &[5.0, 13.0]);
+        let g = mul(r, &tensor([1.0, -1.0])).sum().backward();
+        assert_eq!(
+            g.ref_gradient(&t),
+            &[[[1.0, 0.0], [0.0, 1.0]], [[-1.0, 0.0], [0.0, -1.0]]]
+        );
+    }
+
+    #[test]
+    fn test_outer() {
+        let a = tensor([1.0, 2.0]);
+        let b = tensor([1.0, -1.0, 0.5]);
+        let r = a.trace().outer(&b);
+        assert_eq!(r.data(), &[[1.0, -1.0, 0.5], [2.0, -2.0, 1.0]]);
+        let g = r.sum::<_, AllAxes>().backward();
+        assert_eq!(g.ref_gradient(&a), &[0.5; 2]);
+        assert_eq!(g.ref_gradient(&b), &[3.0; 3]);
+
+        let a: Tensor2D<2, 2> = tensor([[1.0, 2.0], [3.0, 4.0]]);
+        let b: Tensor2D<2, 1> = tensor([[2.0], [-1.0]]);
+        let r: Tensor3D<2, 2, 1> = a.outer(&b);
+        assert_eq!(r.data(), &[[[2.0], [4.0]], [[-3.0], [-4.0]]]);
+    }
+}
@@ -49,6 +49,7 @@
 //! - [max()]
 //! - [mean()]
 //! - [min()]
+//! - [prod()]
 //! - [sum()]
 //! - [var()]
 //! - [stddev()]
@@ -131,12 +132,27 @@
 //! let b: Tensor2D<2, 2> = t.select(&[[0, 2], [1, 1]]); // select multiple from the last axis
 //! assert_eq!(b.data(), &[[1.0, 3.0], [5.0, 5.0]]);
 //! ```
+//!
+//! # Custom operations
+//!
+//! New differentiable operations can be added outside of this crate by implementing
+//! [CustomOp] (or [CustomBinaryOp]), which only need to compute the output array
+//! and the gradients of the inputs. [custom_op()] and [custom_binary_op()] take care of moving the
+//! tape and registering the backward operation.
 
+mod allclose;
 mod arith_scalar;
+mod custom_op;
+mod einsum;
+mod fft;
+mod hook;
 mod impl_add;
+mod impl_atan2;
 mod impl_backward;
+mod impl_broadcast_binary;
 mod impl_broadcast_reduce;
 mod impl_clamp;
+mod impl_cumulative;
 mod impl_div;
 mod impl_dropout;
 mod impl_mask;
@@ -147,23 +163,41 @@ mod impl_min;
 mod impl_minimum;
 mod impl_mul;
 mod impl_nans;
+mod impl_norm;
 mod impl_normalize;
 mod impl_pow;
+mod impl_prod;
+mod impl_repeat;
 mod impl_softmax;
 mod impl_stddev;
 mod impl_sub;
 mod impl_sum;
+mod interpolate;
+mod jacobian;
+mod jvp;
+mod linalg;
 mod map;
 mod matmul;
+mod matrix;
 mod permute;
+mod sample;
 mod select;
+mod sparse;
 pub(crate) mod utils;
 
+pub use allclose::*;
 pub use arith_scalar::*;
+pub use custom_op::*;
+pub use einsum::*;
+pub use fft::*;
+pub use hook::*;
 pub use impl_add::*;
+pub use impl_atan2::*;
 pub use impl_backward::*;
+pub use impl_broadcast_binary::*;
 pub use impl_broadcast_reduce::*;
 pub use impl_clamp::*;
+pub use impl_cumulative::*;
 pub use impl_div::*;
 pub use impl_dropout::*;
 pub use impl_mask::*;
@@ -174,16 +208,26 @@ pub use impl_min::*;
 pub use impl_minimum::*;
 pub use impl_mul::*;
 pub use impl_nans::*;
+pub use impl_norm::*;
 pub use impl_normalize::*;
 pub use impl_pow::*;
+pub use impl_prod::*;
+pub use impl_repeat::*;
 pub use impl_softmax::*;
 pub use impl_stddev::*;
 pub use impl_sub::*;
 pub use impl_sum::*;
+pub use interpolate::*;
+pub use jacobian::*;
+pub use jvp::*;
+pub use linalg::*;
 pub use map::*;
 pub use matmul::*;
+pub use matrix::*;
 pub use permute::*;
+pub use sample::*;
 pub use select::SelectTo;
+pub use sparse::*;
 
 #[cfg(feature = "nightly")]
 mod impl_reshape;
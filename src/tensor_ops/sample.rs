@@ -0,0 +1,451 @@
+use crate::arrays::{CountElements, HasShape, ZeroElements};
+use crate::devices::{as_slice, DeviceReduce, EqAccum, ForEachElement, MaxAccum};
+use crate::gradients::Tape;
+use crate::prelude::*;
+use rand::Rng;
+use rand_distr::Gumbel;
+
+/// Sample `Indices` from the categorical distributions along `Axes` of `Self`.
+///
+/// `Indices` either has one index per distribution, which is the shape of `Self` with `Axes`
+/// removed (e.g. `[usize; M]` for `Axis<1>` of a `Tensor2D<M, N>`, the same type [SelectTo]
+/// uses to select a single element from that axis), or `Z` indices per distribution, which is
+/// the shape of `Self` with `Axes` replaced by `Z` (e.g. `[[usize; Z]; M]` for `Axis<1>`, or
+/// `[[usize; N]; Z]` for `Axis<0>`).
+///
+/// This trait can't be used directly as it only contains the index of the axis. Instead
+/// it is used by methods to specify the input type must be able to be sampled into `Indices`.
+pub trait SampleTo<Indices, Axes>: Tensor<Dtype = f32> {
+    /// The index of the axis that is sampled along.
+    const AXIS: usize;
+}
+
+macro_rules! impl_sample {
+    ($Axis:expr, $SrcTy:ty, $IndTy:ty, {$($Dims:tt),*}) => {
+impl<$(const $Dims: usize, )* H: Tape> SampleTo<$IndTy, Axis<$Axis>> for $SrcTy {
+    const AXIS: usize = $Axis;
+}
+    };
+}
+
+// 1d
+impl_sample!(0, Tensor1D<M, H>, usize, { M });
+impl_sample!(0, Tensor1D<M, H>, [usize; Z], {M, Z});
+
+// 2d
+impl_sample!(0, Tensor2D<M, N, H>, [usize; N], {M, N});
+impl_sample!(0, Tensor2D<M, N, H>, [[usize; N]; Z], {M, N, Z});
+impl_sample!(1, Tensor2D<M, N, H>, [usize; M], {M, N});
+impl_sample!(1, Tensor2D<M, N, H>, [[usize; Z]; M], {M, N, Z});
+
+// 3d
+impl_sample!(0, Tensor3D<M, N, O, H>, [[usize; O]; N], {M, N, O});
+impl_sample!(0, Tensor3D<M, N, O, H>, [[[usize; O]; N]; Z], {M, N, O, Z});
+impl_sample!(1, Tensor3D<M, N, O, H>, [[usize; O]; M], {M, N, O});
+impl_sample!(1, Tensor3D<M, N, O, H>, [[[usize; O]; Z]; M], {M, N, O, Z});
+impl_sample!(2, Tensor3D<M, N, O, H>, [[usize; N]; M], {M, N, O});
+impl_sample!(2, Tensor3D<M, N, O, H>, [[[usize; Z]; N]; M], {M, N, O, Z});
+
+// 4d
+impl_sample!(0, Tensor4D<M, N, O, P, H>, [[[usize; P]; O]; N], {M, N, O, P});
+impl_sample!(0, Tensor4D<M, N, O, P, H>, [[[[usize; P]; O]; N]; Z], {M, N, O, P, Z});
+impl_sample!(1, Tensor4D<M, N, O, P, H>, [[[usize; P]; O]; M], {M, N, O, P});
+impl_sample!(1, Tensor4D<M, N, O, P, H>, [[[[usize; P]; O]; Z]; M], {M, N, O, P, Z});
+impl_sample!(2, Tensor4D<M, N, O, P, H>, [[[usize; P]; N]; M], {M, N, O, P});
+impl_sample!(2, Tensor4D<M, N, O, P, H>, [[[[usize; P]; Z]; N]; M], {M, N, O, P, Z});
+impl_sample!(3, Tensor4D<M, N, O, P, H>, [[[usize; O]; N]; M], {M, N, O, P});
+impl_sample!(3, Tensor4D<M, N, O, P, H>, [[[[usize; Z]; O]; N]; M], {M, N, O, P, Z});
+
+/// Samples indices (with replacement) from the categorical distributions along `Axes`
+/// of `probs`. The probabilities must be non-negative, but don't need to sum to `1.0`.
+///
+/// See [SampleTo] for the possible index types. `Axes` is inferred from the index type,
+/// unless it fits more than one axis (e.g. because the tensor has equal dimensions).
+///
+/// **Pytorch equivalent**: `torch.multinomial(probs, Z, replacement=True)`
+///
+/// **Related functions**: [sample_categorical_logits()], [multinomial()], [SelectTo]
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// # use rand::prelude::*;
+/// let mut rng = StdRng::seed_from_u64(0);
+/// let probs = tensor([[0.0, 1.0, 0.0], [0.0, 0.0, 2.0]]);
+/// let a: [usize; 2] = probs.sample_categorical(&mut rng);
+/// assert_eq!(a, [1, 2]);
+/// let a: [[usize; 3]; 2] = probs.sample_categorical::<Axis<1>, _, _>(&mut rng);
+/// assert_eq!(a, [[1, 1, 1], [2, 2, 2]]);
+/// ```
+///
+/// Sampling along the first axis:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// # use rand::prelude::*;
+/// # let mut rng = StdRng::seed_from_u64(0);
+/// let probs = tensor([[0.0, 1.0, 0.0], [1.0, 0.0, 2.0]]);
+/// let a: [usize; 3] = probs.sample_categorical(&mut rng);
+/// assert_eq!(a, [1, 0, 1]);
+/// ```
+pub fn sample_categorical<Axes, T: SampleTo<I, Axes>, I, R: Rng>(probs: &T, rng: &mut R) -> I
+where
+    I: CountElements<Dtype = usize> + ZeroElements,
+{
+    sample_rows::<Axes, _, _, _>(probs, |row, out| {
+        for i in out.iter_mut() {
+            *i = sample_index(row, rng);
+        }
+    })
+}
+
+/// Same as [sample_categorical()], but the distributions are given as unnormalized log
+/// probabilities (e.g. the output of a policy network). This is equivalent to sampling
+/// from `logits.softmax()`.
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// # use rand::prelude::*;
+/// let mut rng = StdRng::seed_from_u64(0);
+/// let logits = tensor([[0.0, f32::NEG_INFINITY], [f32::NEG_INFINITY, 100.0]]);
+/// let a: [usize; 2] = logits.sample_categorical_logits::<Axis<1>, _, _>(&mut rng);
+/// assert_eq!(a, [0, 1]);
+/// ```
+pub fn sample_categorical_logits<Axes, T: SampleTo<I, Axes>, I, R: Rng>(
+    logits: &T,
+    rng: &mut R,
+) -> I
+where
+    I: CountElements<Dtype = usize> + ZeroElements,
+{
+    let mut probs = Vec::new();
+    sample_rows::<Axes, _, _, _>(logits, |row, out| {
+        let max = row.iter().cloned().fold(f32::NEG_INFINITY, f32::max);
+        probs.clear();
+        probs.extend(row.iter().map(|x| (x - max).exp()));
+        for i in out.iter_mut() {
+            *i = sample_index(&probs, rng);
+        }
+    })
+}
+
+/// Samples distinct indices (without replacement) from the categorical distributions along
+/// `Axes` of `probs`. After each index is sampled, its probability is set to `0.0`
+/// and the remaining probabilities are renormalized.
+///
+/// Panics if a distribution has fewer non-zero probabilities than the number of indices to sample.
+///
+/// **Pytorch equivalent**: `torch.multinomial(probs, Z, replacement=False)`
+///
+/// **Related functions**: [sample_categorical()]
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// # use rand::prelude::*;
+/// let mut rng = StdRng::seed_from_u64(0);
+/// let probs = tensor([0.0, 0.5, 0.0, 0.5]);
+/// let mut a: [usize; 2] = probs.multinomial(&mut rng);
+/// a.sort();
+/// assert_eq!(a, [1, 3]);
+/// ```
+pub fn multinomial<Axes, T: SampleTo<I, Axes>, I, R: Rng>(probs: &T, rng: &mut R) -> I
+where
+    I: CountElements<Dtype = usize> + ZeroElements,
+{
+    let mut remaining = Vec::new();
+    sample_rows::<Axes, _, _, _>(probs, |row, out| {
+        remaining.clear();
+        remaining.extend_from_slice(row);
+        for i in out.iter_mut() {
+            *i = sample_index(&remaining, rng);
+            remaining[*i] = 0.0;
+        }
+    })
+}
+
+/// Calls `f` with every distribution along `Axes` of `t`, and the indices
+/// sampled for it.
+fn sample_rows<Axes, T, I, F>(t: &T, mut f: F) -> I
+where
+    T: SampleTo<I, Axes>,
+    I: CountElements<Dtype = usize> + ZeroElements,
+    F: FnMut(&[f32], &mut [usize]),
+{
+    let shape = <T::Array as HasShape>::SHAPE;
+    let size = shape[T::AXIS];
+    let stride: usize = shape[T::AXIS + 1..].iter().product();
+    let num_rows = T::Array::NUM_ELEMENTS / size;
+    let num_samples = I::NUM_ELEMENTS / num_rows;
+    let mut indices = I::ZEROS;
+    // SAFETY: `I` is an nd array of usize with `I::NUM_ELEMENTS` elements
+    let out = unsafe {
+        std::slice::from_raw_parts_mut(&mut indices as *mut I as *mut usize, I::NUM_ELEMENTS)
+    };
+    let data = as_slice(t.data());
+    let mut row = vec![0.0; size];
+    let mut samples = vec![0; num_samples];
+    for (data, out) in data
+        .chunks_exact(size * stride)
+        .zip(out.chunks_exact_mut(num_samples * stride))
+    {
+        for inner in 0..stride {
+            for (k, x) in row.iter_mut().enumerate() {
+                *x = data[k * stride + inner];
+            }
+            f(&row, &mut samples);
+            for (z, &i) in samples.iter().enumerate() {
+                out[z * stride + inner] = i;
+            }
+        }
+    }
+    indices
+}
+
+/// Samples an index of `probs` with probability proportional to its value.
+fn sample_index<R: Rng>(probs: &[f32], rng: &mut R) -> usize {
+    let mut total = 0.0;
+    for &p in probs.iter() {
+        assert!(p >= 0.0, "Probabilities must be non-negative, found {}", p);
+        total += p;
+    }
+    assert!(
+        total > 0.0 && total.is_finite(),
+        "Can't sample from probabilities with a sum of {}",
+        total
+    );
+    let mut u = rng.gen::<f32>() * total;
+    let mut last = 0;
+    for (i, &p) in probs.iter().enumerate() {
+        if p > 0.0 {
+            if u < p {
+                return i;
+            }
+            u -= p;
+            last = i;
+        }
+    }
+    // only reachable due to floating point error in `total`
+    last
+}
+
+/// Samples from the [Gumbel-Softmax](https://arxiv.org/abs/1611.01144) distribution along `Axes`:
+/// `softmax((logits + g) / temperature)` where `g` is sampled from the standard Gumbel distribution.
+/// This is a differentiable approximation of sampling one-hot vectors from the categorical
+/// distribution defined by `logits`, which gets closer to one-hot as `temperature` goes to `0.0`.
+///
+/// If `hard` is true, the result is the one-hot vector of the largest value, but the
+/// gradient is that of the soft sample (the straight-through estimator).
+///
+/// **Pytorch equivalent**: `torch.nn.functional.gumbel_softmax(logits, temperature, hard, dim=Axes)`
+///
+/// **Related functions**: [softmax()], [sample_categorical_logits()]
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// # use rand::prelude::*;
+/// let mut rng = StdRng::seed_from_u64(0);
+/// let logits: Tensor2D<2, 3> = TensorCreator::zeros();
+/// let soft = logits.clone().gumbel_softmax::<Axis<1>, _>(0.5, false, &mut rng);
+/// let hard = logits.gumbel_softmax::<Axis<1>, _>(0.5, true, &mut rng);
+/// for row in hard.data() {
+///     assert_eq!(row.iter().sum::<f32>(), 1.0);
+///     assert!(row.iter().all(|&x| x == 0.0 || x == 1.0));
+/// }
+/// ```
+pub fn gumbel_softmax<T: Reduce<Axes>, Axes, R: Rng>(
+    logits: T,
+    temperature: f32,
+    hard: bool,
+    rng: &mut R,
+) -> T {
+    let gumbel = Gumbel::new(0.0, 1.0).unwrap();
+    let mut noise = T::NoTape::zeros();
+    T::Device::foreach_m(noise.mut_data(), &mut |x| *x = rng.sample(gumbel));
+    let y = softmax::<_, Axes>(div_scalar(add(logits, &noise), temperature));
+    if !hard {
+        return y;
+    }
+
+    // one_hot(y) - y, so that y + (one_hot(y) - y) has the gradient of y
+    let max = T::DeviceR::reduce::<MaxAccum>(y.data());
+    let mut delta = T::NoTape::zeros();
+    delta.mut_data().clone_from(y.data());
+    T::DeviceR::broadcast_into_no_reset::<EqAccum>(delta.mut_data(), max.as_ref());
+    T::Device::foreach_mr(delta.mut_data(), y.data(), &mut |d, y| *d -= y);
+    add(y, &delta)
+}
+
+macro_rules! tensor_impl {
+    ($typename:ident, [$($Vs:tt),*]) => {
+impl<$(const $Vs: usize, )* H: Tape> $typename<$($Vs, )* H> {
+    /// Calls [sample_categorical()] on `self` with `Axes`.
+    pub fn sample_categorical<Axes, I, R: Rng>(&self, rng: &mut R) -> I
+    where
+        Self: SampleTo<I, Axes>,
+        I: CountElements<Dtype = usize> + ZeroElements,
+    {
+        sample_categorical(self, rng)
+    }
+    /// Calls [sample_categorical_logits()] on `self` with `Axes`.
+    pub fn sample_categorical_logits<Axes, I, R: Rng>(&self, rng: &mut R) -> I
+    where
+        Self: SampleTo<I, Axes>,
+        I: CountElements<Dtype = usize> + ZeroElements,
+    {
+        sample_categorical_logits(self, rng)
+    }
+    /// Calls [multinomial()] on `self` with `Axes`.
+    pub fn multinomial<Axes, I, R: Rng>(&self, rng: &mut R) -> I
+    where
+        Self: SampleTo<I, Axes>,
+        I: CountElements<Dtype = usize> + ZeroElements,
+    {
+        multinomial(self, rng)
+    }
+    /// Calls [gumbel_softmax()] on `self` with `Axes`.
+    pub fn gumbel_softmax<Axes, R: Rng>(self, temperature: f32, hard: bool, rng: &mut R) -> Self
+    where
+        Self: Reduce<Axes>,
+    {
+        gumbel_softmax(self, temperature, hard, rng)
+    }
+}
+    };
+}
+
+tensor_impl!(Tensor1D, [M]);
+tensor_impl!(Tensor2D, [M, N]);
+tensor_impl!(Tensor3D, [M, N, O]);
+tensor_impl!(Tensor4D, [M, N, O, P]);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arrays::AllAxes;
+    use crate::tests::{assert_close, AssertClose};
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn test_sample_categorical_frequencies() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let probs = tensor([0.1, 0.2, 0.3, 0.4]);
+        let samples: [usize; 10000] = probs.sample_categorical(&mut rng);
+        let mut counts = [0.0; 4];
+        for i in samples {
+            counts[i] += 1.0 / 10000.0;
+        }
+        counts.assert_close(&[0.1, 0.2, 0.3, 0.4], 1e-2);
+    }
+
+    #[test]
+    fn test_sample_categorical_logits_matches_softmax() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let logits = tensor([[1.0, 2.0, 3.0], [-1000.0, 0.0, -1000.0]]);
+        let samples: [[usize; 10000]; 2] = logits.sample_categorical_logits(&mut rng);
+        let probs = logits.softmax::<Axis<1>>();
+        let mut counts = [0.0; 3];
+        for &i in samples[0].iter() {
+            counts[i] += 1.0 / 10000.0;
+        }
+        counts.assert_close(&probs.data()[0], 1e-2);
+        assert!(samples[1].iter().all(|&i| i == 1));
+    }
+
+    #[test]
+    fn test_sample_categorical_3d() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut probs: Tensor3D<2, 3, 4> = TensorCreator::zeros();
+        probs.mut_data()[0][1][3] = 1.0;
+        probs.mut_data()[1][2][2] = 1.0;
+        for row in probs.mut_data().iter_mut().flatten() {
+            row[0] += 1e-30;
+        }
+        let a: [[usize; 3]; 2] = probs.sample_categorical(&mut rng);
+        assert_eq!(a, [[0, 3, 0], [0, 0, 2]]);
+    }
+
+    #[test]
+    fn test_sample_categorical_axes() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut probs: Tensor3D<2, 3, 4> = TensorCreator::zeros();
+        for o in 0..2 {
+            for s in 0..4 {
+                probs.mut_data()[o][(o + s) % 3][s] = 1.0;
+            }
+        }
+        let a: [[usize; 4]; 2] = probs.sample_categorical(&mut rng);
+        let b: [[[usize; 4]; 5]; 2] = probs.sample_categorical(&mut rng);
+        for o in 0..2 {
+            for s in 0..4 {
+                assert_eq!(a[o][s], (o + s) % 3);
+                assert!(b[o].iter().all(|z| z[s] == (o + s) % 3));
+            }
+        }
+
+        let probs = tensor([[1.0, 0.0], [0.0, 0.0], [0.0, 1.0]]);
+        let a: [[usize; 2]; 4] = probs.sample_categorical(&mut rng);
+        assert_eq!(a, [[0, 2]; 4]);
+    }
+
+    #[test]
+    #[should_panic = "Probabilities must be non-negative, found -1"]
+    fn test_sample_negative_probability() {
+        let probs = tensor([2.0, -1.0, 1.0]);
+        let _: usize = probs.sample_categorical(&mut StdRng::seed_from_u64(0));
+    }
+
+    #[test]
+    fn test_multinomial_without_replacement() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let probs = tensor([[0.1, 0.2, 0.3, 0.4], [1.0, 0.0, 1.0, 1.0]]);
+        for _ in 0..100 {
+            let [mut a, mut b]: [[usize; 3]; 2] = probs.multinomial(&mut rng);
+            a.sort_unstable();
+            assert!(a[0] < a[1] && a[1] < a[2]);
+            b.sort_unstable();
+            assert_eq!(b, [0, 2, 3]);
+        }
+    }
+
+    #[test]
+    #[should_panic = "Can't sample from probabilities with a sum of 0"]
+    fn test_multinomial_too_many_samples() {
+        let probs = tensor([1.0, 0.0, 1.0]);
+        let _: [usize; 3] = probs.multinomial(&mut StdRng::seed_from_u64(0));
+    }
+
+    #[test]
+    fn test_gumbel_softmax_soft() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let logits = tensor([[1.0, 2.0, 3.0], [0.0, 0.0, 0.0]]);
+        let r = logits
+            .trace()
+            .gumbel_softmax::<Axis<1>, _>(1.0, false, &mut rng);
+        let sums: Tensor1D<2> = r.duplicate().sum();
+        assert_close(sums.data(), &[1.0, 1.0]);
+        // gradients of a sum of softmax are 0
+        let g = r.sum::<_, AllAxes>().backward();
+        assert_close(g.ref_gradient(&logits), &[[0.0; 3]; 2]);
+    }
+
+    #[test]
+    fn test_gumbel_softmax_hard() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let logits = tensor([[0.0, 0.0, 100.0], [-100.0, 0.0, -100.0]]);
+        let w = tensor([[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]);
+        let r = logits
+            .trace()
+            .gumbel_softmax::<Axis<1>, _>(1.0, true, &mut rng);
+        assert_eq!(r.data(), &[[0.0, 0.0, 1.0], [0.0, 1.0, 0.0]]);
+        let g = mul(r, &w).sum::<_, AllAxes>().backward();
+
+        let mut rng = StdRng::seed_from_u64(0);
+        let soft = logits
+            .trace()
+            .gumbel_softmax::<Axis<1>, _>(1.0, false, &mut rng);
+        let g_soft = mul(soft, &w).sum::<_, AllAxes>().backward();
+        assert_eq!(g.ref_gradient(&logits), g_soft.ref_gradient(&logits));
+    }
+}
@@ -0,0 +1,230 @@
+use super::utils::{move_tape_and_add_backward_binop, move_tape_and_add_backward_op};
+use crate::gradients::Tape;
+use crate::prelude::*;
+use std::rc::Rc;
+
+/// Matrix multiplication of a sparse `lhs` with a dense `rhs`. Only the stored entries
+/// of `lhs` are used, so this is `O(Z * O)` instead of `O(M * N * O)`.
+///
+/// Gradients flow into both the values of `lhs` and `rhs`. Like other binary operations,
+/// the tape is taken from `lhs`. If `rhs` is the one with the tape, you can move it over:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let adj: CooTensor2D<3, 3, 2> = CooTensor2D::new([(0, 1), (2, 0)], tensor([1.0, 0.5]));
+/// let x: Tensor2D<3, 2, OwnedTape> = Tensor2D::ones().traced();
+/// let (x, tape) = x.split_tape();
+/// let r: Tensor2D<3, 2, OwnedTape> = sparse_matmul(adj.put_tape(tape), &x);
+/// assert_eq!(r.data(), &[[1.0, 1.0], [0.0, 0.0], [0.5, 0.5]]);
+/// ```
+pub fn sparse_matmul<const M: usize, const N: usize, const Z: usize, const O: usize, H: Tape>(
+    lhs: CooTensor2D<M, N, Z, H>,
+    rhs: &Tensor2D<N, O>,
+) -> Tensor2D<M, O, H> {
+    let indices = lhs.indices;
+    let mut result: Tensor2D<M, O> = TensorCreator::zeros();
+    let out = result.mut_data();
+    for (&(i, j), v) in indices.iter().zip(lhs.values.data().iter()) {
+        for (o, r) in out[i].iter_mut().zip(rhs.data()[j].iter()) {
+            *o += v * r;
+        }
+    }
+
+    let rhs_ = rhs.clone();
+    move_tape_and_add_backward_binop::<_, _, Tensor2D<M, O, H>, _>(
+        lhs.values,
+        rhs,
+        result,
+        move |values, rhs, result, grads| {
+            let (values_grad, result_grad) = grads.mut_and_ref(&values, &result);
+            for (g, &(i, j)) in values_grad.iter_mut().zip(indices.iter()) {
+                *g += result_grad[i]
+                    .iter()
+                    .zip(rhs_.data()[j].iter())
+                    .map(|(a, b)| a * b)
+                    .sum::<f32>();
+            }
+
+            let (rhs_grad, result_grad) = grads.mut_and_ref(&rhs, &result);
+            for (&(i, j), v) in indices.iter().zip(values.data().iter()) {
+                for (g, r) in rhs_grad[j].iter_mut().zip(result_grad[i].iter()) {
+                    *g += v * r;
+                }
+            }
+        },
+    )
+}
+
+/// Converts a dense tensor into a [CooTensor2D] with `Z` stored entries, keeping
+/// the nonzero elements in row major order.
+///
+/// If there are fewer than `Z` nonzero elements, the remaining entries are zeros stored at `(0, 0)`.
+/// Gradients of the nonzero values flow back into `t`, and those of the remaining entries are ignored.
+///
+/// **Panics** if `t` has more than `Z` nonzero elements.
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let t = tensor([[0.0, 2.0], [-1.0, 0.0]]);
+/// let s: CooTensor2D<2, 2, 3> = t.to_sparse();
+/// assert_eq!(s.indices(), &[(0, 1), (1, 0), (0, 0)]);
+/// assert_eq!(s.values().data(), &[2.0, -1.0, 0.0]);
+/// ```
+pub fn to_sparse<const M: usize, const N: usize, const Z: usize, H: Tape>(
+    t: Tensor2D<M, N, H>,
+) -> CooTensor2D<M, N, Z, H> {
+    let mut indices = [(0, 0); Z];
+    let mut values: Tensor1D<Z> = TensorCreator::zeros();
+    let mut num_nonzero = 0;
+    for (i, row) in t.data().iter().enumerate() {
+        for (j, &x) in row.iter().enumerate() {
+            if x != 0.0 {
+                assert!(
+                    num_nonzero < Z,
+                    "Can't fit more than {} nonzero elements into a sparse tensor with {} entries",
+                    Z,
+                    Z
+                );
+                indices[num_nonzero] = (i, j);
+                values.mut_data()[num_nonzero] = x;
+                num_nonzero += 1;
+            }
+        }
+    }
+
+    let indices = Rc::new(indices);
+    let indices_ = indices.clone();
+    let values = move_tape_and_add_backward_op::<_, Tensor1D<Z, H>, _>(
+        t,
+        values,
+        move |t, values, grads| {
+            let (t_grad, values_grad) = grads.mut_and_ref(&t, &values);
+            for (&(i, j), g) in indices_[..num_nonzero].iter().zip(values_grad.iter()) {
+                t_grad[i][j] += g;
+            }
+        },
+    );
+    CooTensor2D { indices, values }
+}
+
+/// Converts a [CooTensor2D] into a dense [Tensor2D], summing duplicate entries.
+/// Gradients flow back into the values of `s`.
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let s: CooTensor2D<2, 3, 3> = CooTensor2D::new([(0, 2), (1, 0), (0, 2)], tensor([1.0, 2.0, 3.0]));
+/// assert_eq!(s.to_dense().data(), &[[0.0, 0.0, 4.0], [2.0, 0.0, 0.0]]);
+/// ```
+pub fn to_dense<const M: usize, const N: usize, const Z: usize, H: Tape>(
+    s: CooTensor2D<M, N, Z, H>,
+) -> Tensor2D<M, N, H> {
+    let indices = s.indices;
+    let mut result: Tensor2D<M, N> = TensorCreator::zeros();
+    let out = result.mut_data();
+    for (&(i, j), v) in indices.iter().zip(s.values.data().iter()) {
+        out[i][j] += v;
+    }
+
+    move_tape_and_add_backward_op::<_, Tensor2D<M, N, H>, _>(
+        s.values,
+        result,
+        move |values, result, grads| {
+            let (values_grad, result_grad) = grads.mut_and_ref(&values, &result);
+            for (g, &(i, j)) in values_grad.iter_mut().zip(indices.iter()) {
+                *g += result_grad[i][j];
+            }
+        },
+    )
+}
+
+impl<const M: usize, const N: usize, const Z: usize, H: Tape> CooTensor2D<M, N, Z, H> {
+    /// Calls [sparse_matmul()] on `self`.
+    pub fn sparse_matmul<const O: usize>(self, rhs: &Tensor2D<N, O>) -> Tensor2D<M, O, H> {
+        sparse_matmul(self, rhs)
+    }
+
+    /// Calls [to_dense()] on `self`.
+    pub fn to_dense(self) -> Tensor2D<M, N, H> {
+        to_dense(self)
+    }
+}
+
+impl<const M: usize, const N: usize, H: Tape> Tensor2D<M, N, H> {
+    /// Calls [to_sparse()] on `self`.
+    pub fn to_sparse<const Z: usize>(self) -> CooTensor2D<M, N, Z, H> {
+        to_sparse(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arrays::AllAxes;
+
+    #[test]
+    fn test_sparse_matmul_matches_dense() {
+        let s: CooTensor2D<2, 3, 4> = CooTensor2D::new(
+            [(0, 0), (0, 2), (1, 1), (0, 2)],
+            tensor([1.0, -2.0, 0.5, 3.0]),
+        );
+        let x = tensor([[1.0, 2.0], [3.0, 4.0], [5.0, 6.0]]);
+
+        let r = sparse_matmul(s.trace(), &x);
+        assert_eq!(r.data(), &[[6.0, 8.0], [1.5, 2.0]]);
+        let g = r.exp().mean::<_, AllAxes>().backward();
+
+        let d = matmul(s.trace().to_dense(), &x);
+        let dg = d.exp().mean::<_, AllAxes>().backward();
+        assert_tensor_close!(g.ref_gradient(&x), dg.ref_gradient(&x));
+        assert_tensor_close!(g.ref_gradient(s.values()), dg.ref_gradient(s.values()));
+    }
+
+    #[test]
+    fn test_sparse_matmul_tape_from_rhs() {
+        let s: CooTensor2D<2, 2, 2> = CooTensor2D::new([(0, 1), (1, 1)], tensor([2.0, 3.0]));
+        let x: Tensor2D<2, 1> = tensor([[1.0], [-1.0]]);
+        let values = s.values().duplicate();
+        let (x_, tape) = x.trace().split_tape();
+        let r = sparse_matmul(s.put_tape(tape), &x_);
+        assert_eq!(r.data(), &[[-2.0], [-3.0]]);
+        let g = r.sum::<_, AllAxes>().backward();
+        assert_eq!(g.ref_gradient(&x), &[[0.0], [5.0]]);
+        assert_eq!(g.ref_gradient(&values), &[-1.0, -1.0]);
+    }
+
+    #[test]
+    fn test_to_sparse_to_dense() {
+        let t = tensor([[0.0, 1.0, 0.0], [2.0, 0.0, 3.0]]);
+        let s: CooTensor2D<2, 3, 4, OwnedTape> = t.trace().to_sparse();
+        assert_eq!(s.indices(), &[(0, 1), (1, 0), (1, 2), (0, 0)]);
+        assert_eq!(s.values().data(), &[1.0, 2.0, 3.0, 0.0]);
+        let d = s.to_dense();
+        assert_eq!(d.data(), t.data());
+        let g = (d * 2.0).sum::<_, AllAxes>().backward();
+        // only the nonzero entries get gradients, not the padding at (0, 0)
+        assert_eq!(g.ref_gradient(&t), &[[0.0, 2.0, 0.0], [2.0, 0.0, 2.0]]);
+    }
+
+    #[test]
+    fn test_to_sparse_padding_gradients() {
+        let t = tensor([[0.0, 0.0], [0.0, 5.0]]);
+        let s: CooTensor2D<2, 2, 4, OwnedTape> = t.trace().to_sparse();
+        assert_eq!(s.indices(), &[(1, 1), (0, 0), (0, 0), (0, 0)]);
+        let w = tensor([1.0, 2.0, 3.0, 4.0]);
+        let g = mul(s.values, &w).sum().backward();
+        assert_eq!(g.ref_gradient(&t), &[[0.0, 0.0], [0.0, 1.0]]);
+    }
+
+    #[test]
+    #[should_panic = "Can't fit more than 1 nonzero elements into a sparse tensor with 1 entries"]
+    fn test_to_sparse_too_many_nonzeros() {
+        let _: CooTensor2D<1, 2, 1> = tensor([[1.0, 2.0]]).to_sparse();
+    }
+
+    #[test]
+    #[should_panic = "Index (2, 0) is out of bounds for a 2x2 sparse tensor"]
+    fn test_sparse_out_of_bounds() {
+        let _: CooTensor2D<2, 2, 1> = CooTensor2D::new([(2, 0)], tensor([1.0]));
+    }
+}
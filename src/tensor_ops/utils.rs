@@ -7,48 +7,107 @@
 //! 4. You can't really separate these operations since they are very inter-dependent. So it makes
 //!    sense to have a single unit for doing it.
 
-use crate::devices::{AllocateZeros, Device, ForEachElement};
-use crate::gradients::{Gradients, Tape};
+use crate::arrays::HasArrayType;
+use crate::devices::{as_mut_slice, as_slice, AllocateZeros, Device, ForEachElement};
+use crate::gradients::{GradientGraph, Gradients, Tape};
 use crate::prelude::*;
 
 /// `f(t)`. Applies a function `f` to every element of the [Tensor]. The derivative
-/// `df` must also be provided.
+/// `df` must also be provided, as well as the second derivative `d2f`, which is only
+/// used for higher order gradients (see [GradientGraph]).
 ///
 /// This is primarily used to implement standard functions such as [relu()], [exp()], etc.
 /// But users can also implement their own activations with this.
-pub(crate) fn map<T: Tensor<Dtype = f32>, F, Df>(t: T, f: F, mut df: Df) -> T
+pub(crate) fn map<T: Tensor<Dtype = f32>, F, Df, D2f>(t: T, f: F, mut df: Df, d2f: D2f) -> T
 where
     F: 'static + FnMut(&f32) -> f32,
-    Df: 'static + FnMut(&f32) -> f32,
+    Df: 'static + Clone + FnMut(&f32) -> f32,
+    D2f: 'static + FnMut(&f32) -> f32,
 {
     let result = T::NoTape::new_boxed(T::Device::map(t.data(), f));
-    move_tape_and_add_backward_op(t, result, move |t, result, grads| {
-        let (t_grad, result_grad) = grads.mut_and_ref(&t, &result);
-        T::Device::foreach_mrr(t_grad, t.data(), result_grad, &mut |g, t, r| {
-            *g += df(t) * r;
-        });
-    })
+    let graph_df = df.clone();
+    move_tape_and_add_backward_op_with_graph(
+        t,
+        result,
+        move |t, result, grads| {
+            let (t_grad, result_grad) = grads.mut_and_ref_or_zeros(&t, &result);
+            T::Device::foreach_mrr(t_grad, t.data(), result_grad, &mut |g, t, r| {
+                *g += df(t) * r;
+            });
+        },
+        move |t, result, graph| map_graph_op(t, result, graph, graph_df, d2f),
+    )
 }
 
-/// Same as [map()], but calls `df` with the result of `f(x)`. This can potentially remove an allocation.
-pub(crate) fn map_df_uses_fx<T: Tensor<Dtype = f32>, F, Df>(mut t: T, mut f: F, mut df: Df) -> T
+/// Same as [map()], but calls `df` and `d2f` with the result of `f(x)`. This can potentially remove an allocation.
+pub(crate) fn map_df_uses_fx<T: Tensor<Dtype = f32>, F, Df, D2f>(
+    mut t: T,
+    mut f: F,
+    mut df: Df,
+    d2f: D2f,
+) -> T
 where
     F: FnMut(&f32) -> f32,
-    Df: 'static + FnMut(&f32) -> f32,
+    Df: 'static + Clone + FnMut(&f32) -> f32,
+    D2f: 'static + FnMut(&f32) -> f32,
 {
     T::Device::foreach_m(t.mut_data(), &mut |x| *x = f(x)); // clones if there is more than 1 reference to t
     let (t, mut tape) = t.split_tape();
     let result = t.clone(); // will always a new reference to t, not start a new one
     let phantom_result = result.phantom();
-    tape.add_backward_op(move |grads| {
-        let (t_grad, result_grad) = grads.mut_and_ref(&t, &phantom_result);
-        T::Device::foreach_mrr(t_grad, t.data(), result_grad, &mut |g, fx, r| {
-            *g += df(fx) * r;
-        });
-    });
+    let graph_t = t.duplicate();
+    let graph_result = result.phantom();
+    let graph_df = df.clone();
+    tape.add_backward_op_with_graph(
+        move |grads| {
+            let (t_grad, result_grad) = grads.mut_and_ref_or_zeros(&t, &phantom_result);
+            T::Device::foreach_mrr(t_grad, t.data(), result_grad, &mut |g, fx, r| {
+                *g += df(fx) * r;
+            });
+        },
+        move |graph| map_graph_op(graph_t, graph_result, graph, graph_df, d2f),
+    );
     result.put_tape(tape)
 }
 
+/// The [GradientGraph] version of the backward op of [map()] and [map_df_uses_fx()].
+/// `t` has the id of the input, and holds the values that `df` and `d2f` are called with.
+fn map_graph_op<T, Df, D2f>(
+    t: T,
+    result: PhantomTensor<T>,
+    graph: &mut GradientGraph,
+    mut df: Df,
+    mut d2f: D2f,
+) where
+    T: 'static + Tensor<Dtype = f32>,
+    Df: FnMut(&f32) -> f32,
+    D2f: FnMut(&f32) -> f32,
+{
+    let t_slot = graph.slot(&t);
+    let result_slot = graph.slot(&result);
+
+    let mut deriv: Box<T::Array> = T::Device::map(t.data(), &mut df);
+    let mut d2f_grad: Box<T::Array> = T::Device::zeros();
+    let (t_grad, result_grad) = graph
+        .gradients()
+        .mut_and_ref_or_zeros(&t_slot, &result_slot);
+    T::Device::addmul(t_grad, deriv.as_ref(), result_grad);
+    T::Device::foreach_mrr(d2f_grad.as_mut(), t.data(), result_grad, &mut |d, x, r| {
+        *d = d2f(x) * r;
+    });
+
+    graph.add_backward_op(move |grads| {
+        let t_slot_grad = grads.clone_gradient(&t_slot);
+        T::Device::addmul(
+            grads.mut_gradient(&t),
+            d2f_grad.as_ref(),
+            t_slot_grad.as_ref(),
+        );
+        T::Device::foreach_mr(deriv.as_mut(), t_slot_grad.as_ref(), &mut |d, u| *d *= u);
+        T::Device::add(grads.mut_gradient(&result_slot), deriv.as_ref());
+    });
+}
+
 /// Applies a binary function `f`, it's partial wrt. x `dfdx`, and its partial wrt. y `dfdy`
 /// to a pair of [Tensor]s `lhs` and `rhs.
 ///
@@ -93,6 +152,151 @@ pub(crate) fn binary_map<
     })
 }
 
+/// Same as [binary_map()], but `lhs` and `rhs` can have different shapes that are
+/// broadcasted together with [BroadcastBinary]. The gradients are summed over the
+/// broadcasted axes.
+///
+/// `d2f` returns the second order partials `[d2f/dx2, d2f/dxdy, d2f/dy2]`, which are only
+/// used for higher order gradients (see [GradientGraph]).
+///
+/// This is primarily used to implement [add()], [sub()], [mul()], and [div()].
+pub(crate) fn broadcast_binary_map<L, R, F, Dfdx, Dfdy, D2f>(
+    lhs: L,
+    rhs: &R,
+    mut f: F,
+    mut dfdx: Dfdx,
+    mut dfdy: Dfdy,
+    mut d2f: D2f,
+) -> L::Output
+where
+    L: BroadcastBinary<R>,
+    R: 'static + Tensor<Dtype = f32>,
+    F: FnMut(&f32, &f32) -> f32,
+    Dfdx: 'static + Clone + FnMut(&f32, &f32) -> f32,
+    Dfdy: 'static + Clone + FnMut(&f32, &f32) -> f32,
+    D2f: 'static + FnMut(&f32, &f32) -> [f32; 3],
+{
+    type Arr<L, R> = <<L as BroadcastBinary<R>>::Output as HasArrayType>::Array;
+    type Dev<L, R> = <<L as BroadcastBinary<R>>::Output as HasDevice>::Device;
+
+    let mut graph_dfdx = dfdx.clone();
+    let mut graph_dfdy = dfdy.clone();
+    let graph_rhs = rhs.duplicate();
+
+    let mut result = <L::Output as Tensor>::NoTape::zeros();
+    let mut lhs_deriv: Box<Arr<L, R>> = Dev::<L, R>::zeros();
+    let mut rhs_deriv: Box<Arr<L, R>> = Dev::<L, R>::zeros();
+    L::broadcast_lhs(lhs.data(), lhs_deriv.as_mut());
+    L::broadcast_rhs(rhs.data(), rhs_deriv.as_mut());
+
+    // compute result & derivatives
+    Dev::<L, R>::foreach_mmm(
+        result.mut_data(),
+        lhs_deriv.as_mut(),
+        rhs_deriv.as_mut(),
+        &mut |o, l, r| {
+            *o = f(l, r);
+            let dx = dfdx(l, r);
+            *r = dfdy(l, r);
+            *l = dx;
+        },
+    );
+
+    move_tape_and_add_backward_binop_with_graph(
+        lhs,
+        rhs,
+        result,
+        move |lhs, rhs, result, grads| {
+            let mut grad: Box<Arr<L, R>> = Dev::<L, R>::zeros();
+
+            let (lhs_grad, result_grad) = grads.mut_and_ref_or_zeros(&lhs, &result);
+            Dev::<L, R>::addmul(grad.as_mut(), lhs_deriv.as_ref(), result_grad);
+            L::reduce_lhs(grad.as_ref(), lhs_grad);
+
+            let (rhs_grad, result_grad) = grads.mut_and_ref_or_zeros(&rhs, &result);
+            Dev::<L, R>::foreach_mrr(
+                grad.as_mut(),
+                rhs_deriv.as_ref(),
+                result_grad,
+                &mut |g, d, r| *g = d * r,
+            );
+            L::reduce_rhs(grad.as_ref(), rhs_grad);
+        },
+        move |lhs, rhs, result, graph| {
+            let lhs_slot = graph.slot(&lhs);
+            let rhs_slot = graph.slot(&rhs);
+            let result_slot = graph.slot(&result);
+
+            let mut l: Box<Arr<L, R>> = Dev::<L, R>::zeros();
+            let mut r: Box<Arr<L, R>> = Dev::<L, R>::zeros();
+            L::broadcast_lhs(lhs.data(), l.as_mut());
+            L::broadcast_rhs(graph_rhs.data(), r.as_mut());
+            let mut lhs_deriv: Box<Arr<L, R>> = Dev::<L, R>::zeros();
+            let mut rhs_deriv: Box<Arr<L, R>> = Dev::<L, R>::zeros();
+            Dev::<L, R>::foreach_mrr(
+                lhs_deriv.as_mut(),
+                l.as_ref(),
+                r.as_ref(),
+                &mut |d, l, r| {
+                    *d = graph_dfdx(l, r);
+                },
+            );
+            Dev::<L, R>::foreach_mrr(
+                rhs_deriv.as_mut(),
+                l.as_ref(),
+                r.as_ref(),
+                &mut |d, l, r| {
+                    *d = graph_dfdy(l, r);
+                },
+            );
+
+            let result_grad: Box<Arr<L, R>> = graph.gradients().clone_gradient(&result_slot);
+            let mut grad: Box<Arr<L, R>> = Dev::<L, R>::zeros();
+            Dev::<L, R>::addmul(grad.as_mut(), lhs_deriv.as_ref(), result_grad.as_ref());
+            L::reduce_lhs(grad.as_ref(), graph.gradients().mut_gradient(&lhs_slot));
+            Dev::<L, R>::foreach_mrr(
+                grad.as_mut(),
+                rhs_deriv.as_ref(),
+                result_grad.as_ref(),
+                &mut |g, d, r| *g = d * r,
+            );
+            L::reduce_rhs(grad.as_ref(), graph.gradients().mut_gradient(&rhs_slot));
+
+            graph.add_backward_op(move |grads| {
+                let mut u: Box<Arr<L, R>> = Dev::<L, R>::zeros();
+                let mut v: Box<Arr<L, R>> = Dev::<L, R>::zeros();
+                L::broadcast_lhs(grads.clone_gradient(&lhs_slot).as_ref(), u.as_mut());
+                L::broadcast_rhs(grads.clone_gradient(&rhs_slot).as_ref(), v.as_mut());
+
+                let mut result_slot_grad: Box<Arr<L, R>> = Dev::<L, R>::zeros();
+                let mut lhs_grad: Box<Arr<L, R>> = Dev::<L, R>::zeros();
+                let mut rhs_grad: Box<Arr<L, R>> = Dev::<L, R>::zeros();
+                let values = as_slice(l.as_ref())
+                    .iter()
+                    .zip(as_slice(r.as_ref()).iter())
+                    .zip(as_slice(lhs_deriv.as_ref()).iter())
+                    .zip(as_slice(rhs_deriv.as_ref()).iter())
+                    .zip(as_slice(result_grad.as_ref()).iter())
+                    .zip(as_slice(u.as_ref()).iter().zip(as_slice(v.as_ref()).iter()));
+                let grads_out = as_mut_slice(result_slot_grad.as_mut())
+                    .iter_mut()
+                    .zip(as_mut_slice(lhs_grad.as_mut()).iter_mut())
+                    .zip(as_mut_slice(rhs_grad.as_mut()).iter_mut());
+                for (((o, gl), gr), (((((l, r), dx), dy), g), (u, v))) in grads_out.zip(values) {
+                    let [dxx, dxy, dyy] = d2f(l, r);
+                    *o = dx * u + dy * v;
+                    *gl = g * (dxx * u + dxy * v);
+                    *gr = g * (dxy * u + dyy * v);
+                }
+
+                Dev::<L, R>::add(grads.mut_gradient(&result_slot), result_slot_grad.as_ref());
+                L::reduce_lhs(lhs_grad.as_ref(), grads.mut_gradient(&lhs));
+                L::reduce_rhs(rhs_grad.as_ref(), grads.mut_gradient(&rhs));
+            });
+        },
+    )
+}
+
 /// Moves tape from `inp` to `out`, and does `tape.add_backward_op()` with `f`
 pub(super) fn move_tape_and_add_backward_op<Inp, Out, F>(
     inp: Inp,
@@ -119,13 +323,71 @@ pub(super) fn move_tape_and_add_backward_binop<Lhs, Rhs, Out, F>(
 ) -> Out
 where
     Lhs: Tensor,
-    Rhs: 'static + Tensor,
+    Rhs: 'static + Tensor<Dtype = f32>,
     Out: Tensor<Tape = Lhs::Tape>,
     F: 'static + FnMut(Lhs::NoTape, PhantomTensor<Rhs>, PhantomTensor<Out::NoTape>, &mut Gradients),
 {
     let phantom_rhs = rhs.phantom();
     let phantom_out = out.phantom();
     let (lhs, mut tape) = lhs.split_tape();
+    tape.add_input(rhs);
     tape.add_backward_op(move |grads| f(lhs, phantom_rhs, phantom_out, grads));
     out.put_tape(tape)
 }
+
+/// Same as [move_tape_and_add_backward_op()], but also adds `g`, the version of `f` that acts
+/// on a [GradientGraph]. See [crate::gradients::GradientTape::add_backward_op_with_graph()].
+pub(super) fn move_tape_and_add_backward_op_with_graph<Inp, Out, F, G>(
+    inp: Inp,
+    out: Out::NoTape,
+    f: F,
+    g: G,
+) -> Out
+where
+    Inp: Tensor,
+    Out: Tensor<Tape = Inp::Tape>,
+    F: 'static + FnOnce(Inp::NoTape, PhantomTensor<Out::NoTape>, &mut Gradients),
+    G: 'static + FnOnce(Inp::NoTape, PhantomTensor<Out::NoTape>, &mut GradientGraph),
+{
+    let phantom_out = out.phantom();
+    let graph_out = out.phantom();
+    let (t, mut tape) = inp.split_tape();
+    let graph_t = t.duplicate();
+    tape.add_backward_op_with_graph(
+        move |grads| f(t, phantom_out, grads),
+        move |graph| g(graph_t, graph_out, graph),
+    );
+    out.put_tape(tape)
+}
+
+/// Same as [move_tape_and_add_backward_binop()], but also adds `g`, the version of `f` that acts
+/// on a [GradientGraph]. See [crate::gradients::GradientTape::add_backward_op_with_graph()].
+pub(super) fn move_tape_and_add_backward_binop_with_graph<Lhs, Rhs, Out, F, G>(
+    lhs: Lhs,
+    rhs: &Rhs,
+    out: Out::NoTape,
+    f: F,
+    g: G,
+) -> Out
+where
+    Lhs: Tensor,
+    Rhs: 'static + Tensor<Dtype = f32>,
+    Out: Tensor<Tape = Lhs::Tape>,
+    F: 'static
+        + FnOnce(Lhs::NoTape, PhantomTensor<Rhs>, PhantomTensor<Out::NoTape>, &mut Gradients),
+    G: 'static
+        + FnOnce(Lhs::NoTape, PhantomTensor<Rhs>, PhantomTensor<Out::NoTape>, &mut GradientGraph),
+{
+    let phantom_rhs = rhs.phantom();
+    let phantom_out = out.phantom();
+    let graph_rhs = rhs.phantom();
+    let graph_out = out.phantom();
+    let (lhs, mut tape) = lhs.split_tape();
+    tape.add_input(rhs);
+    let graph_lhs = lhs.duplicate();
+    tape.add_backward_op_with_graph(
+        move |grads| f(lhs, phantom_rhs, phantom_out, grads),
+        move |graph| g(graph_lhs, graph_rhs, graph_out, graph),
+    );
+    out.put_tape(tape)
+}